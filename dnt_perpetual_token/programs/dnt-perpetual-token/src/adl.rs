@@ -0,0 +1,219 @@
+// -----------------------------------------------------------------------------
+// Auto-deleveraging (ADL): once the protocol's first-loss capital is used up, profitable
+// positions are force-reduced in order of their PnL/leverage score instead of letting losses
+// reach stakers. Scores are stored on each `Position` and ranked per side in an `AdlLadder`.
+// -----------------------------------------------------------------------------
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    book_position_payment, get_mark_price, next_event_seq, position_pnl, settle_position_funding,
+    CustomError, PnlBucket, Position, PositionActionKind, PositionAutoDeleveraged, State,
+    StrategyKind, BPS_DENOMINATOR, RESERVED_SPACE,
+};
+
+/// Number of ranked positions kept per ladder.
+pub const ADL_LADDER_LEN: usize = 32;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct AdlEntry {
+    pub position: Pubkey,
+    pub is_long: bool,
+    pub score: u64,
+}
+
+/// Highest-scoring positions first; entries are refreshed by `update_adl_rank`.
+#[account]
+#[derive(InitSpace)]
+pub struct AdlLadder {
+    pub bump: u8,
+    pub state: Pubkey,
+    #[max_len(ADL_LADDER_LEN)]
+    pub entries: Vec<AdlEntry>,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+impl AdlLadder {
+    /// Re-ranks `position` with `score`. Unprofitable positions drop off the ladder, and a full
+    /// ladder only admits scores above its lowest entry.
+    pub fn upsert(&mut self, position: Pubkey, is_long: bool, score: u64) {
+        self.entries.retain(|entry| entry.position != position);
+        if score == 0 {
+            return;
+        }
+        let at = self.entries.partition_point(|entry| entry.score >= score);
+        if at >= ADL_LADDER_LEN {
+            return;
+        }
+        self.entries.insert(at, AdlEntry { position, is_long, score });
+        self.entries.truncate(ADL_LADDER_LEN);
+    }
+
+    /// Highest-ranked position on the given side.
+    pub fn head(&self, is_long: bool) -> Option<Pubkey> {
+        self.entries
+            .iter()
+            .find(|entry| entry.is_long == is_long)
+            .map(|entry| entry.position)
+    }
+}
+
+/// ADL score: PnL as a fraction of collateral times leverage, in basis points. Zero for
+/// unprofitable or empty positions.
+pub fn adl_score(position: &Position, mark_price: u64) -> Result<u64> {
+    let pnl = position_pnl(position, mark_price)?;
+    if pnl <= 0 || position.collateral == 0 {
+        return Ok(0);
+    }
+    let collateral = position.collateral as u128;
+    let score = (pnl as u128)
+        .saturating_mul(position.size.unsigned_abs() as u128)
+        .saturating_mul(BPS_DENOMINATOR as u128)
+        / collateral
+        / collateral;
+    Ok(score.min(u64::MAX as u128) as u64)
+}
+
+pub fn initialize(ctx: Context<InitializeAdlLadder>) -> Result<()> {
+    let ladder = &mut ctx.accounts.ladder;
+    ladder.bump = ctx.bumps.ladder;
+    ladder.state = ctx.accounts.state.key();
+    ladder.entries = Vec::new();
+    Ok(())
+}
+
+pub fn update_rank(ctx: Context<UpdateAdlRank>) -> Result<()> {
+    let position = &mut ctx.accounts.position;
+    position.adl_score = adl_score(position, get_mark_price()?)?;
+    ctx.accounts
+        .ladder
+        .upsert(position.key(), position.size > 0, position.adl_score);
+    Ok(())
+}
+
+pub fn auto_deleverage(ctx: Context<AutoDeleverage>, amount: u64) -> Result<()> {
+    require!(
+        ctx.accounts.state.protocol_equity() == 0,
+        CustomError::InsuranceNotExhausted
+    );
+    let funding = settle_position_funding(&mut ctx.accounts.state, &mut ctx.accounts.position)?;
+    ctx.accounts.pnl_bucket.record_funding(funding)?;
+
+    let position = &mut ctx.accounts.position;
+    let is_long = position.size > 0;
+    require!(
+        ctx.accounts.ladder.head(is_long) == Some(position.key()),
+        CustomError::NotAdlCandidate
+    );
+    let mark_price = get_mark_price()?;
+    require!(adl_score(position, mark_price)? > 0, CustomError::NotAdlCandidate);
+
+    let abs_size = position.size.unsigned_abs();
+    let amount = amount.min(abs_size);
+    require!(amount > 0, CustomError::InvalidParameter);
+    // Close at the mark: the trader's PnL on the reduced size is settled into collateral.
+    let closed_pnl = position_pnl(position, mark_price)?
+        .checked_mul(amount as i128)
+        .ok_or(CustomError::MathOverflow)?
+        / abs_size as i128;
+    book_position_payment(&mut ctx.accounts.state, position, -closed_pnl)?;
+    let remaining = (abs_size - amount) as i64;
+    position.size = if is_long { remaining } else { -remaining };
+    position.refresh_liquidation_price(ctx.accounts.state.max_allowed_loss_bps);
+    position.record(PositionActionKind::AutoDeleverage, amount as i64)?;
+    position.adl_score = adl_score(position, mark_price)?;
+    ctx.accounts
+        .ladder
+        .upsert(position.key(), is_long, position.adl_score);
+
+    let seq = next_event_seq(&mut ctx.accounts.state)?;
+    emit!(PositionAutoDeleveraged {
+        seq,
+        state: ctx.accounts.state.key(),
+        owner: ctx.accounts.position.owner,
+        size_closed: amount,
+        realized_pnl: i64::try_from(closed_pnl).map_err(|_| CustomError::MathOverflow)?,
+        size: ctx.accounts.position.size,
+        liquidation_price: ctx.accounts.position.liquidation_price,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeAdlLadder<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        seeds = [b"adl_ladder", state.key().as_ref()],
+        bump,
+        payer = state_owner,
+        space = 8 + AdlLadder::INIT_SPACE,
+    )]
+    pub ladder: Account<'info, AdlLadder>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAdlRank<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"adl_ladder", state.key().as_ref()],
+        bump = ladder.bump,
+    )]
+    pub ladder: Account<'info, AdlLadder>,
+    #[account(
+        mut,
+        seeds = [b"position", state.key().as_ref(), position.owner.as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+}
+
+#[derive(Accounts)]
+pub struct AutoDeleverage<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"adl_ladder", state.key().as_ref()],
+        bump = ladder.bump,
+    )]
+    pub ladder: Account<'info, AdlLadder>,
+    #[account(
+        mut,
+        seeds = [b"position", state.key().as_ref(), position.owner.as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        seeds = [b"pnl_bucket", state.key().as_ref(), &[StrategyKind::Hedge as u8]],
+        bump = pnl_bucket.bump,
+    )]
+    pub pnl_bucket: Account<'info, PnlBucket>,
+    #[account(constraint = keeper.key() == state.keeper @ CustomError::Unauthorized)]
+    pub keeper: Signer<'info>,
+}
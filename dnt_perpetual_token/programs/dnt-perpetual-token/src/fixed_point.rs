@@ -0,0 +1,192 @@
+// -----------------------------------------------------------------------------
+// Fixed-point math: Q64.64 values stored in a u128 and a 256-bit `mul_div` with explicit
+// rounding. The NAV share conversions used by compounding and index settlement round down, so
+// they never credit more shares or report more value than the exact pro-rata amount; deposits
+// and withdrawals themselves are 1:1 in principal and do not go through them. The reward index
+// is Q64.64 and accruals round down, so the index never pays out more than was distributed.
+// -----------------------------------------------------------------------------
+
+/// 1.0 in Q64.64.
+pub const Q64_ONE: u128 = 1 << 64;
+
+const LOW_MASK: u128 = u64::MAX as u128;
+
+/// Which way `mul_div` rounds an inexact quotient.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rounding {
+    /// Toward zero; the default in the vault's favor when paying out.
+    Down,
+    /// Away from zero; for amounts the vault collects.
+    Up,
+}
+
+/// `a * b / denominator` with a 256-bit intermediate product. `None` when `denominator` is zero
+/// or the quotient does not fit in a u128.
+pub fn mul_div(a: u128, b: u128, denominator: u128, rounding: Rounding) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+    let (high, low) = full_mul(a, b);
+    if high >= denominator {
+        return None;
+    }
+    let (quotient, remainder) = div_wide(high, low, denominator);
+    match rounding {
+        Rounding::Up if remainder > 0 => quotient.checked_add(1),
+        _ => Some(quotient),
+    }
+}
+
+/// `mul_div` over u64 operands, narrowed back to a u64.
+pub fn mul_div_u64(a: u64, b: u64, denominator: u64, rounding: Rounding) -> Option<u64> {
+    mul_div(a as u128, b as u128, denominator as u128, rounding)
+        .and_then(|v| u64::try_from(v).ok())
+}
+
+/// `numerator / denominator` as a Q64.64 value.
+pub fn q64_from_ratio(numerator: u64, denominator: u64, rounding: Rounding) -> Option<u128> {
+    mul_div(numerator as u128, Q64_ONE, denominator as u128, rounding)
+}
+
+/// `amount * q` for a Q64.64 `q`, as an integer.
+pub fn q64_mul(q: u128, amount: u64, rounding: Rounding) -> Option<u64> {
+    mul_div(q, amount as u128, Q64_ONE, rounding).and_then(|v| u64::try_from(v).ok())
+}
+
+/// Full 256-bit product of `a` and `b`, as (high, low) halves.
+fn full_mul(a: u128, b: u128) -> (u128, u128) {
+    let (a_hi, a_lo) = (a >> 64, a & LOW_MASK);
+    let (b_hi, b_lo) = (b >> 64, b & LOW_MASK);
+    let lo_lo = a_lo * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_lo = a_hi * b_lo;
+    let hi_hi = a_hi * b_hi;
+    let mid = (lo_lo >> 64) + (lo_hi & LOW_MASK) + (hi_lo & LOW_MASK);
+    let low = (lo_lo & LOW_MASK) | (mid << 64);
+    let high = hi_hi + (lo_hi >> 64) + (hi_lo >> 64) + (mid >> 64);
+    (high, low)
+}
+
+/// Divides the 256-bit value (high, low) by `d`, returning (quotient, remainder). Requires
+/// `high < d`, so the quotient fits in a u128.
+fn div_wide(high: u128, low: u128, d: u128) -> (u128, u128) {
+    let mut remainder = high;
+    let mut quotient = 0u128;
+    for i in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> i) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= d {
+            remainder = remainder.wrapping_sub(d);
+            quotient |= 1;
+        }
+    }
+    (quotient, remainder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic xorshift generator, so failures reproduce.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        /// Values spread across magnitudes rather than clustered near u64::MAX.
+        fn amount(&mut self) -> u64 {
+            let bits = self.next() % 64 + 1;
+            self.next() >> (64 - bits)
+        }
+    }
+
+    const CASES: usize = 20_000;
+
+    #[test]
+    fn mul_div_matches_exact_u128_math() {
+        let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+        for _ in 0..CASES {
+            let (a, b, d) = (rng.amount(), rng.amount(), rng.amount().max(1));
+            let exact = a as u128 * b as u128;
+            let down = mul_div(a as u128, b as u128, d as u128, Rounding::Down).unwrap();
+            let up = mul_div(a as u128, b as u128, d as u128, Rounding::Up).unwrap();
+            assert_eq!(down, exact / d as u128);
+            assert_eq!(up, exact.div_ceil(d as u128));
+        }
+    }
+
+    #[test]
+    fn mul_div_handles_wide_products() {
+        let mut rng = Rng(0xD1B5_4A32_D192_ED03);
+        for _ in 0..CASES {
+            let a = (rng.next() as u128) << 64 | rng.next() as u128;
+            let b = rng.amount() as u128;
+            // a * b / b is exact whenever the quotient fits.
+            assert_eq!(mul_div(a, b.max(1), b.max(1), Rounding::Down), Some(a));
+            assert_eq!(mul_div(a, b.max(1), b.max(1), Rounding::Up), Some(a));
+        }
+        assert_eq!(mul_div(u128::MAX, 2, 1, Rounding::Down), None);
+        assert_eq!(mul_div(1, 1, 0, Rounding::Down), None);
+    }
+
+    #[test]
+    fn deposit_then_withdraw_never_gains() {
+        let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+        for _ in 0..CASES {
+            let total_shares = rng.amount().max(1);
+            let nav = rng.amount().max(1);
+            let deposit = rng.amount();
+            let Some(shares) = mul_div_u64(deposit, total_shares, nav, Rounding::Down) else {
+                continue;
+            };
+            let (Some(new_shares), Some(new_nav)) =
+                (total_shares.checked_add(shares), nav.checked_add(deposit))
+            else {
+                continue;
+            };
+            let redeemed = mul_div_u64(shares, new_nav, new_shares, Rounding::Down).unwrap();
+            assert!(redeemed <= deposit, "deposit {deposit} redeemed {redeemed}");
+        }
+    }
+
+    #[test]
+    fn withdrawal_never_exceeds_pro_rata_value() {
+        let mut rng = Rng(0xBF58_476D_1CE4_E5B9);
+        for _ in 0..CASES {
+            let total_shares = rng.amount().max(1);
+            let nav = rng.amount();
+            let shares = rng.amount() % total_shares + 1;
+            let value = mul_div_u64(shares, nav, total_shares, Rounding::Down).unwrap();
+            assert!(value as u128 * total_shares as u128 <= shares as u128 * nav as u128);
+            // Whatever is left still backs the remaining shares at no worse a price.
+            let rest_value = nav - value;
+            let rest_shares = total_shares - shares;
+            assert!(rest_value as u128 * total_shares as u128 >= rest_shares as u128 * nav as u128);
+        }
+    }
+
+    #[test]
+    fn reward_index_never_overpays() {
+        let mut rng = Rng(0x94D0_49BB_1331_11EB);
+        for _ in 0..CASES / 10 {
+            let stakes: Vec<u64> = (0..8).map(|_| rng.amount() >> 8).collect();
+            let total: u64 = stakes.iter().sum();
+            if total == 0 {
+                continue;
+            }
+            let rewards = rng.amount();
+            let index = q64_from_ratio(rewards, total, Rounding::Down).unwrap();
+            let paid: u128 = stakes
+                .iter()
+                .map(|&s| q64_mul(index, s, Rounding::Down).unwrap() as u128)
+                .sum();
+            assert!(paid <= rewards as u128, "rewards {rewards} paid {paid}");
+        }
+    }
+}
@@ -0,0 +1,207 @@
+// -----------------------------------------------------------------------------
+// Funding publishers: an allowlist of keepers each post their view of the perp funding rate.
+// The rate fed into the funding index and TWAP is the median of the fresh submissions, and only
+// once at least `quorum` of them are within `max_age` seconds, so one rogue or stale publisher
+// cannot move it.
+// -----------------------------------------------------------------------------
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    next_event_seq, CustomError, FundingPublisherUpdated, FundingRateSubmitted, State,
+    RESERVED_SPACE,
+};
+
+/// Most publishers a vault's allowlist can hold.
+pub const MAX_FUNDING_PUBLISHERS: usize = 8;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct FundingPublisher {
+    pub key: Pubkey,
+    /// Latest submitted rate and when it was submitted; zero before the first submission.
+    pub rate: u64,
+    pub submitted_at: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct FundingPublishers {
+    pub bump: u8,
+    pub state: Pubkey,
+    /// Fresh submissions needed before a funding rate can be used.
+    pub quorum: u8,
+    /// Age in seconds after which a submission no longer counts.
+    pub max_age: i64,
+    #[max_len(MAX_FUNDING_PUBLISHERS)]
+    pub publishers: Vec<FundingPublisher>,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+impl FundingPublishers {
+    /// Median of the submissions made within `max_age` of `now`. Fails below quorum.
+    pub fn median_rate(&self, now: i64) -> Result<u64> {
+        let mut rates: Vec<u64> = self
+            .publishers
+            .iter()
+            .filter(|p| p.submitted_at > 0 && now.saturating_sub(p.submitted_at) <= self.max_age)
+            .map(|p| p.rate)
+            .collect();
+        require!(
+            self.quorum > 0 && rates.len() >= self.quorum as usize,
+            CustomError::FundingQuorumNotMet
+        );
+        rates.sort_unstable();
+        let mid = rates.len() / 2;
+        if rates.len() % 2 == 1 {
+            Ok(rates[mid])
+        } else {
+            Ok(((rates[mid - 1] as u128 + rates[mid] as u128) / 2) as u64)
+        }
+    }
+}
+
+fn validate_quorum(quorum: u8, max_age: i64) -> Result<()> {
+    require!(
+        quorum > 0 && quorum as usize <= MAX_FUNDING_PUBLISHERS && max_age > 0,
+        CustomError::InvalidParameter
+    );
+    Ok(())
+}
+
+pub fn initialize(ctx: Context<InitializeFundingPublishers>, quorum: u8, max_age: i64) -> Result<()> {
+    validate_quorum(quorum, max_age)?;
+    let registry = &mut ctx.accounts.funding_publishers;
+    registry.bump = ctx.bumps.funding_publishers;
+    registry.state = ctx.accounts.state.key();
+    registry.quorum = quorum;
+    registry.max_age = max_age;
+    Ok(())
+}
+
+pub fn set_quorum(ctx: Context<ManageFundingPublishers>, quorum: u8, max_age: i64) -> Result<()> {
+    validate_quorum(quorum, max_age)?;
+    let registry = &mut ctx.accounts.funding_publishers;
+    registry.quorum = quorum;
+    registry.max_age = max_age;
+    Ok(())
+}
+
+pub fn add_publisher(ctx: Context<ManageFundingPublishers>, publisher: Pubkey) -> Result<()> {
+    let registry = &mut ctx.accounts.funding_publishers;
+    require!(
+        !registry.publishers.iter().any(|p| p.key == publisher),
+        CustomError::InvalidParameter
+    );
+    require!(
+        registry.publishers.len() < MAX_FUNDING_PUBLISHERS,
+        CustomError::FundingPublishersFull
+    );
+    registry.publishers.push(FundingPublisher { key: publisher, rate: 0, submitted_at: 0 });
+    let seq = next_event_seq(&mut ctx.accounts.state)?;
+    emit!(FundingPublisherUpdated {
+        seq,
+        state: ctx.accounts.state.key(),
+        publisher,
+        listed: true,
+    });
+    Ok(())
+}
+
+pub fn remove_publisher(ctx: Context<ManageFundingPublishers>, publisher: Pubkey) -> Result<()> {
+    let registry = &mut ctx.accounts.funding_publishers;
+    let index = registry
+        .publishers
+        .iter()
+        .position(|p| p.key == publisher)
+        .ok_or(CustomError::Unauthorized)?;
+    registry.publishers.swap_remove(index);
+    let seq = next_event_seq(&mut ctx.accounts.state)?;
+    emit!(FundingPublisherUpdated {
+        seq,
+        state: ctx.accounts.state.key(),
+        publisher,
+        listed: false,
+    });
+    Ok(())
+}
+
+pub fn submit(ctx: Context<SubmitFundingRate>, rate: u64) -> Result<()> {
+    let publisher = ctx.accounts.publisher.key();
+    let entry = ctx
+        .accounts
+        .funding_publishers
+        .publishers
+        .iter_mut()
+        .find(|p| p.key == publisher)
+        .ok_or(CustomError::Unauthorized)?;
+    entry.rate = rate;
+    entry.submitted_at = Clock::get()?.unix_timestamp;
+    let seq = next_event_seq(&mut ctx.accounts.state)?;
+    emit!(FundingRateSubmitted {
+        seq,
+        state: ctx.accounts.state.key(),
+        publisher,
+        rate,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeFundingPublishers<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        seeds = [b"funding_publishers", state.key().as_ref()],
+        bump,
+        payer = state_owner,
+        space = 8 + FundingPublishers::INIT_SPACE,
+    )]
+    pub funding_publishers: Account<'info, FundingPublishers>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageFundingPublishers<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        mut,
+        seeds = [b"funding_publishers", state.key().as_ref()],
+        bump = funding_publishers.bump,
+    )]
+    pub funding_publishers: Account<'info, FundingPublishers>,
+    pub state_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitFundingRate<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"funding_publishers", state.key().as_ref()],
+        bump = funding_publishers.bump,
+    )]
+    pub funding_publishers: Account<'info, FundingPublishers>,
+    pub publisher: Signer<'info>,
+}
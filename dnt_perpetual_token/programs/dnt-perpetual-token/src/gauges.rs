@@ -0,0 +1,447 @@
+// -----------------------------------------------------------------------------
+// Gauges: a `GaugeController` splits a weekly $DNT emission across vaults, Curve-style. Each
+// vault has a `Gauge`, and vote-escrow holders spread their lock's power across gauges with
+// `vote_gauge_weight`. Votes cast during a week count from the next one: the first touch of a
+// gauge or the controller in a new week freezes the weights it had at the week boundary, and
+// `apply_gauge_rate` sets the vault's per-second reward rate from its frozen share. An
+// allocation is priced at the lock's power when cast; `kick_gauge_voter` lets anyone re-price it
+// as the lock decays, and clears it once the lock has expired or been unlocked.
+// -----------------------------------------------------------------------------
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    checkpoint_rewards, next_event_seq, CustomError, GaugeRateApplied, GaugeVoteKicked,
+    GaugeVoted, State, VoteEscrow, BPS_DENOMINATOR, RESERVED_SPACE, VOTE_ESCROW_MAX_LOCK_SECS,
+};
+
+/// Length of a gauge epoch.
+pub const GAUGE_EPOCH_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Gauges one vote-escrow lock can split its power across.
+pub const MAX_GAUGE_VOTES: usize = 8;
+
+/// Splits `weekly_emission` across registered gauges, at `[b"gauge_controller", authority]`.
+#[account]
+#[derive(InitSpace)]
+pub struct GaugeController {
+    pub bump: u8,
+    /// Governance authority that sets the emission and adds gauges.
+    pub authority: Pubkey,
+    /// $DNT emitted across all gauges per epoch.
+    pub weekly_emission: u64,
+    /// Sum of all gauges' live weights.
+    pub total_weight: u64,
+    /// `total_weight` at the start of `week`, which prices this epoch's rates.
+    pub applied_total_weight: u64,
+    pub week: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+/// A vault's share of the controller's emission, at `[b"gauge", controller, state]`.
+#[account]
+#[derive(InitSpace)]
+pub struct Gauge {
+    pub bump: u8,
+    pub controller: Pubkey,
+    pub state: Pubkey,
+    /// Votes currently allocated to this gauge.
+    pub weight: u64,
+    /// `weight` at the start of `week`.
+    pub applied_weight: u64,
+    pub week: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct GaugeAllocation {
+    pub gauge: Pubkey,
+    pub weight_bps: u64,
+    /// Votes this allocation contributes to the gauge's weight.
+    pub weight: u64,
+}
+
+/// How one vote-escrow lock's power is split, at `[b"gauge_voter", controller, vote_escrow]`.
+#[account]
+#[derive(InitSpace)]
+pub struct GaugeVoter {
+    pub bump: u8,
+    pub vote_escrow: Pubkey,
+    /// Unused slots have the default gauge.
+    pub allocations: [GaugeAllocation; MAX_GAUGE_VOTES],
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+fn current_week(now: i64) -> i64 {
+    now / GAUGE_EPOCH_SECS
+}
+
+impl GaugeController {
+    /// Freezes the total weight for a new epoch. Must run before `total_weight` changes in it.
+    fn roll(&mut self, week: i64) {
+        if self.week < week {
+            self.applied_total_weight = self.total_weight;
+            self.week = week;
+        }
+    }
+}
+
+impl Gauge {
+    /// Freezes the weight for a new epoch. Must run before `weight` changes in it.
+    fn roll(&mut self, week: i64) {
+        if self.week < week {
+            self.applied_weight = self.weight;
+            self.week = week;
+        }
+    }
+}
+
+/// Gauge voting power of `escrow` at `now`: its locked shares, decaying linearly to zero at
+/// unlock.
+fn gauge_power(escrow: &VoteEscrow, now: i64) -> Result<u64> {
+    let remaining = escrow.unlock_at.saturating_sub(now).clamp(0, VOTE_ESCROW_MAX_LOCK_SECS);
+    let power = escrow.amount as u128 * remaining as u128 / VOTE_ESCROW_MAX_LOCK_SECS as u128;
+    u64::try_from(power).map_err(|_| error!(CustomError::MathOverflow))
+}
+
+pub fn initialize_controller(
+    ctx: Context<InitializeGaugeController>,
+    weekly_emission: u64,
+) -> Result<()> {
+    let controller = &mut ctx.accounts.gauge_controller;
+    controller.bump = ctx.bumps.gauge_controller;
+    controller.authority = ctx.accounts.authority.key();
+    controller.weekly_emission = weekly_emission;
+    controller.week = current_week(Clock::get()?.unix_timestamp);
+    Ok(())
+}
+
+pub fn set_emission(ctx: Context<SetGaugeEmission>, weekly_emission: u64) -> Result<()> {
+    ctx.accounts.gauge_controller.weekly_emission = weekly_emission;
+    Ok(())
+}
+
+pub fn add_gauge(ctx: Context<AddGauge>) -> Result<()> {
+    let gauge = &mut ctx.accounts.gauge;
+    gauge.bump = ctx.bumps.gauge;
+    gauge.controller = ctx.accounts.gauge_controller.key();
+    gauge.state = ctx.accounts.state.key();
+    gauge.week = current_week(Clock::get()?.unix_timestamp);
+    Ok(())
+}
+
+pub fn register_voter(ctx: Context<RegisterGaugeVoter>) -> Result<()> {
+    let voter = &mut ctx.accounts.gauge_voter;
+    voter.bump = ctx.bumps.gauge_voter;
+    voter.vote_escrow = ctx.accounts.vote_escrow.key();
+    Ok(())
+}
+
+pub fn vote(ctx: Context<VoteGaugeWeight>, weight_bps: u64) -> Result<()> {
+    require!(weight_bps <= BPS_DENOMINATOR, CustomError::InvalidParameter);
+    let now = Clock::get()?.unix_timestamp;
+    let week = current_week(now);
+    let controller = &mut ctx.accounts.gauge_controller;
+    let gauge = &mut ctx.accounts.gauge;
+    controller.roll(week);
+    gauge.roll(week);
+
+    let power = gauge_power(&ctx.accounts.vote_escrow, now)?;
+    require!(weight_bps == 0 || power > 0, CustomError::LockExpired);
+    let weight = u64::try_from(power as u128 * weight_bps as u128 / BPS_DENOMINATOR as u128)
+        .map_err(|_| CustomError::MathOverflow)?;
+
+    let gauge_key = gauge.key();
+    let allocations = &mut ctx.accounts.gauge_voter.allocations;
+    let slot = match allocations.iter().position(|a| a.gauge == gauge_key) {
+        Some(i) => i,
+        None => allocations
+            .iter()
+            .position(|a| a.weight_bps == 0)
+            .ok_or(CustomError::GaugeSlotsFull)?,
+    };
+    let previous = allocations[slot].weight;
+    allocations[slot] = if weight_bps == 0 {
+        GaugeAllocation::default()
+    } else {
+        GaugeAllocation { gauge: gauge_key, weight_bps, weight }
+    };
+    let used_bps: u64 = allocations.iter().map(|a| a.weight_bps).sum();
+    require!(used_bps <= BPS_DENOMINATOR, CustomError::InvalidParameter);
+
+    gauge.weight = gauge
+        .weight
+        .checked_sub(previous)
+        .and_then(|w| w.checked_add(weight))
+        .ok_or(CustomError::MathOverflow)?;
+    controller.total_weight = controller
+        .total_weight
+        .checked_sub(previous)
+        .and_then(|w| w.checked_add(weight))
+        .ok_or(CustomError::MathOverflow)?;
+
+    let seq = next_event_seq(&mut ctx.accounts.state)?;
+    emit!(GaugeVoted {
+        seq,
+        state: ctx.accounts.state.key(),
+        gauge: gauge_key,
+        voter: ctx.accounts.voter.key(),
+        weight_bps,
+        weight,
+        gauge_weight: ctx.accounts.gauge.weight,
+    });
+    Ok(())
+}
+
+pub fn kick(ctx: Context<KickGaugeVoter>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let week = current_week(now);
+    let controller = &mut ctx.accounts.gauge_controller;
+    let gauge = &mut ctx.accounts.gauge;
+    controller.roll(week);
+    gauge.roll(week);
+
+    // An unlocked escrow has been closed and has no power left.
+    let escrow_info = ctx.accounts.vote_escrow.to_account_info();
+    let power = if escrow_info.owner == &crate::ID && !escrow_info.data_is_empty() {
+        let escrow = VoteEscrow::try_deserialize(&mut &escrow_info.try_borrow_data()?[..])?;
+        gauge_power(&escrow, now)?
+    } else {
+        0
+    };
+
+    let gauge_key = gauge.key();
+    let allocation = ctx
+        .accounts
+        .gauge_voter
+        .allocations
+        .iter_mut()
+        .find(|a| a.weight_bps > 0 && a.gauge == gauge_key)
+        .ok_or(CustomError::InvalidParameter)?;
+    let previous = allocation.weight;
+    let weight =
+        u64::try_from(power as u128 * allocation.weight_bps as u128 / BPS_DENOMINATOR as u128)
+            .map_err(|_| CustomError::MathOverflow)?;
+    if power == 0 {
+        *allocation = GaugeAllocation::default();
+    } else {
+        allocation.weight = weight;
+    }
+
+    gauge.weight = gauge
+        .weight
+        .checked_sub(previous)
+        .and_then(|w| w.checked_add(weight))
+        .ok_or(CustomError::MathOverflow)?;
+    controller.total_weight = controller
+        .total_weight
+        .checked_sub(previous)
+        .and_then(|w| w.checked_add(weight))
+        .ok_or(CustomError::MathOverflow)?;
+
+    let seq = next_event_seq(&mut ctx.accounts.state)?;
+    emit!(GaugeVoteKicked {
+        seq,
+        state: ctx.accounts.state.key(),
+        gauge: gauge_key,
+        vote_escrow: ctx.accounts.vote_escrow.key(),
+        previous_weight: previous,
+        weight,
+        gauge_weight: ctx.accounts.gauge.weight,
+    });
+    Ok(())
+}
+
+pub fn apply_rate(ctx: Context<ApplyGaugeRate>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let week = current_week(now);
+    let controller = &mut ctx.accounts.gauge_controller;
+    let gauge = &mut ctx.accounts.gauge;
+    controller.roll(week);
+    gauge.roll(week);
+    let weekly = if controller.applied_total_weight == 0 {
+        0
+    } else {
+        controller.weekly_emission as u128 * gauge.applied_weight as u128
+            / controller.applied_total_weight as u128
+    };
+    let reward_per_second = u64::try_from(weekly / GAUGE_EPOCH_SECS as u128)
+        .map_err(|_| CustomError::MathOverflow)?;
+
+    // Accrue at the old rate up to now, so the new one only applies from here on.
+    let state = &mut ctx.accounts.state;
+    checkpoint_rewards(state, now)?;
+    state.reward_per_second = reward_per_second;
+
+    let seq = next_event_seq(state)?;
+    emit!(GaugeRateApplied {
+        seq,
+        state: state.key(),
+        week,
+        gauge_weight: ctx.accounts.gauge.applied_weight,
+        total_weight: ctx.accounts.gauge_controller.applied_total_weight,
+        reward_per_second,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeGaugeController<'info> {
+    #[account(
+        init,
+        seeds = [b"gauge_controller", authority.key().as_ref()],
+        bump,
+        payer = authority,
+        space = 8 + GaugeController::INIT_SPACE,
+    )]
+    pub gauge_controller: Account<'info, GaugeController>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetGaugeEmission<'info> {
+    #[account(
+        mut,
+        seeds = [b"gauge_controller", authority.key().as_ref()],
+        bump = gauge_controller.bump,
+        has_one = authority @ CustomError::Unauthorized,
+    )]
+    pub gauge_controller: Account<'info, GaugeController>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddGauge<'info> {
+    #[account(
+        seeds = [b"gauge_controller", authority.key().as_ref()],
+        bump = gauge_controller.bump,
+        has_one = authority @ CustomError::Unauthorized,
+    )]
+    pub gauge_controller: Account<'info, GaugeController>,
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        init,
+        seeds = [b"gauge", gauge_controller.key().as_ref(), state.key().as_ref()],
+        bump,
+        payer = authority,
+        space = 8 + Gauge::INIT_SPACE,
+    )]
+    pub gauge: Account<'info, Gauge>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterGaugeVoter<'info> {
+    pub gauge_controller: Account<'info, GaugeController>,
+    #[account(constraint = vote_escrow.owner == voter.key() @ CustomError::Unauthorized)]
+    pub vote_escrow: Account<'info, VoteEscrow>,
+    #[account(
+        init,
+        seeds = [b"gauge_voter", gauge_controller.key().as_ref(), vote_escrow.key().as_ref()],
+        bump,
+        payer = voter,
+        space = 8 + GaugeVoter::INIT_SPACE,
+    )]
+    pub gauge_voter: Account<'info, GaugeVoter>,
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VoteGaugeWeight<'info> {
+    #[account(mut)]
+    pub gauge_controller: Account<'info, GaugeController>,
+    #[account(
+        mut,
+        seeds = [b"gauge", gauge_controller.key().as_ref(), state.key().as_ref()],
+        bump = gauge.bump,
+    )]
+    pub gauge: Account<'info, Gauge>,
+    /// The gauge's vault; only its event sequence is advanced.
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(constraint = vote_escrow.owner == voter.key() @ CustomError::Unauthorized)]
+    pub vote_escrow: Account<'info, VoteEscrow>,
+    #[account(
+        mut,
+        seeds = [b"gauge_voter", gauge_controller.key().as_ref(), vote_escrow.key().as_ref()],
+        bump = gauge_voter.bump,
+    )]
+    pub gauge_voter: Account<'info, GaugeVoter>,
+    pub voter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct KickGaugeVoter<'info> {
+    #[account(mut)]
+    pub gauge_controller: Account<'info, GaugeController>,
+    #[account(
+        mut,
+        seeds = [b"gauge", gauge_controller.key().as_ref(), state.key().as_ref()],
+        bump = gauge.bump,
+    )]
+    pub gauge: Account<'info, Gauge>,
+    /// The gauge's vault; only its event sequence is advanced.
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    /// CHECK: The voter's escrow, possibly already closed by `unlock_stake`; checked against
+    /// `gauge_voter.vote_escrow` and only deserialized while it is a live program account.
+    #[account(address = gauge_voter.vote_escrow)]
+    pub vote_escrow: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"gauge_voter", gauge_controller.key().as_ref(), vote_escrow.key().as_ref()],
+        bump = gauge_voter.bump,
+    )]
+    pub gauge_voter: Account<'info, GaugeVoter>,
+}
+
+#[derive(Accounts)]
+pub struct ApplyGaugeRate<'info> {
+    #[account(mut)]
+    pub gauge_controller: Account<'info, GaugeController>,
+    #[account(
+        mut,
+        seeds = [b"gauge", gauge_controller.key().as_ref(), state.key().as_ref()],
+        bump = gauge.bump,
+    )]
+    pub gauge: Account<'info, Gauge>,
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+}
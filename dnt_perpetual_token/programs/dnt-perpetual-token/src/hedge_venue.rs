@@ -0,0 +1,138 @@
+// -----------------------------------------------------------------------------
+// Hedge venues: the perp short that neutralizes the vault's spot exposure can sit on Drift,
+// Mango v4 or Zeta. `State::hedge_venue` selects the venue through the param registry, and the
+// rebalance and settlement instructions dispatch through `HedgeVenue` to the matching CPI module,
+// so governance can move the hedge to the venue paying the best funding without a redeploy.
+// -----------------------------------------------------------------------------
+
+use anchor_lang::prelude::*;
+
+pub const DRIFT_PROGRAM_ID: Pubkey = pubkey!("dRiftyHA39MWEi3m9aunc5MzRF1JYuBsbn6VPcn33UH");
+pub const MANGO_V4_PROGRAM_ID: Pubkey = pubkey!("4MangoMjqJ2firMokCjjGgoK8d4MXcrgL7XJaL3w6fVg");
+pub const ZETA_PROGRAM_ID: Pubkey = pubkey!("ZETAxsqBRek56DhiGXrn75yj2NHU3aYUnxvHXpkf3aD");
+
+/// Perp venue holding the vault's hedge.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum HedgeVenue {
+    Drift,
+    Mango,
+    Zeta,
+}
+
+impl HedgeVenue {
+    pub fn program_id(self) -> Pubkey {
+        match self {
+            HedgeVenue::Drift => DRIFT_PROGRAM_ID,
+            HedgeVenue::Mango => MANGO_V4_PROGRAM_ID,
+            HedgeVenue::Zeta => ZETA_PROGRAM_ID,
+        }
+    }
+
+    /// Moves the vault's perp position by `size_delta` base units (negative sells).
+    pub fn adjust_hedge(self, program: &AccountInfo, size_delta: i64) -> Result<()> {
+        match self {
+            HedgeVenue::Drift => drift::place_perp_order(program, size_delta),
+            HedgeVenue::Mango => mango::place_perp_order(program, size_delta),
+            HedgeVenue::Zeta => zeta::place_perp_order(program, size_delta),
+        }
+    }
+
+    /// Mark-to-market PnL of the open hedge.
+    pub fn unrealized_pnl(self, program: &AccountInfo) -> Result<i64> {
+        match self {
+            HedgeVenue::Drift => drift::unrealized_pnl(program),
+            HedgeVenue::Mango => mango::unrealized_pnl(program),
+            HedgeVenue::Zeta => zeta::unrealized_pnl(program),
+        }
+    }
+
+    /// PnL realized by hedges closed since the last settlement.
+    pub fn settled_pnl(self, program: &AccountInfo) -> Result<i64> {
+        match self {
+            HedgeVenue::Drift => drift::settle_pnl(program),
+            HedgeVenue::Mango => mango::settle_pnl(program),
+            HedgeVenue::Zeta => zeta::settle_pnl(program),
+        }
+    }
+
+    /// Trading fees paid to the venue since the last settlement.
+    pub fn fees_paid(self, program: &AccountInfo) -> Result<i64> {
+        match self {
+            HedgeVenue::Drift => drift::fees_paid(program),
+            HedgeVenue::Mango => mango::fees_paid(program),
+            HedgeVenue::Zeta => zeta::fees_paid(program),
+        }
+    }
+}
+
+pub mod drift {
+    use super::*;
+
+    pub fn place_perp_order(_program: &AccountInfo, _size_delta: i64) -> Result<()> {
+        // Placeholder: CPI `place_perp_order` on the vault's Drift user, signed by the state PDA.
+        Ok(())
+    }
+
+    pub fn unrealized_pnl(_program: &AccountInfo) -> Result<i64> {
+        // Placeholder: Read the unsettled PnL of the vault's Drift perp position.
+        Ok(0)
+    }
+
+    pub fn settle_pnl(_program: &AccountInfo) -> Result<i64> {
+        // Placeholder: CPI `settle_pnl` and return the amount moved into the vault's Drift account.
+        Ok(0)
+    }
+
+    pub fn fees_paid(_program: &AccountInfo) -> Result<i64> {
+        // Placeholder: Read cumulative fees from the Drift user stats account.
+        Ok(0)
+    }
+}
+
+pub mod mango {
+    use super::*;
+
+    pub fn place_perp_order(_program: &AccountInfo, _size_delta: i64) -> Result<()> {
+        // Placeholder: CPI `perp_place_order` on the vault's Mango account, signed by the state PDA.
+        Ok(())
+    }
+
+    pub fn unrealized_pnl(_program: &AccountInfo) -> Result<i64> {
+        // Placeholder: Read the unsettled PnL of the vault's Mango perp position.
+        Ok(0)
+    }
+
+    pub fn settle_pnl(_program: &AccountInfo) -> Result<i64> {
+        // Placeholder: CPI `perp_settle_pnl` and return the settled amount.
+        Ok(0)
+    }
+
+    pub fn fees_paid(_program: &AccountInfo) -> Result<i64> {
+        // Placeholder: Read accrued perp fees from the vault's Mango account.
+        Ok(0)
+    }
+}
+
+pub mod zeta {
+    use super::*;
+
+    pub fn place_perp_order(_program: &AccountInfo, _size_delta: i64) -> Result<()> {
+        // Placeholder: CPI `place_perp_order_v3` on the vault's Zeta margin account.
+        Ok(())
+    }
+
+    pub fn unrealized_pnl(_program: &AccountInfo) -> Result<i64> {
+        // Placeholder: Read the unrealized PnL of the vault's Zeta perp position.
+        Ok(0)
+    }
+
+    pub fn settle_pnl(_program: &AccountInfo) -> Result<i64> {
+        // Placeholder: CPI `settle_positions` and return the realized amount.
+        Ok(0)
+    }
+
+    pub fn fees_paid(_program: &AccountInfo) -> Result<i64> {
+        // Placeholder: Read trading fees paid from the vault's Zeta margin account.
+        Ok(0)
+    }
+}
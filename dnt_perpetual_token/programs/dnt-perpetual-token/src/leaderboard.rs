@@ -0,0 +1,222 @@
+// -----------------------------------------------------------------------------
+// Maker leaderboard: an attestor posts per-epoch maker scores (volume, uptime, spread
+// tightness) into a zero-copy `Leaderboard`; on epoch close the top ranks share a
+// bonus minted on top of the regular liquidity-provider rewards.
+// -----------------------------------------------------------------------------
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::{
+    mint_rewards, next_event_seq, CustomError, LeaderboardClosed, State, BPS_DENOMINATOR,
+    RESERVED_SPACE,
+};
+
+/// Ranked makers kept per epoch.
+pub const LEADERBOARD_LEN: usize = 16;
+/// Bonus minted across the ranked makers when an epoch closes.
+pub const LEADERBOARD_EPOCH_BONUS: u64 = 10_000;
+
+#[zero_copy]
+pub struct MakerScore {
+    pub maker: Pubkey,
+    pub volume: u64,
+    /// Share of the epoch the maker kept quotes live, in bps.
+    pub uptime_bps: u64,
+    /// Spread tightness relative to the venue's best, in bps (10_000 is tightest).
+    pub spread_score_bps: u64,
+    /// Volume weighted by uptime and spread tightness; the ranking key.
+    pub score: u64,
+}
+
+#[account(zero_copy)]
+pub struct Leaderboard {
+    pub state: Pubkey,
+    pub attestor: Pubkey,
+    /// Solana epoch the current scores belong to.
+    pub epoch: u64,
+    pub count: u32,
+    pub bump: u8,
+    pub _padding: [u8; 3],
+    /// Sorted by `score`, highest first; only the first `count` entries are live.
+    pub entries: [MakerScore; LEADERBOARD_LEN],
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+impl Leaderboard {
+    pub const SPACE: usize = 8 + std::mem::size_of::<Leaderboard>();
+
+    /// Inserts or replaces `entry`, keeping the board sorted and capped at LEADERBOARD_LEN.
+    pub fn upsert(&mut self, entry: MakerScore) {
+        let mut live: Vec<MakerScore> = self.entries[..self.count as usize]
+            .iter()
+            .filter(|e| e.maker != entry.maker)
+            .copied()
+            .collect();
+        let at = live.partition_point(|e| e.score >= entry.score);
+        live.insert(at, entry);
+        live.truncate(LEADERBOARD_LEN);
+        self.count = live.len() as u32;
+        self.entries[..live.len()].copy_from_slice(&live);
+    }
+}
+
+/// Attested epoch metrics for one maker.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct MakerScoreInput {
+    pub maker: Pubkey,
+    pub volume: u64,
+    pub uptime_bps: u64,
+    pub spread_score_bps: u64,
+}
+
+pub fn initialize(ctx: Context<InitializeLeaderboard>, attestor: Pubkey) -> Result<()> {
+    let mut board = ctx.accounts.leaderboard.load_init()?;
+    board.state = ctx.accounts.state.key();
+    board.attestor = attestor;
+    board.epoch = Clock::get()?.epoch;
+    board.count = 0;
+    board.bump = ctx.bumps.leaderboard;
+    Ok(())
+}
+
+pub fn post_scores(ctx: Context<PostMakerScores>, scores: Vec<MakerScoreInput>) -> Result<()> {
+    let mut board = ctx.accounts.leaderboard.load_mut()?;
+    require!(board.epoch == Clock::get()?.epoch, CustomError::LeaderboardEpochOver);
+    for input in scores {
+        require!(
+            input.uptime_bps <= BPS_DENOMINATOR && input.spread_score_bps <= BPS_DENOMINATOR,
+            CustomError::InvalidParameter
+        );
+        let score = (input.volume as u128)
+            * input.uptime_bps as u128
+            * input.spread_score_bps as u128
+            / (BPS_DENOMINATOR as u128 * BPS_DENOMINATOR as u128);
+        board.upsert(MakerScore {
+            maker: input.maker,
+            volume: input.volume,
+            uptime_bps: input.uptime_bps,
+            spread_score_bps: input.spread_score_bps,
+            score: score as u64,
+        });
+    }
+    Ok(())
+}
+
+/// Pays the epoch bonus by rank and starts the next epoch. Rank `i` of `n` earns weight `n - i`.
+/// `remaining_accounts` holds each ranked maker's reward token account, in rank order.
+pub fn close_epoch<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CloseLeaderboardEpoch<'info>>,
+) -> Result<()> {
+    let (epoch, ranked) = {
+        let board = ctx.accounts.leaderboard.load()?;
+        require!(Clock::get()?.epoch > board.epoch, CustomError::LeaderboardEpochActive);
+        (board.epoch, board.entries[..board.count as usize].to_vec())
+    };
+    require!(
+        ctx.remaining_accounts.len() == ranked.len(),
+        CustomError::InvalidBatchAccounts
+    );
+
+    let n = ranked.len() as u64;
+    let total_weight = n * (n + 1) / 2;
+    let mut paid = 0u64;
+    for (rank, (entry, info)) in ranked.iter().zip(ctx.remaining_accounts).enumerate() {
+        let destination: Account<'info, TokenAccount> = Account::try_from(info)?;
+        require_keys_eq!(destination.owner, entry.maker, CustomError::InvalidBatchAccounts);
+        require_keys_eq!(
+            destination.mint,
+            ctx.accounts.token_mint.key(),
+            CustomError::InvalidBatchAccounts
+        );
+        let bonus = LEADERBOARD_EPOCH_BONUS * (n - rank as u64) / total_weight;
+        mint_rewards(
+            &mut ctx.accounts.state,
+            &ctx.accounts.state_owner,
+            &ctx.accounts.token_mint,
+            &destination,
+            &ctx.accounts.token_program,
+            bonus,
+        )?;
+        paid += bonus;
+    }
+
+    {
+        let mut board = ctx.accounts.leaderboard.load_mut()?;
+        board.epoch = Clock::get()?.epoch;
+        board.count = 0;
+    }
+    let seq = next_event_seq(&mut ctx.accounts.state)?;
+    emit!(LeaderboardClosed {
+        seq,
+        state: ctx.accounts.state.key(),
+        epoch,
+        makers: n as u32,
+        bonus_paid: paid,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeLeaderboard<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        seeds = [b"leaderboard", state.key().as_ref()],
+        bump,
+        payer = state_owner,
+        space = Leaderboard::SPACE,
+    )]
+    pub leaderboard: AccountLoader<'info, Leaderboard>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PostMakerScores<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"leaderboard", state.key().as_ref()],
+        bump = leaderboard.load()?.bump,
+        has_one = attestor @ CustomError::Unauthorized,
+    )]
+    pub leaderboard: AccountLoader<'info, Leaderboard>,
+    pub attestor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseLeaderboardEpoch<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"leaderboard", state.key().as_ref()],
+        bump = leaderboard.load()?.bump,
+    )]
+    pub leaderboard: AccountLoader<'info, Leaderboard>,
+    #[account(mut)]
+    pub token_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+}
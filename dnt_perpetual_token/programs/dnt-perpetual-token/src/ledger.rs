@@ -0,0 +1,219 @@
+// -----------------------------------------------------------------------------
+// Double-entry ledger: the vault's core value movements (base-mint stakes, queued deposits and
+// withdrawals, withdrawal fees, reward emission and claims, realized hedge PnL) post balanced
+// debit/credit entries into a zero-copy `Ledger`. `verify_ledger` lets anyone check that the
+// books balance and that the vault holds what they say it does.
+// -----------------------------------------------------------------------------
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+use crate::{next_event_seq, CustomError, LedgerVerified, State, BPS_DENOMINATOR, RESERVED_SPACE};
+
+/// Number of accounts in the chart of accounts; see `LedgerAccount`.
+pub const LEDGER_ACCOUNT_COUNT: usize = 7;
+/// Most recent entries kept in the journal ring buffer.
+pub const LEDGER_JOURNAL_LEN: usize = 64;
+
+/// Chart of accounts. Amounts are in base-mint units, or reward-token units for the two reward
+/// accounts.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LedgerAccount {
+    /// Base assets held by the vault.
+    Vault,
+    /// Principal owed to stakers.
+    StakerPrincipal,
+    /// Fees earned by the protocol.
+    FeeRevenue,
+    /// Reward tokens held in the rewards pool.
+    RewardsPool,
+    /// Rewards emitted and not yet paid out.
+    RewardsOwed,
+    /// Realized hedge PnL attributed to stakers.
+    HedgePnl,
+    /// Base deposits waiting in the deposit queue.
+    PendingDeposits,
+}
+
+#[zero_copy]
+pub struct LedgerEntry {
+    /// Position of the entry in the full history, starting at zero.
+    pub id: u64,
+    pub amount: u64,
+    pub posted_at: i64,
+    pub debit: u8,
+    pub credit: u8,
+    pub _padding: [u8; 6],
+}
+
+#[account(zero_copy)]
+pub struct Ledger {
+    pub state: Pubkey,
+    /// Entries posted over the ledger's lifetime.
+    pub entry_count: u64,
+    /// Sum of all posted amounts; equal to both total debits and total credits.
+    pub total_posted: u64,
+    /// Running debit and credit totals, indexed by `LedgerAccount`.
+    pub debits: [u64; LEDGER_ACCOUNT_COUNT],
+    pub credits: [u64; LEDGER_ACCOUNT_COUNT],
+    pub bump: u8,
+    pub _padding: [u8; 7],
+    /// Last LEDGER_JOURNAL_LEN entries; entry `id` sits at `id % LEDGER_JOURNAL_LEN`.
+    pub journal: [LedgerEntry; LEDGER_JOURNAL_LEN],
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+impl Ledger {
+    pub const SPACE: usize = 8 + std::mem::size_of::<Ledger>();
+
+    /// Debits `debit` and credits `credit` by `amount`. Zero amounts are not journaled.
+    pub fn post(
+        &mut self,
+        debit: LedgerAccount,
+        credit: LedgerAccount,
+        amount: u64,
+        now: i64,
+    ) -> Result<()> {
+        require!(debit != credit, CustomError::InvalidParameter);
+        if amount == 0 {
+            return Ok(());
+        }
+        let (d, c) = (debit as usize, credit as usize);
+        self.debits[d] = self.debits[d].checked_add(amount).ok_or(CustomError::MathOverflow)?;
+        self.credits[c] = self.credits[c].checked_add(amount).ok_or(CustomError::MathOverflow)?;
+        self.total_posted = self
+            .total_posted
+            .checked_add(amount)
+            .ok_or(CustomError::MathOverflow)?;
+        self.journal[(self.entry_count % LEDGER_JOURNAL_LEN as u64) as usize] = LedgerEntry {
+            id: self.entry_count,
+            amount,
+            posted_at: now,
+            debit: debit as u8,
+            credit: credit as u8,
+            _padding: [0; 6],
+        };
+        self.entry_count = self.entry_count.checked_add(1).ok_or(CustomError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Debits less credits posted to `account`: its balance for an asset account, or minus its
+    /// balance for a liability.
+    pub fn net_debit(&self, account: LedgerAccount) -> i128 {
+        self.debits[account as usize] as i128 - self.credits[account as usize] as i128
+    }
+
+    /// True when total debits, total credits and the posted total all agree.
+    pub fn balanced(&self) -> bool {
+        let debits: u128 = self.debits.iter().map(|&v| v as u128).sum();
+        let credits: u128 = self.credits.iter().map(|&v| v as u128).sum();
+        debits == credits && debits == self.total_posted as u128
+    }
+}
+
+/// Posts one balanced entry to the vault's ledger.
+pub fn post(
+    ledger: &AccountLoader<Ledger>,
+    debit: LedgerAccount,
+    credit: LedgerAccount,
+    amount: u64,
+) -> Result<()> {
+    ledger.load_mut()?.post(debit, credit, amount, Clock::get()?.unix_timestamp)
+}
+
+pub fn initialize(ctx: Context<InitializeLedger>) -> Result<()> {
+    let mut ledger = ctx.accounts.ledger.load_init()?;
+    ledger.state = ctx.accounts.state.key();
+    ledger.bump = ctx.bumps.ledger;
+    Ok(())
+}
+
+pub fn verify(ctx: Context<VerifyLedger>) -> Result<()> {
+    let (entry_count, total_posted, vault, fee_revenue, pending_deposits) = {
+        let ledger = ctx.accounts.ledger.load()?;
+        require!(ledger.balanced(), CustomError::LedgerImbalance);
+        (
+            ledger.entry_count,
+            ledger.total_posted,
+            ledger.net_debit(LedgerAccount::Vault),
+            -ledger.net_debit(LedgerAccount::FeeRevenue),
+            -ledger.net_debit(LedgerAccount::PendingDeposits),
+        )
+    };
+    // Base tokens in custody that the ledger covers: the vault and fee vault, less trader
+    // margin, the trader book's realized PnL and the protocol's own stake.
+    let state = &ctx.accounts.state;
+    let custody = ctx.accounts.vault_account.amount as i128
+        + ctx.accounts.fee_vault.amount as i128
+        - state.total_position_collateral as i128
+        - state.position_realized_pnl as i128
+        - state.protocol_stake as i128;
+    let tolerance =
+        (custody.max(0) * state.reconcile_tolerance_bps as i128) / BPS_DENOMINATOR as i128;
+    // As in `reconcile`, only a shortfall fails: donations leave the vault holding more than the
+    // books, never less. Staker principal is not compared with `total_staked`, which counts
+    // shares issued at NAV; principal the vault cannot cover shows up here as a Vault shortfall.
+    require!(vault <= custody + tolerance, CustomError::LedgerImbalance);
+    // Fees and queued deposits in other mints never reach the ledger.
+    require!(fee_revenue <= state.base_fees_accrued as i128, CustomError::LedgerImbalance);
+    require!(pending_deposits <= state.pending_deposits as i128, CustomError::LedgerImbalance);
+    let seq = next_event_seq(&mut ctx.accounts.state)?;
+    emit!(LedgerVerified {
+        seq,
+        state: ctx.accounts.state.key(),
+        entry_count,
+        total_posted,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeLedger<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        seeds = [b"ledger", state.key().as_ref()],
+        bump,
+        payer = state_owner,
+        space = Ledger::SPACE,
+    )]
+    pub ledger: AccountLoader<'info, Ledger>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyLedger<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(seeds = [b"ledger", state.key().as_ref()], bump = ledger.load()?.bump)]
+    pub ledger: AccountLoader<'info, Ledger>,
+    #[account(
+        seeds = [b"vault", state.key().as_ref()],
+        bump,
+        constraint = vault_account.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = vault_account.mint == state.base_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub vault_account: Account<'info, TokenAccount>,
+    #[account(
+        seeds = [b"fee_vault", state.key().as_ref(), state.base_mint.as_ref()],
+        bump,
+        constraint = fee_vault.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = fee_vault.mint == state.base_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+}
@@ -1,69 +1,369 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer, MintTo};
+use anchor_lang::solana_program::keccak;
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer, MintTo, SyncNative, CloseAccount, Burn};
+
+pub mod market_making;
+use market_making::*;
+pub mod adl;
+use adl::*;
+pub mod leaderboard;
+use leaderboard::*;
+pub mod hedge_venue;
+use hedge_venue::*;
+pub mod liquidators;
+use liquidators::*;
+pub mod ledger;
+use ledger::*;
+pub mod funding_oracle;
+use funding_oracle::*;
+pub mod fixed_point;
+use fixed_point::*;
+pub mod vesting;
+use vesting::*;
+pub mod stake_index;
+use stake_index::*;
+pub mod lp_rewards;
+use lp_rewards::*;
+pub mod trade_intents;
+use trade_intents::*;
+pub mod mock_oracle;
+use mock_oracle::*;
+pub mod tvl_stats;
+use tvl_stats::*;
+pub mod gauges;
+use gauges::*;
+pub mod sandwich_guard;
+use sandwich_guard::*;
+pub mod program_whitelist;
+use program_whitelist::*;
+pub mod price_cache;
+use price_cache::*;
 
 declare_id!("9rBKpkU7gkq7nndgQuhhped2zQdt5pYwfAUH2XpsfBch");
 
-/// Constants for risk management and flash loan protection.
-const MIN_STAKE_DURATION: i64 = 60; // Minimum staking duration in seconds.
-const MAX_ALLOWED_LOSS: u64 = 50;   // Maximum allowed loss percentage before liquidation.
+/// Default shortfall `reconcile` tolerates before pausing, in bps of book value.
+const DEFAULT_RECONCILE_TOLERANCE_BPS: u64 = 10;
+
+/// Default risk policy for new vaults; governance can change both through the param registry.
+const DEFAULT_MIN_STAKE_DURATION: i64 = 60; // Minimum staking duration in seconds.
+const DEFAULT_MAX_ALLOWED_LOSS_BPS: u64 = 5_000; // Maximum allowed loss before liquidation.
+
+/// Default window after which unclaimed rewards may be swept (one year).
+const DEFAULT_UNCLAIMED_EXPIRY_SECS: i64 = 365 * 24 * 60 * 60;
+/// Delay between proposing a parameter change and when it may be activated.
+const PARAM_TIMELOCK_SECS: i64 = 2 * 24 * 60 * 60;
+
+/// Seconds per year; reward rates are annualized.
+const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+
+/// Default maximum age of a keeper delta report before it is considered stale.
+const DEFAULT_MAX_DELTA_REPORT_AGE: i64 = 300;
+/// Denominator for basis-point parameters.
+const BPS_DENOMINATOR: u64 = 10_000;
+/// Fixed-point scale of oracle prices (base units per collateral unit).
+const PRICE_PRECISION: u64 = 1_000_000;
+
+/// SPL stake pool program; LST exchange rates are read from its pool accounts.
+const SPL_STAKE_POOL_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy");
+/// Byte offsets of `total_lamports` and `pool_token_supply` in an SPL stake pool account.
+const STAKE_POOL_TOTAL_LAMPORTS_OFFSET: usize = 258;
+const STAKE_POOL_TOKEN_SUPPLY_OFFSET: usize = 266;
+
+/// Time a margin-called position has to restore health before it can be liquidated.
+const MARGIN_CALL_GRACE_PERIOD: i64 = 3_600;
+
+/// Funding rates are quoted in basis points per this many seconds.
+const FUNDING_INTERVAL: i64 = 3_600;
+
+/// Number of funding observations kept for the funding TWAP.
+const FUNDING_HISTORY_LEN: usize = 24;
+
+/// Number of recent actions kept on each position.
+pub const POSITION_HISTORY_LEN: usize = 16;
+/// Zeroed bytes kept at the end of every account so fields can be added without a migration.
+pub const RESERVED_SPACE: usize = 64;
+
+/// Tip paid to whoever executes a take-profit or stop-loss, in bps of the collateral returned.
+const TRIGGER_EXECUTION_TIP_BPS: u64 = 10;
+/// Tip paid to whoever delivers a user's rewards through `auto_claim_for`, in bps of the claim.
+const AUTO_CLAIM_TIP_BPS: u64 = 10;
+
+/// Extra rewards paid on claim to users who voted during the current epoch.
+const GOVERNANCE_REWARD_BOOST_BPS: u64 = 500;
+
+/// Longest vote-escrow lock, and the voting weight multiplier (bps) a lock of that length earns.
+const VOTE_ESCROW_MAX_LOCK_SECS: i64 = 4 * 365 * 24 * 60 * 60;
+const VOTE_ESCROW_MAX_MULTIPLIER_BPS: u64 = 40_000;
+
+/// Upper bound on the extra rewards an NFT boost campaign may grant.
+const MAX_NFT_BOOST_BPS: u64 = 5_000;
+
+/// Metaplex token metadata program; NFT collections are read from its metadata accounts.
+const TOKEN_METADATA_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Leverage limits for positions, in bps of collateral (10_000 = 1x): the default when the base
+/// mint has no collateral config, and the highest a config may allow.
+const DEFAULT_MAX_LEVERAGE_BPS: u64 = 50_000;
+const MAX_LEVERAGE_LIMIT_BPS: u64 = 200_000;
+
+/// Band, in bps of $1, a stablecoin's oracle price must stay within for the fixed $1 price to
+/// apply; outside it the collateral is treated as depegged and pricing halts.
+const STABLE_DEPEG_LOWER_BPS: u64 = 9_500;
+const STABLE_DEPEG_UPPER_BPS: u64 = 10_500;
+
+/// Vaults the global config can register.
+pub const MAX_REGISTERED_VAULTS: usize = 64;
+
+/// Upper bound on per-collateral deposit and withdraw fees.
+const MAX_COLLATERAL_FEE_BPS: u64 = 1_000;
+
+/// Non-base collaterals a single stake can track for in-kind withdrawal.
+pub const USER_ASSET_SLOTS: usize = 4;
+
+/// Queue-jump fee: a base charge plus weights (bps per 100% of queue depth and of hedge
+/// utilization), capped at the maximum.
+const QUEUE_JUMP_BASE_FEE_BPS: u64 = 50;
+const QUEUE_JUMP_DEPTH_WEIGHT_BPS: u64 = 1_000;
+const QUEUE_JUMP_UTILIZATION_WEIGHT_BPS: u64 = 500;
+const QUEUE_JUMP_MAX_FEE_BPS: u64 = 1_000;
 
 #[program]
 pub mod dnt_perpetual_token {
     use super::*;
 
-    // Initialize the global protocol state.
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+    // Create the program-wide config at the constant `[b"config"]` seed. Only the program's
+    // upgrade authority may do this, and it becomes the config admin.
+    pub fn initialize_config(ctx: Context<InitializeConfig>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.bump = ctx.bumps.config;
+        config.admin = ctx.accounts.authority.key();
+        config.vaults = Vec::new();
+        config.whitelisted_programs = DEFAULT_WHITELISTED_PROGRAMS.to_vec();
+        Ok(())
+    }
+
+    // Config admin: register a vault as canonical. `State` PDAs are seeded by their creator, so
+    // anyone can create one; only registered vaults may mint rewards.
+    pub fn register_vault(ctx: Context<RegisterVault>) -> Result<()> {
+        let state_key = ctx.accounts.state.key();
+        let config = &mut ctx.accounts.config;
+        require!(!config.vaults.contains(&state_key), CustomError::VaultAlreadyRegistered);
+        require!(
+            config.vaults.len() < MAX_REGISTERED_VAULTS,
+            CustomError::VaultRegistryFull
+        );
+        config.vaults.push(state_key);
+        ctx.accounts.state.registered = true;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(VaultRegistered {
+            seq,
+            state: state_key,
+            registered_by: ctx.accounts.admin.key(),
+        });
+        Ok(())
+    }
+
+    // Config admin: queue adding (`add`) or removing a hedge or quoting program on the CPI
+    // whitelist. It takes effect through `apply_whitelist_change` after PARAM_TIMELOCK_SECS.
+    pub fn propose_whitelist_change(
+        ctx: Context<ProposeWhitelistChange>,
+        program: Pubkey,
+        add: bool,
+    ) -> Result<()> {
+        program_whitelist::propose(ctx, program, add)
+    }
+
+    // Permissionless once the timelock has passed: apply a queued whitelist change.
+    pub fn apply_whitelist_change(ctx: Context<ApplyWhitelistChange>) -> Result<()> {
+        program_whitelist::apply(ctx)
+    }
+
+    // Config admin: drop a queued whitelist change.
+    pub fn cancel_whitelist_change(_ctx: Context<CancelWhitelistChange>) -> Result<()> {
+        Ok(())
+    }
+
+    // Create a strategy vault. Each vault has its own state, base mint, share mint and
+    // risk parameters, so several strategies can run side by side under one owner. It cannot
+    // mint rewards until the config admin registers it.
+    pub fn create_vault(
+        ctx: Context<CreateVault>,
+        vault_id: u64,
+        allowed_delta_threshold: u64,
+    ) -> Result<()> {
         let state = &mut ctx.accounts.state;
         state.bump = ctx.bumps.state;
+        state.vault_id = vault_id;
+        state.state_owner = ctx.accounts.payer.key();
+        state.base_mint = ctx.accounts.base_mint.key();
+        state.share_mint = ctx.accounts.share_mint.key();
         state.total_staked = 0;
         let now = Clock::get()?.unix_timestamp;
         state.last_update = now;
         state.last_rebalance = now;
-        // Default governance risk parameter.
-        state.allowed_delta_threshold = 100;
+        // Governance risk parameter for this strategy.
+        state.allowed_delta_threshold = allowed_delta_threshold;
+        // Launch starts whitelist-only; staking stays closed until a root is configured.
+        state.launch_phase = LaunchPhase::Whitelist;
+        state.whitelist_root = [0u8; 32];
+        state.capped_phase_max_stake = 0;
+        state.cumulative_realized_pnl = 0;
+        state.unrealized_pnl = 0;
+        state.reward_index = 0;
+        state.undistributed_rewards = 0;
+        state.unclaimed_expiry_secs = DEFAULT_UNCLAIMED_EXPIRY_SECS;
+        // The deployer acts as keeper until a dedicated key is assigned.
+        state.keeper = ctx.accounts.payer.key();
+        state.max_delta_report_age = DEFAULT_MAX_DELTA_REPORT_AGE;
+        // No outflow limit until governance sets one.
+        state.max_outflow_bps_per_epoch = BPS_DENOMINATOR;
+        state.outflow_epoch = Clock::get()?.epoch;
+        state.epoch_start_staked = 0;
+        state.epoch_outflow = 0;
+        state.queued_withdrawals = 0;
+        state.event_seq = 0;
+        state.total_position_collateral = 0;
+        state.protocol_stake = 0;
+        // Dead-man's switch stays off until governance sets a staleness limit.
+        state.max_rebalance_staleness = 0;
+        state.cumulative_funding_index = 0;
+        state.last_funding_accrual = now;
+        // Emissions follow a kinked utilization curve: 5% APR at zero utilization, 15% at the 80%
+        // kink, and 65% once fully utilized.
+        state.reward_rate_base_bps = 500;
+        state.reward_rate_slope1_bps = 1_000;
+        state.reward_rate_slope2_bps = 5_000;
+        state.reward_kink_utilization_bps = 8_000;
+        // Drawdown breaker stays off until governance sets a limit.
+        state.max_drawdown_bps = 0;
+        state.peak_nav_per_share = 0;
+        state.drawdown_paused = false;
+        // No crank spacing until governance sets minimum intervals.
+        state.min_rebalance_interval = 0;
+        state.min_distribution_interval = 0;
+        state.next_crank_due = now;
+        state.min_stake_duration = DEFAULT_MIN_STAKE_DURATION;
+        state.max_allowed_loss_bps = DEFAULT_MAX_ALLOWED_LOSS_BPS;
+        state.base_fees_accrued = 0;
+        state.reconcile_tolerance_bps = DEFAULT_RECONCILE_TOLERANCE_BPS;
+        state.reconcile_paused = false;
+        // No priority exits until governance sets a threshold.
+        state.small_balance_threshold = 0;
+        // Buybacks stay off until governance enables them and sets a cap.
+        state.buyback_enabled = false;
+        state.buyback_cap_per_epoch = 0;
+        state.buyback_epoch = 0;
+        state.buyback_spent_this_epoch = 0;
+        // Tiers are uncapped until governance sets limits.
+        state.tier_caps = [u64::MAX; Tier::COUNT];
+        state.hedge_venue = HedgeVenue::Drift;
+        state.registered = false;
+        state.emission_mode = EmissionMode::UtilizationCurve;
+        state.guardian = Pubkey::default();
+        state.pending_guardian = Pubkey::default();
+        state.guardian_paused = false;
+        state.stake_index_pages = 0;
+        state.fee_sweep_cosign_threshold = 0;
+        state.keeper_nonce = 0;
+        state.lp_emission_bps = 0;
+        state.lp_rewards_pending = 0;
+        state.total_rewards_minted = 0;
+        state.reward_mint_cap = 0;
+        state.reward_checkpoint_at = now;
+        state.accrual_utilization_bps = 0;
+        state.rewards_accrued_unminted = 0;
+        state.rewards_redirected_pending = 0;
+        state.trade_intent_count = 0;
+        state.lending_staked = 0;
+        state.lending_pnl_index = 0;
+        state.max_single_hedge_notional = 0;
+        state.pending_tranches = 0;
+        state.dust_carry = 0;
+        state.strategy_capacity = 0;
+        state.pending_deposits = 0;
+        state.pending_deposit_head = 0;
+        state.pending_deposit_tail = 0;
+        state.last_hedged_at = now;
+        state.queue_fee_index = 0;
+        state.position_realized_pnl = 0;
         Ok(())
     }
 
-    // Stake tokens to join the automated trading pool.
-    pub fn stake(ctx: Context<StakeAccounts>, amount: u64) -> Result<()> {
-        // Transfer tokens from the trader’s account to the vault.
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.user_token_account.to_account_info(),
-            to: ctx.accounts.vault_account.to_account_info(),
-            authority: ctx.accounts.user.to_account_info(),
-        };
-        token::transfer(
-            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
-            amount,
-        )?;
-
-        let state = &mut ctx.accounts.state;
-        state.total_staked = state.total_staked.checked_add(amount).unwrap();
+    // Create the base-mint vault token account at the `[b"vault", state]` PDA, owned by the
+    // state PDA, so deployment needs no manual account setup.
+    pub fn initialize_vault(_ctx: Context<InitializeVault>) -> Result<()> {
+        Ok(())
+    }
 
-        let user_stake = &mut ctx.accounts.user_stake;
-        user_stake.amount = user_stake.amount.checked_add(amount).unwrap();
-        user_stake.last_update = Clock::get()?.unix_timestamp;
+    // Create the vault token account for a registered collateral mint at
+    // `[b"collateral_vault", state, mint]`.
+    pub fn initialize_collateral_vault(ctx: Context<InitializeCollateralVault>) -> Result<()> {
+        let mint = ctx.accounts.collateral_mint.key();
+        require!(
+            ctx.accounts
+                .collateral_registry
+                .collaterals
+                .iter()
+                .any(|c| c.mint == mint),
+            CustomError::UnknownCollateral
+        );
         Ok(())
     }
 
-    // Stake using multiple collateral types (e.g., SOL, USDC, USDT).
-    pub fn stake_with_multiple_assets(
-        ctx: Context<MultiCollateralStakeAccounts>,
-        asset_type: u8,
-        amount: u64,
+    // Set the whitelist merkle root and the per-wallet cap used during the guarded launch.
+    pub fn configure_launch(
+        ctx: Context<ManageLaunch>,
+        whitelist_root: [u8; 32],
+        capped_phase_max_stake: u64,
     ) -> Result<()> {
-        // Convert the provided amount to a normalized value.
-        let conversion_rate = get_conversion_rate(asset_type)?;
-        let normalized_amount = amount.checked_mul(conversion_rate).unwrap();
+        let state = &mut ctx.accounts.state;
+        state.whitelist_root = whitelist_root;
+        state.capped_phase_max_stake = capped_phase_max_stake;
+        Ok(())
+    }
 
+    // Move the launch forward one phase (Whitelist -> Capped -> Open). Phases never go back.
+    pub fn advance_launch_phase(ctx: Context<ManageLaunch>) -> Result<()> {
         let state = &mut ctx.accounts.state;
-        state.total_staked = state.total_staked.checked_add(normalized_amount).unwrap();
+        state.launch_phase = match state.launch_phase {
+            LaunchPhase::Whitelist => LaunchPhase::Capped,
+            LaunchPhase::Capped => LaunchPhase::Open,
+            LaunchPhase::Open => return err!(CustomError::LaunchAlreadyOpen),
+        };
+        Ok(())
+    }
 
-        let user_stake = &mut ctx.accounts.user_stake;
-        user_stake.amount = user_stake.amount.checked_add(normalized_amount).unwrap();
-        user_stake.last_update = Clock::get()?.unix_timestamp;
+    // Stake tokens to join the automated trading pool.
+    // During the whitelist phase `proof` must show the user is in the whitelist merkle tree.
+    // `reference` is an optional caller-chosen ID (e.g. an exchange order ID) recorded in a
+    // `DepositReceipt` and the `Staked` event for off-chain reconciliation. `bucket` picks the
+    // stake's risk bucket; it can only change while the stake is empty.
+    pub fn stake(
+        ctx: Context<StakeAccounts>,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+        reference: Option<[u8; 32]>,
+        bucket: RiskBucket,
+    ) -> Result<()> {
+        require_not_blocked(&ctx.accounts.blocked)?;
+        ctx.accounts.user_stake.choose_risk_bucket(bucket)?;
+        require!(
+            reference.is_some() == ctx.accounts.deposit_receipt.is_some(),
+            CustomError::InvalidParameter
+        );
+        let new_user_amount = ctx
+            .accounts
+            .user_stake
+            .amount
+            .checked_add(amount)
+            .ok_or(CustomError::MathOverflow)?;
+        enforce_launch_phase(&ctx.accounts.state, ctx.accounts.user.key, &proof, new_user_amount)?;
+        require_accepting_stakes(&ctx.accounts.state, &ctx.accounts.delta_report)?;
 
-        // Transfer the provided tokens from the user to the vault.
+        // Transfer tokens from the trader’s account to the vault.
         let cpi_accounts = Transfer {
             from: ctx.accounts.user_token_account.to_account_info(),
             to: ctx.accounts.vault_account.to_account_info(),
@@ -73,390 +373,9156 @@ pub mod dnt_perpetual_token {
             CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
             amount,
         )?;
-        Ok(())
+
+        // Anything over the strategy capacity joins the deposit queue.
+        let admitted = amount.min(capacity_headroom(&ctx.accounts.state));
+        let queued = amount - admitted;
+        if let Some(pending_deposit) = pending_deposit_slot(
+            &mut ctx.accounts.pending_deposit,
+            ctx.bumps.pending_deposit,
+            queued,
+        )? {
+            let base_mint = ctx.accounts.state.base_mint;
+            queue_deposit(
+                &mut ctx.accounts.state,
+                &mut ctx.accounts.user_stake,
+                pending_deposit,
+                ctx.accounts.user.key(),
+                base_mint,
+                queued,
+                queued,
+            )?;
+        }
+        let credited =
+            split_large_stake(&mut ctx.accounts.state, &mut ctx.accounts.user_stake, admitted)?;
+        credit_stake(
+            &mut ctx.accounts.state,
+            &mut ctx.accounts.user_stake,
+            credited,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let user_stake_key = ctx.accounts.user_stake.key();
+        stake_index::sync(&ctx.accounts.stake_index, &mut ctx.accounts.user_stake, user_stake_key)?;
+        post_deposit(&ctx.accounts.ledger, admitted, queued)?;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        if let (Some(receipt), Some(reference)) = (&mut ctx.accounts.deposit_receipt, reference) {
+            receipt.bump = ctx.bumps.deposit_receipt.ok_or(CustomError::InvalidParameter)?;
+            receipt.state = ctx.accounts.state.key();
+            receipt.user = ctx.accounts.user.key();
+            receipt.reference = reference;
+            receipt.mint = ctx.accounts.user_token_account.mint;
+            receipt.amount = amount;
+            receipt.event_seq = seq;
+            receipt.deposited_at = Clock::get()?.unix_timestamp;
+        }
+        emit!(Staked {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            mint: ctx.accounts.user_token_account.mint,
+            deposited: amount,
+            stake_credited: credited,
+            reference,
+        });
+        emit_pending_tranche(
+            &mut ctx.accounts.state,
+            ctx.accounts.user.key(),
+            &ctx.accounts.user_stake,
+            admitted - credited,
+        )?;
+        emit_deposit_queued(&mut ctx.accounts.state, ctx.accounts.pending_deposit.as_deref())
     }
 
-    // Unstake tokens and withdraw from the pool.
-    pub fn unstake(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+    // Permissionless: credit the deposit at the head of the queue once it fits under
+    // `strategy_capacity`, whether stake has left or governance raised the capacity. Deposits are
+    // admitted strictly in FIFO order.
+    pub fn admit_pending_deposit(ctx: Context<AdmitPendingDeposit>) -> Result<()> {
+        let pending_deposit = &ctx.accounts.pending_deposit;
+        let (amount, mint, deposited) =
+            (pending_deposit.amount, pending_deposit.mint, pending_deposit.deposited);
+        let state = &mut ctx.accounts.state;
+        let staked = state
+            .total_staked
+            .checked_add(state.pending_tranches)
+            .and_then(|v| v.checked_add(amount))
+            .ok_or(CustomError::MathOverflow)?;
+        require!(
+            state.strategy_capacity == 0 || staked <= state.strategy_capacity,
+            CustomError::StrategyAtCapacity
+        );
+        state.pending_deposits = state
+            .pending_deposits
+            .checked_sub(amount)
+            .ok_or(CustomError::MathOverflow)?;
+        state.pending_deposit_head = state
+            .pending_deposit_head
+            .checked_add(1)
+            .ok_or(CustomError::MathOverflow)?;
         let user_stake = &mut ctx.accounts.user_stake;
-        require!(user_stake.amount >= amount, CustomError::InsufficientStake);
+        user_stake.queued_deposits = user_stake
+            .queued_deposits
+            .checked_sub(1)
+            .ok_or(CustomError::MathOverflow)?;
+        // Collateral deposits are credited whole, as in `stake_with_multiple_assets`, and sit
+        // outside the base ledger.
+        let credited = if mint == state.base_mint {
+            ledger::post(
+                &ctx.accounts.ledger,
+                LedgerAccount::PendingDeposits,
+                LedgerAccount::StakerPrincipal,
+                amount,
+            )?;
+            split_large_stake(state, user_stake, amount)?
+        } else {
+            user_stake.record_asset_deposit(mint, amount, deposited)?;
+            amount
+        };
+        credit_stake(state, user_stake, credited, Clock::get()?.unix_timestamp)?;
+        let user_stake_key = ctx.accounts.user_stake.key();
+        stake_index::sync(&ctx.accounts.stake_index, &mut ctx.accounts.user_stake, user_stake_key)?;
 
-        // Enforce a minimum staking duration to help prevent flash loan exploits.
-        let now = Clock::get()?.unix_timestamp;
-        require!(
-            now.checked_sub(user_stake.last_update).unwrap() >= MIN_STAKE_DURATION,
-            CustomError::EarlyUnstakeNotAllowed
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(PendingDepositAdmitted {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            id: ctx.accounts.pending_deposit.id,
+            amount,
+        });
+        emit_pending_tranche(
+            &mut ctx.accounts.state,
+            ctx.accounts.user.key(),
+            &ctx.accounts.user_stake,
+            amount - credited,
+        )
+    }
+
+    // Depositor: leave the deposit queue from its head, taking back the tokens held for the
+    // queued deposit.
+    pub fn cancel_pending_deposit(ctx: Context<CancelPendingDeposit>) -> Result<()> {
+        let pending_deposit = &ctx.accounts.pending_deposit;
+        let (amount, mint, deposited) =
+            (pending_deposit.amount, pending_deposit.mint, pending_deposit.deposited);
+        let state_key = ctx.accounts.state.key();
+        let (source_vault, _) = if mint == ctx.accounts.state.base_mint {
+            Pubkey::find_program_address(&[b"vault", state_key.as_ref()], ctx.program_id)
+        } else {
+            Pubkey::find_program_address(
+                &[b"collateral_vault", state_key.as_ref(), mint.as_ref()],
+                ctx.program_id,
+            )
+        };
+        require_keys_eq!(
+            ctx.accounts.vault_account.key(),
+            source_vault,
+            CustomError::InvalidVaultOwner
         );
+        if mint == ctx.accounts.state.base_mint {
+            ledger::post(
+                &ctx.accounts.ledger,
+                LedgerAccount::PendingDeposits,
+                LedgerAccount::Vault,
+                deposited,
+            )?;
+        } else {
+            ctx.accounts.collateral_registry.track_deposit(mint, 0, deposited)?;
+        }
 
-        user_stake.amount = user_stake.amount.checked_sub(amount).unwrap();
         let state = &mut ctx.accounts.state;
-        state.total_staked = state.total_staked.checked_sub(amount).unwrap();
+        state.pending_deposits = state
+            .pending_deposits
+            .checked_sub(amount)
+            .ok_or(CustomError::MathOverflow)?;
+        state.pending_deposit_head = state
+            .pending_deposit_head
+            .checked_add(1)
+            .ok_or(CustomError::MathOverflow)?;
+        let user_stake = &mut ctx.accounts.user_stake;
+        user_stake.queued_deposits = user_stake
+            .queued_deposits
+            .checked_sub(1)
+            .ok_or(CustomError::MathOverflow)?;
 
-        // Prepare PDA seeds for signing.
-        let seeds = &[b"state", ctx.accounts.state_owner.key.as_ref(), &[state.bump]];
-        let signer = &[&seeds[..]];
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.vault_account.to_account_info(),
-            to: ctx.accounts.user_token_account.to_account_info(),
-            authority: ctx.accounts.state.to_account_info(),
-        };
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                cpi_accounts,
-                signer,
-            ),
-            amount,
+        transfer_from_vault(
+            &ctx.accounts.state,
+            &ctx.accounts.state_owner,
+            &ctx.accounts.vault_account,
+            &ctx.accounts.user_token_account,
+            &ctx.accounts.token_program,
+            deposited,
         )?;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(PendingDepositCancelled {
+            seq,
+            state: state_key,
+            user: ctx.accounts.user.key(),
+            id: ctx.accounts.pending_deposit.id,
+            mint,
+            refunded: deposited,
+        });
         Ok(())
     }
 
-    // Rebalance positions to maintain delta-neutral exposure.
-    pub fn rebalance(ctx: Context<Rebalance>) -> Result<()> {
+    // Permissionless: credit a stake's pending tranche once the keeper has confirmed the hedge
+    // since it was deposited, by a rebalance or an in-band delta report.
+    pub fn activate_pending_tranche(ctx: Context<ActivatePendingTranche>) -> Result<()> {
+        let user_stake = &mut ctx.accounts.user_stake;
+        let amount = user_stake.pending_tranche;
+        require!(amount > 0, CustomError::InvalidParameter);
+        require!(
+            ctx.accounts.state.last_hedged_at > user_stake.pending_tranche_after,
+            CustomError::TranchePending
+        );
+        user_stake.pending_tranche = 0;
         let state = &mut ctx.accounts.state;
-        state.last_rebalance = Clock::get()?.unix_timestamp;
+        state.pending_tranches = state
+            .pending_tranches
+            .checked_sub(amount)
+            .ok_or(CustomError::MathOverflow)?;
+        credit_stake(state, &mut ctx.accounts.user_stake, amount, Clock::get()?.unix_timestamp)?;
+        let user_stake_key = ctx.accounts.user_stake.key();
+        stake_index::sync(&ctx.accounts.stake_index, &mut ctx.accounts.user_stake, user_stake_key)?;
+
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(PendingTrancheActivated {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            amount,
+        });
         Ok(())
     }
 
-    // Distribute rewards to staked participants.
-    // This simplified calculation multiplies the total stake by a reward rate and the staking duration.
-    pub fn distribute_rewards(ctx: Context<DistributeRewards>) -> Result<()> {
-        let current_time = Clock::get()?.unix_timestamp;
-        let duration = current_time.checked_sub(ctx.accounts.state.last_update).unwrap() as u64;
-        let reward_rate: u64 = 1; // Placeholder reward rate.
-        let reward_amount = ctx.accounts.state
-            .total_staked
-            .checked_mul(reward_rate)
-            .unwrap()
-            .checked_mul(duration)
-            .unwrap();
+    // Relayer-paid staking: the user approves the vault state PDA as delegate on their token
+    // account off-chain, and anyone may then submit the stake, paying the fees. Stake is always
+    // credited to the token account's owner. Anything over the strategy capacity is queued as in
+    // `stake`, with the relayer paying for the `PendingDeposit`.
+    pub fn stake_with_delegate(
+        ctx: Context<StakeWithDelegate>,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require_not_blocked(&ctx.accounts.blocked)?;
+        let new_user_amount = ctx
+            .accounts
+            .user_stake
+            .amount
+            .checked_add(amount)
+            .ok_or(CustomError::MathOverflow)?;
+        enforce_launch_phase(&ctx.accounts.state, ctx.accounts.user.key, &proof, new_user_amount)?;
+        require_accepting_stakes(&ctx.accounts.state, &ctx.accounts.delta_report)?;
+        require!(
+            ctx.accounts.user_token_account.delegated_amount >= amount,
+            CustomError::InsufficientDelegation
+        );
 
-        mint_rewards(
+        // The state PDA signs as the approved delegate.
+        transfer_from_vault(
             &ctx.accounts.state,
             &ctx.accounts.state_owner,
-            &ctx.accounts.token_mint,
-            &ctx.accounts.rewards_account,
+            &ctx.accounts.user_token_account,
+            &ctx.accounts.vault_account,
             &ctx.accounts.token_program,
-            reward_amount,
+            amount,
+        )?;
+
+        let admitted = amount.min(capacity_headroom(&ctx.accounts.state));
+        let queued = amount - admitted;
+        if let Some(pending_deposit) = pending_deposit_slot(
+            &mut ctx.accounts.pending_deposit,
+            ctx.bumps.pending_deposit,
+            queued,
+        )? {
+            let base_mint = ctx.accounts.state.base_mint;
+            queue_deposit(
+                &mut ctx.accounts.state,
+                &mut ctx.accounts.user_stake,
+                pending_deposit,
+                ctx.accounts.user.key(),
+                base_mint,
+                queued,
+                queued,
+            )?;
+        }
+        let credited =
+            split_large_stake(&mut ctx.accounts.state, &mut ctx.accounts.user_stake, admitted)?;
+        credit_stake(
+            &mut ctx.accounts.state,
+            &mut ctx.accounts.user_stake,
+            credited,
+            Clock::get()?.unix_timestamp,
         )?;
-        ctx.accounts.state.last_update = current_time;
+        let user_stake_key = ctx.accounts.user_stake.key();
+        stake_index::sync(&ctx.accounts.stake_index, &mut ctx.accounts.user_stake, user_stake_key)?;
+        post_deposit(&ctx.accounts.ledger, admitted, queued)?;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(Staked {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            mint: ctx.accounts.user_token_account.mint,
+            deposited: amount,
+            stake_credited: credited,
+            reference: None,
+        });
+        emit_pending_tranche(
+            &mut ctx.accounts.state,
+            ctx.accounts.user.key(),
+            &ctx.accounts.user_stake,
+            admitted - credited,
+        )?;
+        emit_deposit_queued(&mut ctx.accounts.state, ctx.accounts.pending_deposit.as_deref())
+    }
+
+    // Integrators: stake base tokens from the authority's token accounts on behalf of many users in
+    // one transaction. `remaining_accounts` holds one (user_stake, source token account, blocklist
+    // PDA, stake index page, pending deposit) group per entry, in order. The pending deposit is
+    // the `PendingDeposit` PDA at the queue tail as of that entry; it is created, paid for by the
+    // authority, only when the entry exceeds the strategy capacity. During the whitelist phase
+    // each entry carries its user's merkle proof.
+    pub fn batch_stake<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchStake<'info>>,
+        entries: Vec<BatchStakeEntry>,
+    ) -> Result<()> {
+        require!(
+            !entries.is_empty() && ctx.remaining_accounts.len() == entries.len() * 5,
+            CustomError::InvalidBatchAccounts
+        );
+        require_accepting_stakes(&ctx.accounts.state, &ctx.accounts.delta_report)?;
+        let now = Clock::get()?.unix_timestamp;
+        let state_key = ctx.accounts.state.key();
+
+        for (entry, accounts) in entries.iter().zip(ctx.remaining_accounts.chunks(5)) {
+            let (user_stake_pda, _) = Pubkey::find_program_address(
+                &[b"user_stake", state_key.as_ref(), entry.user.as_ref()],
+                ctx.program_id,
+            );
+            let (blocked_pda, _) = Pubkey::find_program_address(
+                &[b"blocked", state_key.as_ref(), entry.user.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(accounts[0].key(), user_stake_pda, CustomError::InvalidBatchAccounts);
+            require_keys_eq!(accounts[2].key(), blocked_pda, CustomError::InvalidBatchAccounts);
+            require_not_blocked(&accounts[2])?;
+
+            let mut user_stake: Account<UserStake> = Account::try_from(&accounts[0])?;
+            let source: Account<TokenAccount> = Account::try_from(&accounts[1])?;
+            require_keys_eq!(
+                source.owner,
+                ctx.accounts.authority.key(),
+                CustomError::InvalidBatchAccounts
+            );
+            let new_user_amount = user_stake
+                .amount
+                .checked_add(entry.amount)
+                .ok_or(CustomError::MathOverflow)?;
+            enforce_launch_phase(&ctx.accounts.state, &entry.user, &entry.proof, new_user_amount)?;
+
+            let cpi_accounts = Transfer {
+                from: source.to_account_info(),
+                to: ctx.accounts.vault_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            };
+            token::transfer(
+                CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+                entry.amount,
+            )?;
+            let admitted = entry.amount.min(capacity_headroom(&ctx.accounts.state));
+            let queued = entry.amount - admitted;
+            let mut pending_deposit = None;
+            if queued > 0 {
+                let mut queued_deposit = create_pending_deposit(
+                    &ctx.accounts.state,
+                    &accounts[4],
+                    &ctx.accounts.authority,
+                    &ctx.accounts.system_program,
+                )?;
+                let base_mint = ctx.accounts.state.base_mint;
+                queue_deposit(
+                    &mut ctx.accounts.state,
+                    &mut user_stake,
+                    &mut queued_deposit,
+                    entry.user,
+                    base_mint,
+                    queued,
+                    queued,
+                )?;
+                queued_deposit.try_serialize(&mut &mut accounts[4].try_borrow_mut_data()?[..])?;
+                pending_deposit = Some(queued_deposit);
+            }
+            let credited = split_large_stake(&mut ctx.accounts.state, &mut user_stake, admitted)?;
+            credit_stake(&mut ctx.accounts.state, &mut user_stake, credited, now)?;
+            stake_index::sync_page_account(
+                state_key,
+                &accounts[3],
+                &mut user_stake,
+                user_stake_pda,
+            )?;
+            user_stake.exit(ctx.program_id)?;
+            post_deposit(&ctx.accounts.ledger, admitted, queued)?;
+
+            let seq = next_event_seq(&mut ctx.accounts.state)?;
+            emit!(Staked {
+                seq,
+                state: state_key,
+                user: entry.user,
+                mint: source.mint,
+                deposited: entry.amount,
+                stake_credited: credited,
+                reference: None,
+            });
+            emit_pending_tranche(
+                &mut ctx.accounts.state,
+                entry.user,
+                &user_stake,
+                admitted - credited,
+            )?;
+            emit_deposit_queued(&mut ctx.accounts.state, pending_deposit.as_ref())?;
+        }
         Ok(())
     }
 
-    // 1️⃣ Dynamic Funding Rate Distribution.
-    // Adjust rewards based on real-time funding rates from the perpetual futures market.
-    pub fn update_rewards_based_on_funding(ctx: Context<UpdateRewards>) -> Result<()> {
-        let funding_rate = get_funding_rate_from_oracle()?;
-        let reward_amount = ctx.accounts.state
-            .total_staked
-            .checked_mul(funding_rate as u64)
-            .unwrap()
-            .checked_div(100)
-            .unwrap();
-        mint_rewards(
-            &ctx.accounts.state,
-            &ctx.accounts.state_owner,
-            &ctx.accounts.token_mint,
-            &ctx.accounts.rewards_account,
+    // Integrators: unstake for many users in one transaction. `remaining_accounts` holds one
+    // (user, user_stake, destination token account, the stake's index page) group per amount;
+    // every user must sign.
+    // Amounts over the epoch outflow budget are queued exactly as in `unstake`.
+    pub fn batch_unstake<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchUnstake<'info>>,
+        amounts: Vec<u64>,
+    ) -> Result<()> {
+        require!(
+            !amounts.is_empty() && ctx.remaining_accounts.len() == amounts.len() * 4,
+            CustomError::InvalidBatchAccounts
+        );
+        let clock = Clock::get()?;
+        let state_key = ctx.accounts.state.key();
+
+        for (&amount, accounts) in amounts.iter().zip(ctx.remaining_accounts.chunks(4)) {
+            let user = &accounts[0];
+            require!(user.is_signer, CustomError::Unauthorized);
+            let (user_stake_pda, _) = Pubkey::find_program_address(
+                &[b"user_stake", state_key.as_ref(), user.key.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(accounts[1].key(), user_stake_pda, CustomError::InvalidBatchAccounts);
+            let mut user_stake: Account<UserStake> = Account::try_from(&accounts[1])?;
+            let destination: Account<TokenAccount> = Account::try_from(&accounts[2])?;
+            require_keys_eq!(destination.owner, user.key(), CustomError::InvalidBatchAccounts);
+            require_keys_eq!(
+                destination.mint,
+                ctx.accounts.state.base_mint,
+                CustomError::InvalidVaultMint
+            );
+
+            let paid_now = debit_unstake(
+                &mut ctx.accounts.state,
+                &mut user_stake,
+                amount,
+                clock.unix_timestamp,
+                clock.epoch,
+            )?;
+            stake_index::sync_page_account(
+                state_key,
+                &accounts[3],
+                &mut user_stake,
+                user_stake_pda,
+            )?;
+            user_stake.exit(ctx.program_id)?;
+            if !paid_now {
+                let seq = next_event_seq(&mut ctx.accounts.state)?;
+                emit!(WithdrawalQueued { seq, state: state_key, user: user.key(), amount });
+                continue;
+            }
+
+            let fee =
+                base_withdraw_fee(&ctx.accounts.collateral_registry, &ctx.accounts.state, amount)?;
+            let net_amount = amount.checked_sub(fee).ok_or(CustomError::MathOverflow)?;
+            transfer_from_vault(
+                &ctx.accounts.state,
+                &ctx.accounts.state_owner,
+                &ctx.accounts.vault_account,
+                &destination,
+                &ctx.accounts.token_program,
+                net_amount,
+            )?;
+            ledger::post(
+                &ctx.accounts.ledger,
+                LedgerAccount::StakerPrincipal,
+                LedgerAccount::Vault,
+                net_amount,
+            )?;
+            ledger::post(
+                &ctx.accounts.ledger,
+                LedgerAccount::StakerPrincipal,
+                LedgerAccount::FeeRevenue,
+                fee,
+            )?;
+            if fee > 0 {
+                transfer_from_vault(
+                    &ctx.accounts.state,
+                    &ctx.accounts.state_owner,
+                    &ctx.accounts.vault_account,
+                    &ctx.accounts.fee_vault,
+                    &ctx.accounts.token_program,
+                    fee,
+                )?;
+                book_fee(&mut ctx.accounts.state, ctx.accounts.vault_account.mint, fee)?;
+                let seq = next_event_seq(&mut ctx.accounts.state)?;
+                emit!(FeeCollected {
+                    seq,
+                    state: state_key,
+                    user: user.key(),
+                    mint: ctx.accounts.state.base_mint,
+                    amount: fee,
+                });
+            }
+            let seq = next_event_seq(&mut ctx.accounts.state)?;
+            emit!(Unstaked { seq, state: state_key, user: user.key(), amount });
+        }
+        Ok(())
+    }
+
+    // Stake native SOL. The lamports are moved straight into the WSOL collateral vault and wrapped
+    // in place with `sync_native`, so the user never handles wrapped SOL. WSOL must be registered
+    // as collateral; it is priced and charged fees like any other collateral.
+    pub fn stake_sol(ctx: Context<StakeSol>, lamports: u64, proof: Vec<[u8; 32]>) -> Result<()> {
+        require_not_blocked(&ctx.accounts.blocked)?;
+        let config = ctx
+            .accounts
+            .collateral_registry
+            .collaterals
+            .iter()
+            .find(|c| c.mint == native_mint::ID)
+            .ok_or(CustomError::UnknownCollateral)?;
+        require!(config.deposits_enabled, CustomError::CollateralDisabled);
+        let cache = price_cache::load(&ctx.accounts.state.key(), ctx.remaining_accounts)?;
+        let (fee, normalized_amount) =
+            price_collateral_deposit(config, None, cache.as_ref(), lamports)?;
+        let net_lamports = lamports.checked_sub(fee).ok_or(CustomError::MathOverflow)?;
+
+        let new_user_amount = ctx
+            .accounts
+            .user_stake
+            .amount
+            .checked_add(normalized_amount)
+            .ok_or(CustomError::MathOverflow)?;
+        enforce_launch_phase(&ctx.accounts.state, ctx.accounts.user.key, &proof, new_user_amount)?;
+        require_accepting_stakes(&ctx.accounts.state, &ctx.accounts.delta_report)?;
+        // Anything over the strategy capacity joins the deposit queue, holding its share of the
+        // deposited tokens.
+        let admitted = normalized_amount.min(capacity_headroom(&ctx.accounts.state));
+        let queued = normalized_amount - admitted;
+        let held = if queued > 0 {
+            mul_div_u64(net_lamports, queued, normalized_amount, Rounding::Down)
+                .ok_or(CustomError::MathOverflow)?
+        } else {
+            0
+        };
+        if let Some(pending_deposit) = pending_deposit_slot(
+            &mut ctx.accounts.pending_deposit,
+            ctx.bumps.pending_deposit,
+            queued,
+        )? {
+            queue_deposit(
+                &mut ctx.accounts.state,
+                &mut ctx.accounts.user_stake,
+                pending_deposit,
+                ctx.accounts.user.key(),
+                native_mint::ID,
+                held,
+                queued,
+            )?;
+        }
+        credit_stake(
+            &mut ctx.accounts.state,
+            &mut ctx.accounts.user_stake,
+            admitted,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let user_stake_key = ctx.accounts.user_stake.key();
+        stake_index::sync(&ctx.accounts.stake_index, &mut ctx.accounts.user_stake, user_stake_key)?;
+        ctx.accounts.user_stake.record_asset_deposit(
+            native_mint::ID,
+            admitted,
+            net_lamports - held,
+        )?;
+        ctx.accounts.collateral_registry.track_deposit(native_mint::ID, net_lamports, 0)?;
+
+        wrap_lamports(
+            &ctx.accounts.user,
+            &ctx.accounts.sol_vault,
+            &ctx.accounts.system_program,
             &ctx.accounts.token_program,
-            reward_amount,
+            net_lamports,
         )?;
-        Ok(())
+        if fee > 0 {
+            wrap_lamports(
+                &ctx.accounts.user,
+                &ctx.accounts.fee_vault,
+                &ctx.accounts.system_program,
+                &ctx.accounts.token_program,
+                fee,
+            )?;
+            book_fee(&mut ctx.accounts.state, native_mint::ID, fee)?;
+            let seq = next_event_seq(&mut ctx.accounts.state)?;
+            emit!(FeeCollected {
+                seq,
+                state: ctx.accounts.state.key(),
+                user: ctx.accounts.user.key(),
+                mint: native_mint::ID,
+                amount: fee,
+            });
+        }
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(Staked {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            mint: native_mint::ID,
+            deposited: lamports,
+            stake_credited: admitted,
+            reference: None,
+        });
+        emit_deposit_queued(&mut ctx.accounts.state, ctx.accounts.pending_deposit.as_deref())
     }
 
-    // 3️⃣ Vault Profit Sharing.
-    // Distribute arbitrage profits from the vault to $DNT holders.
-    pub fn distribute_arbitrage_profits(ctx: Context<DistributeProfits>) -> Result<()> {
-        let total_profits = get_arbitrage_profits_from_vault()?;
-        mint_rewards(
+    // Unstake `shares` of stake deposited as SOL and receive native SOL. The shares' part of the
+    // lamports deposited is moved from the WSOL vault into a temporary state-owned WSOL account,
+    // which is closed to the user to unwrap it.
+    // Withdrawals that would exceed the epoch outflow budget are rejected rather than queued.
+    pub fn unstake_sol(ctx: Context<UnstakeSol>, shares: u64) -> Result<()> {
+        let config = ctx
+            .accounts
+            .collateral_registry
+            .collaterals
+            .iter()
+            .find(|c| c.mint == native_mint::ID)
+            .ok_or(CustomError::UnknownCollateral)?;
+        let withdraw_fee_bps = config.withdraw_fee_bps;
+        // Only stake deposited as SOL redeems in SOL, for its share of the lamports deposited.
+        require!(
+            ctx.accounts.user_stake.asset_deposit(native_mint::ID) >= shares,
+            CustomError::InsufficientStake
+        );
+        let lamports = ctx.accounts.user_stake.release_asset_deposit(native_mint::ID, shares)?;
+        let fee = bps_of(lamports, withdraw_fee_bps)?;
+
+        let clock = Clock::get()?;
+        let paid_now = debit_unstake(
+            &mut ctx.accounts.state,
+            &mut ctx.accounts.user_stake,
+            shares,
+            clock.unix_timestamp,
+            clock.epoch,
+        )?;
+        require!(paid_now, CustomError::OutflowLimitReached);
+        let user_stake_key = ctx.accounts.user_stake.key();
+        stake_index::sync(&ctx.accounts.stake_index, &mut ctx.accounts.user_stake, user_stake_key)?;
+        ctx.accounts.collateral_registry.track_deposit(native_mint::ID, 0, lamports)?;
+
+        transfer_from_vault(
             &ctx.accounts.state,
             &ctx.accounts.state_owner,
-            &ctx.accounts.token_mint,
-            &ctx.accounts.rewards_account,
+            &ctx.accounts.sol_vault,
+            &ctx.accounts.unwrap_account,
             &ctx.accounts.token_program,
-            total_profits,
+            lamports.checked_sub(fee).ok_or(CustomError::MathOverflow)?,
         )?;
+        if fee > 0 {
+            transfer_from_vault(
+                &ctx.accounts.state,
+                &ctx.accounts.state_owner,
+                &ctx.accounts.sol_vault,
+                &ctx.accounts.fee_vault,
+                &ctx.accounts.token_program,
+                fee,
+            )?;
+            book_fee(&mut ctx.accounts.state, native_mint::ID, fee)?;
+            let seq = next_event_seq(&mut ctx.accounts.state)?;
+            emit!(FeeCollected {
+                seq,
+                state: ctx.accounts.state.key(),
+                user: ctx.accounts.user.key(),
+                mint: native_mint::ID,
+                amount: fee,
+            });
+        }
+
+        // Closing the temporary WSOL account releases its balance and rent to the user as SOL.
+        let vault_id = ctx.accounts.state.vault_id.to_le_bytes();
+        let seeds = &[
+            b"state".as_ref(),
+            ctx.accounts.state_owner.key.as_ref(),
+            vault_id.as_ref(),
+            &[ctx.accounts.state.bump],
+        ];
+        let signer = &[&seeds[..]];
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.unwrap_account.to_account_info(),
+                destination: ctx.accounts.user.to_account_info(),
+                authority: ctx.accounts.state.to_account_info(),
+            },
+            signer,
+        ))?;
+
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(Unstaked {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            amount: shares,
+        });
         Ok(())
     }
 
-    // 4️⃣ Liquidity Incentives for Market Makers.
-    // Reward market makers who provide deep liquidity.
-    pub fn reward_liquidity_providers(ctx: Context<RewardMakers>) -> Result<()> {
-        let maker_volume = get_maker_trading_volume()?;
-        let reward_amount = maker_volume.checked_div(1000).unwrap();
-        mint_rewards(
+    // Stake using multiple collateral types (e.g., SOL, USDC, USDT).
+    // `asset_type` indexes the collateral registry; only enabled collateral accepts deposits.
+    pub fn stake_with_multiple_assets(
+        ctx: Context<MultiCollateralStakeAccounts>,
+        asset_type: u8,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require_not_blocked(&ctx.accounts.blocked)?;
+        let config = ctx
+            .accounts
+            .collateral_registry
+            .collaterals
+            .get(asset_type as usize)
+            .ok_or(CustomError::UnknownCollateral)?;
+        require!(config.deposits_enabled, CustomError::CollateralDisabled);
+        require_keys_eq!(
+            ctx.accounts.user_token_account.mint,
+            config.mint,
+            CustomError::CollateralMintMismatch
+        );
+
+        // Convert the provided amount, net of the deposit fee, to a normalized, risk-weighted value.
+        // A price cache refreshed this slot, if passed as a remaining account, stands in for the
+        // oracle reads.
+        let stake_pool = ctx.accounts.lst_stake_pool.as_ref().map(|a| a.to_account_info());
+        let cache = price_cache::load(&ctx.accounts.state.key(), ctx.remaining_accounts)?;
+        let (fee, normalized_amount) =
+            price_collateral_deposit(config, stake_pool.as_ref(), cache.as_ref(), amount)?;
+        let net_amount = amount.checked_sub(fee).ok_or(CustomError::MathOverflow)?;
+
+        let new_user_amount = ctx
+            .accounts
+            .user_stake
+            .amount
+            .checked_add(normalized_amount)
+            .ok_or(CustomError::MathOverflow)?;
+        enforce_launch_phase(&ctx.accounts.state, ctx.accounts.user.key, &proof, new_user_amount)?;
+        require_accepting_stakes(&ctx.accounts.state, &ctx.accounts.delta_report)?;
+        let mint = ctx.accounts.user_token_account.mint;
+
+        // Anything over the strategy capacity joins the deposit queue, holding its share of the
+        // deposited tokens.
+        let admitted = normalized_amount.min(capacity_headroom(&ctx.accounts.state));
+        let queued = normalized_amount - admitted;
+        let held = if queued > 0 {
+            mul_div_u64(net_amount, queued, normalized_amount, Rounding::Down)
+                .ok_or(CustomError::MathOverflow)?
+        } else {
+            0
+        };
+        if let Some(pending_deposit) = pending_deposit_slot(
+            &mut ctx.accounts.pending_deposit,
+            ctx.bumps.pending_deposit,
+            queued,
+        )? {
+            queue_deposit(
+                &mut ctx.accounts.state,
+                &mut ctx.accounts.user_stake,
+                pending_deposit,
+                ctx.accounts.user.key(),
+                mint,
+                held,
+                queued,
+            )?;
+        }
+        credit_stake(
+            &mut ctx.accounts.state,
+            &mut ctx.accounts.user_stake,
+            admitted,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let user_stake_key = ctx.accounts.user_stake.key();
+        stake_index::sync(&ctx.accounts.stake_index, &mut ctx.accounts.user_stake, user_stake_key)?;
+        ctx.accounts.user_stake.record_asset_deposit(mint, admitted, net_amount - held)?;
+        ctx.accounts.collateral_registry.track_deposit(mint, net_amount, 0)?;
+
+        // Transfer the provided tokens from the user to the vault.
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.user_token_account.to_account_info(),
+            to: ctx.accounts.vault_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            net_amount,
+        )?;
+        if fee > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.user_token_account.to_account_info(),
+                to: ctx.accounts.fee_vault.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            };
+            token::transfer(
+                CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+                fee,
+            )?;
+            book_fee(&mut ctx.accounts.state, ctx.accounts.user_token_account.mint, fee)?;
+            let seq = next_event_seq(&mut ctx.accounts.state)?;
+            emit!(FeeCollected {
+                seq,
+                state: ctx.accounts.state.key(),
+                user: ctx.accounts.user.key(),
+                mint: ctx.accounts.user_token_account.mint,
+                amount: fee,
+            });
+        }
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(Staked {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            mint: ctx.accounts.user_token_account.mint,
+            deposited: amount,
+            stake_credited: admitted,
+            reference: None,
+        });
+        emit_deposit_queued(&mut ctx.accounts.state, ctx.accounts.pending_deposit.as_deref())
+    }
+
+    // Unstake `amount` shares that came from deposits of the `asset_type` collateral and receive
+    // that collateral back, valued at its current price, instead of the base asset. Withdrawals
+    // that would exceed the epoch outflow budget are rejected rather than queued.
+    pub fn unstake_asset(ctx: Context<UnstakeAsset>, asset_type: u8, amount: u64) -> Result<()> {
+        let config = ctx
+            .accounts
+            .collateral_registry
+            .collaterals
+            .get(asset_type as usize)
+            .ok_or(CustomError::UnknownCollateral)?;
+        require_keys_eq!(
+            ctx.accounts.user_token_account.mint,
+            config.mint,
+            CustomError::CollateralMintMismatch
+        );
+        let mint = config.mint;
+        let stake_pool = ctx.accounts.lst_stake_pool.as_ref().map(|a| a.to_account_info());
+        let price = get_conversion_rate(config, stake_pool.as_ref())?;
+        require!(price > 0, CustomError::InvalidParameter);
+        let tokens = mul_div_u64(amount, PRICE_PRECISION, price, Rounding::Down)
+            .ok_or(CustomError::MathOverflow)?;
+        let fee = bps_of(tokens, config.withdraw_fee_bps)?;
+        require!(
+            ctx.accounts.user_stake.asset_deposit(mint) >= amount,
+            CustomError::InsufficientStake
+        );
+
+        let clock = Clock::get()?;
+        let paid_now = debit_unstake(
+            &mut ctx.accounts.state,
+            &mut ctx.accounts.user_stake,
+            amount,
+            clock.unix_timestamp,
+            clock.epoch,
+        )?;
+        require!(paid_now, CustomError::OutflowLimitReached);
+        let user_stake_key = ctx.accounts.user_stake.key();
+        stake_index::sync(&ctx.accounts.stake_index, &mut ctx.accounts.user_stake, user_stake_key)?;
+        ctx.accounts.user_stake.release_asset_deposit(mint, amount)?;
+        ctx.accounts.collateral_registry.track_deposit(mint, 0, tokens)?;
+
+        transfer_from_vault(
             &ctx.accounts.state,
             &ctx.accounts.state_owner,
-            &ctx.accounts.token_mint,
-            &ctx.accounts.rewards_account,
+            &ctx.accounts.vault_account,
+            &ctx.accounts.user_token_account,
             &ctx.accounts.token_program,
-            reward_amount,
+            tokens.checked_sub(fee).ok_or(CustomError::MathOverflow)?,
         )?;
+        if fee > 0 {
+            transfer_from_vault(
+                &ctx.accounts.state,
+                &ctx.accounts.state_owner,
+                &ctx.accounts.vault_account,
+                &ctx.accounts.fee_vault,
+                &ctx.accounts.token_program,
+                fee,
+            )?;
+            book_fee(&mut ctx.accounts.state, mint, fee)?;
+            let seq = next_event_seq(&mut ctx.accounts.state)?;
+            emit!(FeeCollected {
+                seq,
+                state: ctx.accounts.state.key(),
+                user: ctx.accounts.user.key(),
+                mint,
+                amount: fee,
+            });
+        }
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(Unstaked {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            amount,
+        });
         Ok(())
     }
 
-    // 6️⃣ Automated Liquidations & Risk Management.
-    // Liquidate traders if their loss exceeds the maximum allowed threshold.
-    pub fn auto_liquidate(ctx: Context<Liquidate>) -> Result<()> {
-        let user_position = get_user_position(ctx.accounts.user.key)?;
-        if user_position.loss_percentage > MAX_ALLOWED_LOSS {
-            force_close_position(&ctx)?;
-            update_state_after_liquidation(&ctx)?;
-        }
+    // Create the empty collateral registry for a vault.
+    pub fn initialize_collateral_registry(ctx: Context<InitializeCollateralRegistry>) -> Result<()> {
+        let registry = &mut ctx.accounts.collateral_registry;
+        registry.bump = ctx.bumps.collateral_registry;
+        registry.state = ctx.accounts.state.key();
+        registry.collaterals = Vec::new();
         Ok(())
     }
 
-    // 8️⃣ Staked Voting (Governance).
-    // Allow staked $DNT holders to vote on protocol risk parameters.
-    pub fn vote_on_risk_params(ctx: Context<Vote>, new_threshold: u64) -> Result<()> {
-        let total_votes = get_total_votes()?;
-        let yes_votes = get_yes_votes()?;
+    // Governance: register a new collateral type. The registry grows by one entry via realloc.
+    // For liquid staking tokens, `oracle` is the SOL/USD feed and the kind carries the SPL stake
+    // pool whose exchange rate converts the LST to SOL. Stablecoins are priced at a fixed $1
+    // within the depeg band.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_collateral(
+        ctx: Context<AddCollateral>,
+        mint: Pubkey,
+        oracle: Pubkey,
+        weight_bps: u64,
+        kind: CollateralKind,
+        deposit_fee_bps: u64,
+        withdraw_fee_bps: u64,
+        max_leverage_bps: u64,
+    ) -> Result<()> {
         require!(
-            yes_votes * 100 / total_votes >= 60,
-            CustomError::NotEnoughVotes
+            weight_bps > 0 && weight_bps <= BPS_DENOMINATOR,
+            CustomError::InvalidParameter
         );
-        let state = &mut ctx.accounts.state;
-        state.allowed_delta_threshold = new_threshold;
+        let (lst_stake_pool, is_stable) = match kind {
+            CollateralKind::Standard => (Pubkey::default(), false),
+            CollateralKind::Stable => (Pubkey::default(), true),
+            CollateralKind::LiquidStaking { stake_pool } => {
+                require!(stake_pool != Pubkey::default(), CustomError::InvalidParameter);
+                (stake_pool, false)
+            }
+        };
+        require!(
+            (BPS_DENOMINATOR..=MAX_LEVERAGE_LIMIT_BPS).contains(&max_leverage_bps),
+            CustomError::InvalidParameter
+        );
+        RiskParams { max_leverage_bps, ..RiskParams::of(&ctx.accounts.state) }.check()?;
+        require!(
+            deposit_fee_bps <= MAX_COLLATERAL_FEE_BPS && withdraw_fee_bps <= MAX_COLLATERAL_FEE_BPS,
+            CustomError::InvalidParameter
+        );
+        let registry = &mut ctx.accounts.collateral_registry;
+        require!(
+            !registry.collaterals.iter().any(|c| c.mint == mint),
+            CustomError::CollateralAlreadyRegistered
+        );
+        registry.collaterals.push(CollateralConfig {
+            mint,
+            oracle,
+            weight_bps,
+            deposits_enabled: true,
+            lst_stake_pool,
+            deposit_fee_bps,
+            withdraw_fee_bps,
+            max_leverage_bps,
+            is_stable,
+            deposited_amount: 0,
+            usd_value_at_last_mark: 0,
+        });
         Ok(())
     }
-}
 
-// -----------------------------------------------------------------------------
-// Global State & User Stake Accounts
-// -----------------------------------------------------------------------------
+    // Governance: deprecate a collateral type. New deposits are rejected, but the entry (and
+    // any balances held in it) stays so existing depositors can still withdraw.
+    pub fn remove_collateral(ctx: Context<RemoveCollateral>, mint: Pubkey) -> Result<()> {
+        let config = ctx
+            .accounts
+            .collateral_registry
+            .collaterals
+            .iter_mut()
+            .find(|c| c.mint == mint)
+            .ok_or(CustomError::UnknownCollateral)?;
+        config.deposits_enabled = false;
+        Ok(())
+    }
 
+    // Governance: update the deposit and withdraw fees charged on a collateral.
+    pub fn set_collateral_fees(
+        ctx: Context<SetCollateralFees>,
+        mint: Pubkey,
+        deposit_fee_bps: u64,
+        withdraw_fee_bps: u64,
+    ) -> Result<()> {
+        require!(
+            deposit_fee_bps <= MAX_COLLATERAL_FEE_BPS && withdraw_fee_bps <= MAX_COLLATERAL_FEE_BPS,
+            CustomError::InvalidParameter
+        );
+        let config = ctx
+            .accounts
+            .collateral_registry
+            .collaterals
+            .iter_mut()
+            .find(|c| c.mint == mint)
+            .ok_or(CustomError::UnknownCollateral)?;
+        config.deposit_fee_bps = deposit_fee_bps;
+        config.withdraw_fee_bps = withdraw_fee_bps;
+        Ok(())
+    }
+
+    // Create the fee vault collecting deposit and withdraw fees for a mint.
+    pub fn initialize_fee_vault(_ctx: Context<InitializeFeeVault>) -> Result<()> {
+        Ok(())
+    }
+
+    // Preview of `stake_with_multiple_assets`: the fee and stake credited for depositing `amount`
+    // of the `asset_type` collateral. Meant to be simulated; the quote is returned as return data.
+    pub fn quote_stake(ctx: Context<QuoteStake>, amount: u64, asset_type: u8) -> Result<StakeQuote> {
+        let config = ctx
+            .accounts
+            .collateral_registry
+            .collaterals
+            .get(asset_type as usize)
+            .ok_or(CustomError::UnknownCollateral)?;
+        require!(config.deposits_enabled, CustomError::CollateralDisabled);
+        let stake_pool = ctx.accounts.lst_stake_pool.as_ref().map(|a| a.to_account_info());
+        let (fee, shares_out) =
+            price_collateral_deposit(config, stake_pool.as_ref(), None, amount)?;
+        Ok(StakeQuote { fee, shares_out, nav: ctx.accounts.state.nav() })
+    }
+
+    // Preview of `unstake`: the withdraw fee and base tokens paid out for `shares`.
+    pub fn quote_unstake(ctx: Context<QuoteUnstake>, shares: u64) -> Result<UnstakeQuote> {
+        let fee = base_withdraw_fee(&ctx.accounts.collateral_registry, &ctx.accounts.state, shares)?;
+        Ok(UnstakeQuote {
+            fee,
+            tokens_out: shares.checked_sub(fee).ok_or(CustomError::MathOverflow)?,
+            nav: ctx.accounts.state.nav(),
+        })
+    }
+
+    // CPI view for integrators such as lending markets valuing vault shares as collateral. Reads
+    // only the state account; `marked_at` lets callers reject a stale mark.
+    pub fn read_nav(ctx: Context<ReadVault>) -> Result<NavView> {
+        let state = &ctx.accounts.state;
+        Ok(NavView {
+            nav: state.nav(),
+            nav_per_share: state.nav_per_share()?,
+            total_shares: state.total_staked,
+            marked_at: state.last_rebalance,
+        })
+    }
+
+    // View for tokenomics dashboards: $DNT minted so far against the vault's reward mint cap.
+    pub fn remaining_emissions(ctx: Context<ReadVault>) -> Result<EmissionsView> {
+        let state = &ctx.accounts.state;
+        Ok(EmissionsView {
+            total_minted: state.total_rewards_minted,
+            cap: state.reward_mint_cap,
+            remaining: state.remaining_emissions(),
+        })
+    }
+
+    // Permissionless: record this epoch's NAV per share, once per epoch, for `estimate_apy`.
+    pub fn snapshot_epoch(ctx: Context<SnapshotEpoch>) -> Result<()> {
+        let clock = Clock::get()?;
+        let snapshot = &mut ctx.accounts.snapshot;
+        snapshot.bump = ctx.bumps.snapshot;
+        snapshot.state = ctx.accounts.state.key();
+        snapshot.epoch = clock.epoch;
+        snapshot.nav_per_share = ctx.accounts.state.nav_per_share()?;
+        snapshot.recorded_at = clock.unix_timestamp;
+        Ok(())
+    }
+
+    // View: annualized return in bps implied by the change in NAV per share across the epoch
+    // snapshots in `remaining_accounts` from the last `lookback_epochs` epochs. Meant to be
+    // simulated; the estimate is returned as return data.
+    pub fn estimate_apy<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReadVault<'info>>,
+        lookback_epochs: u64,
+    ) -> Result<i64> {
+        require!(lookback_epochs > 0, CustomError::InvalidParameter);
+        let state_key = ctx.accounts.state.key();
+        let oldest_epoch = Clock::get()?.epoch.saturating_sub(lookback_epochs);
+        let mut oldest: Option<EpochSnapshot> = None;
+        let mut newest: Option<EpochSnapshot> = None;
+        for info in ctx.remaining_accounts.iter() {
+            let snapshot: Account<EpochSnapshot> = Account::try_from(info)?;
+            let (snapshot_pda, _) = Pubkey::find_program_address(
+                &[b"epoch_snapshot", state_key.as_ref(), &snapshot.epoch.to_le_bytes()],
+                ctx.program_id,
+            );
+            require_keys_eq!(info.key(), snapshot_pda, CustomError::InvalidBatchAccounts);
+            if snapshot.epoch < oldest_epoch {
+                continue;
+            }
+            if oldest.as_ref().is_none_or(|s| snapshot.epoch < s.epoch) {
+                oldest = Some((*snapshot).clone());
+            }
+            if newest.as_ref().is_none_or(|s| snapshot.epoch > s.epoch) {
+                newest = Some((*snapshot).clone());
+            }
+        }
+        let (oldest, newest) = oldest.zip(newest).ok_or(CustomError::InsufficientSnapshots)?;
+        let elapsed = newest.recorded_at.saturating_sub(oldest.recorded_at);
+        require!(elapsed > 0 && oldest.nav_per_share > 0, CustomError::InsufficientSnapshots);
+        let change = newest.nav_per_share as i128 - oldest.nav_per_share as i128;
+        let apy_bps = change * BPS_DENOMINATOR as i128 * SECONDS_PER_YEAR as i128
+            / (oldest.nav_per_share as i128 * elapsed as i128);
+        Ok(apy_bps.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+
+    // CPI view: current value in base tokens of `user`'s staked shares.
+    pub fn read_user_stake_value(ctx: Context<ReadUserStake>) -> Result<u64> {
+        ctx.accounts
+            .state
+            .shares_to_value(ctx.accounts.user_stake.amount)
+    }
+
+    // Create the parameter registry, seeded with the vault's current parameter values.
+    pub fn initialize_param_registry(ctx: Context<InitializeParamRegistry>) -> Result<()> {
+        let registry = &mut ctx.accounts.param_registry;
+        registry.bump = ctx.bumps.param_registry;
+        registry.state = ctx.accounts.state.key();
+        registry.entries = ParamKey::ALL
+            .iter()
+            .map(|&key| ParamEntry {
+                key,
+                value: key.read(&ctx.accounts.state),
+                pending: None,
+                activates_at: 0,
+            })
+            .collect();
+        Ok(())
+    }
+
+    // Check a proposed risk parameter bundle for internal consistency without touching any
+    // vault. Parameter changes and new collateral must pass the same check before they queue.
+    pub fn validate_risk_params(
+        _ctx: Context<ValidateRiskParams>,
+        params: RiskParams,
+    ) -> Result<()> {
+        params.check()
+    }
+
+    // Governance: queue a parameter change. It becomes active only after PARAM_TIMELOCK_SECS, so
+    // every change is visible on-chain before it takes effect. Replaces any pending change.
+    pub fn propose_param_change(
+        ctx: Context<ManageParams>,
+        key: ParamKey,
+        value: ParamValue,
+    ) -> Result<()> {
+        let activates_at =
+            queue_param_change(&ctx.accounts.state, &mut ctx.accounts.param_registry, key, value)?;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(ParamChangeProposed {
+            seq,
+            state: ctx.accounts.state.key(),
+            key,
+            value,
+            activates_at,
+        });
+        Ok(())
+    }
+
+    // Governance: drop a pending parameter change.
+    pub fn cancel_param_change(ctx: Context<ManageParams>, key: ParamKey) -> Result<()> {
+        let entry = ctx.accounts.param_registry.entry_mut(key)?;
+        require!(entry.pending.is_some(), CustomError::NoPendingParamChange);
+        entry.pending = None;
+        entry.activates_at = 0;
+        Ok(())
+    }
+
+    // Apply a pending parameter change once its timelock has passed. Anyone may crank this.
+    pub fn activate_param_change(ctx: Context<ActivateParamChange>, key: ParamKey) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let entry = ctx.accounts.param_registry.entry_mut(key)?;
+        let value = entry.pending.ok_or(CustomError::NoPendingParamChange)?;
+        require!(now >= entry.activates_at, CustomError::ParamTimelockActive);
+        entry.value = value;
+        entry.pending = None;
+        entry.activates_at = 0;
+
+        key.write(&mut ctx.accounts.state, value)?;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(ParamChangeActivated {
+            seq,
+            state: ctx.accounts.state.key(),
+            key,
+            value,
+        });
+        Ok(())
+    }
+
+    // Market-making strategy: configure the order book venue and market the vault quotes on.
+    pub fn initialize_market_making(
+        ctx: Context<InitializeMarketMaking>,
+        venue: QuoteVenue,
+        market: Pubkey,
+    ) -> Result<()> {
+        market_making::initialize(ctx, venue, market)
+    }
+
+    // Keeper: post two-sided resting quotes, replacing any live ones.
+    pub fn place_quotes(
+        ctx: Context<ManageQuotes>,
+        nonce: u64,
+        bid_price: u64,
+        ask_price: u64,
+        quote_size: u64,
+    ) -> Result<()> {
+        consume_keeper_nonce(&mut ctx.accounts.state, nonce)?;
+        market_making::place_quotes(ctx, bid_price, ask_price, quote_size)
+    }
+
+    // Keeper: pull the vault's resting quotes.
+    pub fn cancel_quotes(ctx: Context<ManageQuotes>, nonce: u64) -> Result<()> {
+        consume_keeper_nonce(&mut ctx.accounts.state, nonce)?;
+        market_making::cancel_quotes(ctx)
+    }
+
+    // Keeper: settle filled quotes and book the realized spread for distribution.
+    pub fn settle_fills(ctx: Context<ManageQuotes>, nonce: u64) -> Result<()> {
+        consume_keeper_nonce(&mut ctx.accounts.state, nonce)?;
+        market_making::settle_fills(ctx)
+    }
+
+    // Governance: grant `role` to `holder`. Each role holder has its own assignment PDA, so
+    // privileged actions taken under the role are attributable to that holder.
+    pub fn grant_role(ctx: Context<GrantRole>, role: Role, holder: Pubkey) -> Result<()> {
+        let assignment = &mut ctx.accounts.role_assignment;
+        assignment.bump = ctx.bumps.role_assignment;
+        assignment.state = ctx.accounts.state.key();
+        assignment.role = role;
+        assignment.holder = holder;
+        assignment.granted_by = ctx.accounts.state_owner.key();
+        assignment.granted_at = Clock::get()?.unix_timestamp;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(RoleGranted {
+            seq,
+            state: ctx.accounts.state.key(),
+            role,
+            holder,
+            granted_by: ctx.accounts.state_owner.key(),
+        });
+        Ok(())
+    }
+
+    // Governance: revoke a role assignment, closing its PDA.
+    pub fn revoke_role(ctx: Context<RevokeRole>, role: Role, holder: Pubkey) -> Result<()> {
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(RoleRevoked {
+            seq,
+            state: ctx.accounts.state.key(),
+            role,
+            holder,
+            revoked_by: ctx.accounts.state_owner.key(),
+        });
+        Ok(())
+    }
+
+    // Compliance: assign a staker's tier, e.g. after off-chain KYC.
+    pub fn set_user_tier(ctx: Context<SetUserTier>, tier: Tier) -> Result<()> {
+        ctx.accounts.user_stake.tier = tier;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(UserTierSet {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            tier,
+            set_by: ctx.accounts.authority.key(),
+        });
+        Ok(())
+    }
+
+    // Governance: add an address to the vault's blocklist. Blocked addresses cannot stake or
+    // claim rewards.
+    pub fn block_address(ctx: Context<BlockAddress>, address: Pubkey) -> Result<()> {
+        let blocked = &mut ctx.accounts.blocked;
+        blocked.bump = ctx.bumps.blocked;
+        blocked.state = ctx.accounts.state.key();
+        blocked.address = address;
+        blocked.blocked_at = Clock::get()?.unix_timestamp;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(AddressBlockChanged {
+            seq,
+            state: ctx.accounts.state.key(),
+            address,
+            blocked: true,
+        });
+        Ok(())
+    }
+
+    // Governance: remove an address from the blocklist, closing its entry.
+    pub fn unblock_address(ctx: Context<UnblockAddress>, address: Pubkey) -> Result<()> {
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(AddressBlockChanged {
+            seq,
+            state: ctx.accounts.state.key(),
+            address,
+            blocked: false,
+        });
+        Ok(())
+    }
+
+    // Create the basis-trade book for the vault's long spot / short perp hedge.
+    pub fn initialize_strategy_state(ctx: Context<InitializeStrategyState>) -> Result<()> {
+        let strategy = &mut ctx.accounts.strategy_state;
+        strategy.bump = ctx.bumps.strategy_state;
+        strategy.state = ctx.accounts.state.key();
+        strategy.last_update = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    // Create the PnL attribution bucket for one of the vault's strategies.
+    pub fn initialize_pnl_bucket(ctx: Context<InitializePnlBucket>, strategy: StrategyKind) -> Result<()> {
+        let bucket = &mut ctx.accounts.pnl_bucket;
+        bucket.bump = ctx.bumps.pnl_bucket;
+        bucket.state = ctx.accounts.state.key();
+        bucket.strategy = strategy;
+        bucket.last_update = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    // Treasury deposits protocol-owned liquidity. It earns no rewards and absorbs losses before
+    // stakers do.
+    pub fn seed_vault(ctx: Context<ProtocolLiquidity>, amount: u64) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        state.protocol_stake = state
+            .protocol_stake
+            .checked_add(amount)
+            .ok_or(CustomError::MathOverflow)?;
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.treasury_token_account.to_account_info(),
+            to: ctx.accounts.vault_account.to_account_info(),
+            authority: ctx.accounts.state_owner.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            amount,
+        )?;
+
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(ProtocolStakeChanged {
+            seq,
+            state: ctx.accounts.state.key(),
+            delta: amount as i64,
+            protocol_stake: ctx.accounts.state.protocol_stake,
+        });
+        Ok(())
+    }
+
+    // Governance: withdraw protocol-owned liquidity, limited to what is left after first-loss.
+    pub fn withdraw_protocol_stake(ctx: Context<ProtocolLiquidity>, amount: u64) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(amount <= state.protocol_equity(), CustomError::ExceedsCap);
+        state.protocol_stake = state
+            .protocol_stake
+            .checked_sub(amount)
+            .ok_or(CustomError::MathOverflow)?;
+
+        transfer_from_vault(
+            &ctx.accounts.state,
+            &ctx.accounts.state_owner,
+            &ctx.accounts.vault_account,
+            &ctx.accounts.treasury_token_account,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(ProtocolStakeChanged {
+            seq,
+            state: ctx.accounts.state.key(),
+            delta: -(amount as i64),
+            protocol_stake: ctx.accounts.state.protocol_stake,
+        });
+        Ok(())
+    }
+
+    // Unstake tokens and withdraw from the pool.
+    pub fn unstake(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+        let clock = Clock::get()?;
+        let paid_now = debit_unstake(
+            &mut ctx.accounts.state,
+            &mut ctx.accounts.user_stake,
+            amount,
+            clock.unix_timestamp,
+            clock.epoch,
+        )?;
+        let user_stake_key = ctx.accounts.user_stake.key();
+        stake_index::sync(&ctx.accounts.stake_index, &mut ctx.accounts.user_stake, user_stake_key)?;
+        if !paid_now {
+            let seq = next_event_seq(&mut ctx.accounts.state)?;
+            emit!(WithdrawalQueued {
+                seq,
+                state: ctx.accounts.state.key(),
+                user: ctx.accounts.user.key(),
+                amount,
+            });
+            return Ok(());
+        }
+
+        pay_withdrawal(ctx.accounts, None, amount)?;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(Unstaked {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            amount,
+        });
+        Ok(())
+    }
+
+    // Unstake straight to another token account of the base mint, e.g. a cold wallet or an
+    // exchange deposit address. Amounts beyond this epoch's outflow budget are queued as usual
+    // and later claimed to the user's own account.
+    pub fn unstake_to(ctx: Context<UnstakeTo>, amount: u64) -> Result<()> {
+        let clock = Clock::get()?;
+        let accounts = &mut ctx.accounts.withdrawal;
+        let paid_now = debit_unstake(
+            &mut accounts.state,
+            &mut accounts.user_stake,
+            amount,
+            clock.unix_timestamp,
+            clock.epoch,
+        )?;
+        let user_stake_key = accounts.user_stake.key();
+        stake_index::sync(&accounts.stake_index, &mut accounts.user_stake, user_stake_key)?;
+        if !paid_now {
+            let seq = next_event_seq(&mut accounts.state)?;
+            emit!(WithdrawalQueued {
+                seq,
+                state: accounts.state.key(),
+                user: accounts.user.key(),
+                amount,
+            });
+            return Ok(());
+        }
+
+        let destination = &ctx.accounts.destination_token_account;
+        pay_withdrawal(accounts, Some(destination), amount)?;
+        let seq = next_event_seq(&mut accounts.state)?;
+        emit!(UnstakedTo {
+            seq,
+            state: accounts.state.key(),
+            user: accounts.user.key(),
+            destination: destination.key(),
+            amount,
+        });
+        Ok(())
+    }
+
+    // Pay out as much of the user's queued withdrawal as this epoch's outflow budget allows.
+    pub fn claim_queued_withdrawal(ctx: Context<Unstake>) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        let budget = remaining_outflow_budget(state, Clock::get()?.epoch)?;
+        let user_stake = &mut ctx.accounts.user_stake;
+        let amount = user_stake.queued_withdrawal.min(budget);
+        require!(amount > 0, CustomError::OutflowLimitReached);
+
+        user_stake.queued_withdrawal = user_stake
+            .queued_withdrawal
+            .checked_sub(amount)
+            .ok_or(CustomError::MathOverflow)?;
+        state.queued_withdrawals = state
+            .queued_withdrawals
+            .checked_sub(amount)
+            .ok_or(CustomError::MathOverflow)?;
+        state.epoch_outflow = state
+            .epoch_outflow
+            .checked_add(amount)
+            .ok_or(CustomError::MathOverflow)?;
+
+        pay_withdrawal(ctx.accounts, None, amount)?;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(QueuedWithdrawalPaid {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            amount,
+        });
+        Ok(())
+    }
+
+    // Leave the vault in one transaction: claim pending rewards, unstake the whole balance and
+    // close the `UserStake` for its rent. The usual cooldown applies, and the withdrawal must fit
+    // this epoch's outflow budget since there is no stake account left to queue it on. Queued
+    // withdrawals, queued deposits and a pending tranche must all have settled first.
+    pub fn exit(ctx: Context<Exit>) -> Result<()> {
+        let withdrawal = &mut ctx.accounts.withdrawal;
+        require_not_blocked(&ctx.accounts.blocked)?;
+        require!(
+            withdrawal.user_stake.queued_withdrawal == 0,
+            CustomError::QueuedWithdrawalPending
+        );
+        // Queued deposits are admitted into, and a pending tranche activated on, this stake
+        // account, so both must settle before it closes.
+        require!(
+            withdrawal.user_stake.queued_deposits == 0,
+            CustomError::QueuedDepositPending
+        );
+        require!(withdrawal.user_stake.pending_tranche == 0, CustomError::TranchePending);
+        let rewards = take_pending_rewards(
+            &withdrawal.state,
+            &mut withdrawal.user_stake,
+            &mut ctx.accounts.reward_stats,
+            None,
+        )?;
+        transfer_from_vault(
+            &withdrawal.state,
+            &withdrawal.state_owner,
+            &ctx.accounts.rewards_account,
+            &ctx.accounts.reward_token_account,
+            &withdrawal.token_program,
+            rewards,
+        )?;
+        ledger::post(
+            &withdrawal.ledger,
+            LedgerAccount::RewardsOwed,
+            LedgerAccount::RewardsPool,
+            rewards,
+        )?;
+
+        let amount = withdrawal.user_stake.amount;
+        let clock = Clock::get()?;
+        let paid_now = debit_unstake(
+            &mut withdrawal.state,
+            &mut withdrawal.user_stake,
+            amount,
+            clock.unix_timestamp,
+            clock.epoch,
+        )?;
+        require!(paid_now, CustomError::OutflowLimitReached);
+        let user_stake_key = withdrawal.user_stake.key();
+        stake_index::sync(&withdrawal.stake_index, &mut withdrawal.user_stake, user_stake_key)?;
+        pay_withdrawal(withdrawal, None, amount)?;
+        withdrawal.user_stake.close(withdrawal.user.to_account_info())?;
+
+        let seq = next_event_seq(&mut withdrawal.state)?;
+        emit!(Exited {
+            seq,
+            state: withdrawal.state.key(),
+            user: withdrawal.user.key(),
+            amount,
+            rewards,
+        });
+        Ok(())
+    }
+
+    // Skip the rest of the withdrawal queue by paying a fee that grows with queue depth and
+    // hedge utilization. The withdrawal still counts against this epoch's outflow cap. The fee
+    // stays in the vault and is paid to the stakers who remain as shares, through
+    // `State::queue_fee_index`.
+    pub fn expedite_withdrawal(ctx: Context<ExpediteWithdrawal>) -> Result<()> {
+        let utilization =
+            utilization_bps(&ctx.accounts.delta_report, &ctx.accounts.withdrawal.state)?;
+        let accounts = &mut ctx.accounts.withdrawal;
+        let amount = accounts.user_stake.queued_withdrawal;
+        require!(amount > 0, CustomError::NoQueuedWithdrawal);
+        let state = &mut accounts.state;
+        let fee = bps_of(amount, queue_jump_fee_bps(state, utilization)?)?;
+
+        let budget = remaining_outflow_budget(state, Clock::get()?.epoch)?;
+        require!(amount <= budget, CustomError::OutflowLimitReached);
+        accounts.user_stake.queued_withdrawal = 0;
+        state.queued_withdrawals = state
+            .queued_withdrawals
+            .checked_sub(amount)
+            .ok_or(CustomError::MathOverflow)?;
+        state.epoch_outflow = state
+            .epoch_outflow
+            .checked_add(amount)
+            .ok_or(CustomError::MathOverflow)?;
+        if state.total_staked > 0 {
+            // Shared with the remaining stakers, the fee stays staker principal on the ledger.
+            let index_delta = q64_from_ratio(fee, state.total_staked, Rounding::Down)
+                .ok_or(CustomError::MathOverflow)?;
+            state.queue_fee_index = state
+                .queue_fee_index
+                .checked_add(index_delta)
+                .ok_or(CustomError::MathOverflow)?;
+        } else {
+            // Nobody left to pay; the fee stays in the vault as realized PnL.
+            state.cumulative_realized_pnl = state
+                .cumulative_realized_pnl
+                .checked_add(i64::try_from(fee).map_err(|_| CustomError::MathOverflow)?)
+                .ok_or(CustomError::MathOverflow)?;
+            ledger::post(
+                &accounts.ledger,
+                LedgerAccount::StakerPrincipal,
+                LedgerAccount::HedgePnl,
+                fee,
+            )?;
+        }
+
+        pay_withdrawal(accounts, None, amount.checked_sub(fee).ok_or(CustomError::MathOverflow)?)?;
+        let seq = next_event_seq(&mut accounts.state)?;
+        emit!(WithdrawalExpedited {
+            seq,
+            state: accounts.state.key(),
+            user: accounts.user.key(),
+            amount,
+            fee,
+        });
+        Ok(())
+    }
+
+    // Move `amount` of staked balance to another wallet's stake in the same vault, e.g. for
+    // wallet rotation. Both sides are settled against the reward index first, so accrued
+    // rewards stay with the sender; the receiver inherits the stricter of the two lock times.
+    // A stake that voted this epoch cannot move until the next, so it cannot vote twice.
+    pub fn transfer_stake(ctx: Context<TransferStake>, new_owner: Pubkey, amount: u64) -> Result<()> {
+        require_keys_neq!(new_owner, ctx.accounts.user.key(), CustomError::InvalidParameter);
+        let from_stake = &mut ctx.accounts.from_user_stake;
+        require!(from_stake.unlocked() >= amount, CustomError::InsufficientStake);
+        require!(
+            from_stake.last_cast_epoch != Some(Clock::get()?.epoch),
+            CustomError::StakeVotedThisEpoch
+        );
+        checkpoint_rewards(&mut ctx.accounts.state, Clock::get()?.unix_timestamp)?;
+        settle_user_rewards(&ctx.accounts.state, &mut ctx.accounts.from_user_stake)?;
+        settle_user_rewards(&ctx.accounts.state, &mut ctx.accounts.to_user_stake)?;
+        settle_lending_pnl(&mut ctx.accounts.state, &mut ctx.accounts.from_user_stake)?;
+        settle_lending_pnl(&mut ctx.accounts.state, &mut ctx.accounts.to_user_stake)?;
+        settle_queue_fees(&mut ctx.accounts.state, &mut ctx.accounts.from_user_stake)?;
+        settle_queue_fees(&mut ctx.accounts.state, &mut ctx.accounts.to_user_stake)?;
+
+        let from_stake = &mut ctx.accounts.from_user_stake;
+        from_stake.amount = from_stake
+            .amount
+            .checked_sub(amount)
+            .ok_or(CustomError::MathOverflow)?;
+        let to_stake = &mut ctx.accounts.to_user_stake;
+        to_stake.amount = to_stake
+            .amount
+            .checked_add(amount)
+            .ok_or(CustomError::MathOverflow)?;
+        to_stake.last_update = to_stake.last_update.max(from_stake.last_update);
+        let accounts = &mut *ctx.accounts;
+        track_principal_only(&mut accounts.state, &accounts.from_user_stake, 0, amount)?;
+        track_principal_only(&mut accounts.state, &accounts.to_user_stake, amount, 0)?;
+        track_risk_bucket(&mut accounts.state, &accounts.from_user_stake, 0, amount)?;
+        track_risk_bucket(&mut accounts.state, &accounts.to_user_stake, amount, 0)?;
+        let from_key = accounts.from_user_stake.key();
+        stake_index::sync(&accounts.from_stake_index, &mut accounts.from_user_stake, from_key)?;
+        let to_key = accounts.to_user_stake.key();
+        stake_index::sync(&accounts.to_stake_index, &mut accounts.to_user_stake, to_key)?;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(StakeTransferred {
+            seq,
+            state: ctx.accounts.state.key(),
+            from: ctx.accounts.user.key(),
+            to: new_owner,
+            amount,
+        });
+        Ok(())
+    }
+
+    // Move `amount` of the user's stake from one vault to another with the same base mint. It is
+    // an unstake from the source and a stake into the destination, priced 1:1 like both: the
+    // source's minimum stake duration and outflow budget apply, and the destination applies every
+    // deposit guard `stake` does, queueing what exceeds its capacity. `proof` is the whitelist
+    // proof for the destination's launch phase.
+    pub fn migrate_stake(
+        ctx: Context<MigrateStake>,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require_not_blocked(&ctx.accounts.to_blocked)?;
+        let new_user_amount = ctx
+            .accounts
+            .to_user_stake
+            .amount
+            .checked_add(amount)
+            .ok_or(CustomError::MathOverflow)?;
+        enforce_launch_phase(
+            &ctx.accounts.to_state,
+            ctx.accounts.user.key,
+            &proof,
+            new_user_amount,
+        )?;
+        require_accepting_stakes(&ctx.accounts.to_state, &ctx.accounts.to_delta_report)?;
+
+        let clock = Clock::get()?;
+        let paid_now = debit_unstake(
+            &mut ctx.accounts.from_state,
+            &mut ctx.accounts.from_user_stake,
+            amount,
+            clock.unix_timestamp,
+            clock.epoch,
+        )?;
+        require!(paid_now, CustomError::OutflowLimitReached);
+        let from_key = ctx.accounts.from_user_stake.key();
+        stake_index::sync(
+            &ctx.accounts.from_stake_index,
+            &mut ctx.accounts.from_user_stake,
+            from_key,
+        )?;
+
+        // Anything over the destination's strategy capacity joins its deposit queue.
+        let admitted = amount.min(capacity_headroom(&ctx.accounts.to_state));
+        let queued = amount - admitted;
+        if let Some(pending_deposit) = pending_deposit_slot(
+            &mut ctx.accounts.pending_deposit,
+            ctx.bumps.pending_deposit,
+            queued,
+        )? {
+            let base_mint = ctx.accounts.to_state.base_mint;
+            queue_deposit(
+                &mut ctx.accounts.to_state,
+                &mut ctx.accounts.to_user_stake,
+                pending_deposit,
+                ctx.accounts.user.key(),
+                base_mint,
+                queued,
+                queued,
+            )?;
+        }
+        let credited = split_large_stake(
+            &mut ctx.accounts.to_state,
+            &mut ctx.accounts.to_user_stake,
+            admitted,
+        )?;
+        credit_stake(
+            &mut ctx.accounts.to_state,
+            &mut ctx.accounts.to_user_stake,
+            credited,
+            clock.unix_timestamp,
+        )?;
+        let to_key = ctx.accounts.to_user_stake.key();
+        stake_index::sync(&ctx.accounts.to_stake_index, &mut ctx.accounts.to_user_stake, to_key)?;
+
+        let from_state = &ctx.accounts.from_state;
+        let vault_id = from_state.vault_id.to_le_bytes();
+        let seeds = &[
+            b"state".as_ref(),
+            ctx.accounts.from_state_owner.key.as_ref(),
+            vault_id.as_ref(),
+            &[from_state.bump],
+        ];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.from_vault_account.to_account_info(),
+            to: ctx.accounts.to_vault_account.to_account_info(),
+            authority: ctx.accounts.from_state.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            ),
+            amount,
+        )?;
+        ledger::post(
+            &ctx.accounts.from_ledger,
+            LedgerAccount::StakerPrincipal,
+            LedgerAccount::Vault,
+            amount,
+        )?;
+        post_deposit(&ctx.accounts.to_ledger, admitted, queued)?;
+        let from_seq = next_event_seq(&mut ctx.accounts.from_state)?;
+        let to_seq = next_event_seq(&mut ctx.accounts.to_state)?;
+        emit!(StakeMigrated {
+            from_seq,
+            to_seq,
+            from_state: ctx.accounts.from_state.key(),
+            to_state: ctx.accounts.to_state.key(),
+            user: ctx.accounts.user.key(),
+            amount,
+            stake_credited: credited,
+        });
+        emit_pending_tranche(
+            &mut ctx.accounts.to_state,
+            ctx.accounts.user.key(),
+            &ctx.accounts.to_user_stake,
+            admitted - credited,
+        )?;
+        emit_deposit_queued(&mut ctx.accounts.to_state, ctx.accounts.pending_deposit.as_deref())
+    }
+
+    // Rebalance positions to maintain delta-neutral exposure.
+    // Marks the open hedge book so NAV reflects current unrealized PnL.
+    // Only runs when the latest keeper report shows delta outside `allowed_delta_threshold`, and
+    // never in the same transaction as a DEX swap; see `sandwich_guard`.
+    pub fn rebalance(ctx: Context<Rebalance>) -> Result<()> {
+        require!(!ctx.accounts.state.is_paused(), CustomError::VaultPaused);
+        reject_dex_swaps(&ctx.accounts.instructions_sysvar)?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= ctx.accounts.state.next_rebalance_due()?,
+            CustomError::CrankTooSoon
+        );
+        let net_delta = read_net_delta(&ctx.accounts.delta_report, &ctx.accounts.state)?;
+        require!(
+            net_delta > ctx.accounts.state.allowed_delta_threshold,
+            CustomError::DeltaWithinThreshold
+        );
+
+        // Trade the perp leg against the reported net delta on the configured venue.
+        let venue = ctx.accounts.state.hedge_venue;
+        let hedge_program = ctx.accounts.hedge_program.to_account_info();
+        let size_delta = ctx
+            .accounts
+            .delta_report
+            .net_delta
+            .checked_neg()
+            .ok_or(CustomError::MathOverflow)?;
+        venue.adjust_hedge(&hedge_program, size_delta)?;
+        let report = &ctx.accounts.delta_report;
+        let perp_notional = report
+            .perp_exposure
+            .checked_add(size_delta)
+            .ok_or(CustomError::MathOverflow)?
+            .unsigned_abs();
+        ctx.accounts.strategy_state.record_hedge(
+            report.spot_exposure.unsigned_abs(),
+            perp_notional,
+            current_basis_bps()?,
+        )?;
+
+        let state = &mut ctx.accounts.state;
+        state.unrealized_pnl = venue.unrealized_pnl(&hedge_program)?;
+        state.last_rebalance = now;
+        state.last_hedged_at = now;
+        state.refresh_next_crank_due()?;
+        check_drawdown(state)?;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(Rebalanced {
+            seq,
+            state: ctx.accounts.state.key(),
+            net_delta: ctx.accounts.delta_report.net_delta,
+            unrealized_pnl: ctx.accounts.state.unrealized_pnl,
+        });
+        Ok(())
+    }
+
+    // Permissionless invariant check: compares what the vault's token accounts hold against the
+    // books (staked principal, queued withdrawals, position collateral, protocol stake, realized
+    // PnL and base-mint fees). A shortfall beyond the tolerance pauses staking and hedging; a
+    // later clean run lifts that pause. `remaining_accounts` holds each registered collateral's
+    // vault in registry order, followed by its stake pool for LST collateral.
+    pub fn reconcile<'info>(ctx: Context<'_, '_, 'info, 'info, Reconcile<'info>>) -> Result<()> {
+        let state_key = ctx.accounts.state.key();
+        let mut actual = (ctx.accounts.vault_account.amount as i128)
+            .checked_add(ctx.accounts.fee_vault.amount as i128)
+            .ok_or(CustomError::MathOverflow)?;
+        let mut accounts = ctx.remaining_accounts.iter();
+        for config in ctx.accounts.collateral_registry.collaterals.iter() {
+            let vault_info = accounts.next().ok_or(CustomError::InvalidBatchAccounts)?;
+            let (vault_pda, _) = Pubkey::find_program_address(
+                &[b"collateral_vault", state_key.as_ref(), config.mint.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(vault_info.key(), vault_pda, CustomError::InvalidBatchAccounts);
+            let stake_pool = if config.lst_stake_pool == Pubkey::default() {
+                None
+            } else {
+                Some(accounts.next().ok_or(CustomError::InvalidBatchAccounts)?)
+            };
+            // Collateral vaults are created on demand; one that does not exist yet holds nothing.
+            if vault_info.data_is_empty() {
+                continue;
+            }
+            let vault: Account<TokenAccount> = Account::try_from(vault_info)?;
+            let value = (vault.amount as u128)
+                .checked_mul(get_conversion_rate(config, stake_pool)? as u128)
+                .ok_or(CustomError::MathOverflow)?
+                / PRICE_PRECISION as u128;
+            actual = actual.checked_add(value as i128).ok_or(CustomError::MathOverflow)?;
+        }
+        require!(accounts.next().is_none(), CustomError::InvalidBatchAccounts);
+
+        let state = &mut ctx.accounts.state;
+        let expected = state.book_value()?;
+        let gap = actual - expected;
+        let tolerance = (expected.max(0) * state.reconcile_tolerance_bps as i128)
+            / BPS_DENOMINATOR as i128;
+        let shortfall = gap < -tolerance;
+        state.reconcile_paused = shortfall;
+        if gap != 0 {
+            let seq = next_event_seq(state)?;
+            emit!(Discrepancy {
+                seq,
+                state: state_key,
+                expected: i64::try_from(expected).map_err(|_| CustomError::MathOverflow)?,
+                actual: i64::try_from(actual).map_err(|_| CustomError::MathOverflow)?,
+                paused: shortfall,
+            });
+        }
+        Ok(())
+    }
+
+    // Keeper: revalue each collateral's deposited amount at current prices, so the rebalance
+    // engine can see which asset is driving delta. `remaining_accounts` holds the stake pool of
+    // each LST collateral, in registry order.
+    pub fn mark_collateral<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MarkCollateral<'info>>,
+        nonce: u64,
+    ) -> Result<()> {
+        consume_keeper_nonce(&mut ctx.accounts.state, nonce)?;
+        let mut stake_pools = ctx.remaining_accounts.iter();
+        let mut total_value: u64 = 0;
+        for config in ctx.accounts.collateral_registry.collaterals.iter_mut() {
+            let stake_pool = if config.lst_stake_pool == Pubkey::default() {
+                None
+            } else {
+                Some(stake_pools.next().ok_or(CustomError::InvalidBatchAccounts)?)
+            };
+            config.usd_value_at_last_mark = collateral_value(config, stake_pool)?;
+            total_value = total_value
+                .checked_add(config.usd_value_at_last_mark)
+                .ok_or(CustomError::MathOverflow)?;
+        }
+        require!(stake_pools.next().is_none(), CustomError::InvalidBatchAccounts);
+
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(CollateralMarked {
+            seq,
+            state: ctx.accounts.state.key(),
+            total_value,
+        });
+        Ok(())
+    }
+
+    // Create the vault's TVL stats account. Anyone may pay for it.
+    pub fn initialize_tvl_stats(ctx: Context<InitializeTvlStats>) -> Result<()> {
+        tvl_stats::initialize(ctx)
+    }
+
+    // Permissionless crank: refresh per-asset deposit totals and values in `TvlStats`.
+    // `remaining_accounts` holds the stake pool of each LST collateral, in registry order.
+    pub fn mark_tvl(ctx: Context<MarkTvl>) -> Result<()> {
+        tvl_stats::mark(ctx)
+    }
+
+    // Create the vault's price cache. Anyone may pay for it.
+    pub fn initialize_price_cache(ctx: Context<InitializePriceCache>) -> Result<()> {
+        price_cache::initialize(ctx)
+    }
+
+    // Permissionless crank: read the mark price and every collateral rate into `PriceCache`, at
+    // most once per slot. `remaining_accounts` holds the stake pool of each LST collateral, in
+    // registry order.
+    pub fn refresh_prices(ctx: Context<RefreshPrices>) -> Result<()> {
+        price_cache::refresh(ctx)
+    }
+
+    // Keeper: swap `amount_in` of fee revenue from a fee vault into $DNT on a DEX and burn what
+    // comes back. Governance toggles buybacks and caps the revenue spent per epoch.
+    pub fn buyback_and_burn(
+        ctx: Context<BuybackAndBurn>,
+        nonce: u64,
+        amount_in: u64,
+        min_dnt_out: u64,
+    ) -> Result<()> {
+        consume_keeper_nonce(&mut ctx.accounts.state, nonce)?;
+        let epoch = Clock::get()?.epoch;
+        let state = &mut ctx.accounts.state;
+        require!(state.buyback_enabled, CustomError::BuybackDisabled);
+        if state.buyback_epoch != epoch {
+            state.buyback_epoch = epoch;
+            state.buyback_spent_this_epoch = 0;
+        }
+        let spent = state
+            .buyback_spent_this_epoch
+            .checked_add(amount_in)
+            .ok_or(CustomError::MathOverflow)?;
+        require!(spent <= state.buyback_cap_per_epoch, CustomError::ExceedsCap);
+        state.buyback_spent_this_epoch = spent;
+        if ctx.accounts.fee_vault.mint == state.base_mint {
+            state.base_fees_accrued = state
+                .base_fees_accrued
+                .checked_sub(amount_in)
+                .ok_or(CustomError::MathOverflow)?;
+            ledger::post(
+                &ctx.accounts.ledger,
+                LedgerAccount::FeeRevenue,
+                LedgerAccount::Vault,
+                amount_in,
+            )?;
+        }
+
+        let dnt_out = swap_fees_for_dnt(&ctx, amount_in, min_dnt_out)?;
+        require!(dnt_out >= min_dnt_out, CustomError::SlippageExceeded);
+
+        let vault_id = ctx.accounts.state.vault_id.to_le_bytes();
+        let seeds = &[
+            b"state".as_ref(),
+            ctx.accounts.state_owner.key.as_ref(),
+            vault_id.as_ref(),
+            &[ctx.accounts.state.bump],
+        ];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.dnt_mint.to_account_info(),
+            from: ctx.accounts.buyback_account.to_account_info(),
+            authority: ctx.accounts.state.to_account_info(),
+        };
+        token::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            ),
+            dnt_out,
+        )?;
+
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(BuybackBurned {
+            seq,
+            state: ctx.accounts.state.key(),
+            revenue_mint: ctx.accounts.fee_vault.mint,
+            amount_in,
+            burned: dnt_out,
+        });
+        Ok(())
+    }
+
+    // Fee collector: queue a sweep of `amount` from a fee vault that is above the co-sign
+    // threshold. It may be executed by `sweep_fees` without a co-signer after PARAM_TIMELOCK_SECS.
+    pub fn propose_fee_sweep(ctx: Context<ProposeFeeSweep>, amount: u64) -> Result<()> {
+        require!(amount > 0, CustomError::InvalidParameter);
+        let executable_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(PARAM_TIMELOCK_SECS)
+            .ok_or(CustomError::MathOverflow)?;
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.bump = ctx.bumps.proposal;
+        proposal.state = ctx.accounts.state.key();
+        proposal.fee_vault = ctx.accounts.fee_vault.key();
+        proposal.proposer = ctx.accounts.authority.key();
+        proposal.amount = amount;
+        proposal.executable_at = executable_at;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(FeeSweepProposed {
+            seq,
+            state: ctx.accounts.state.key(),
+            fee_vault: ctx.accounts.fee_vault.key(),
+            proposer: ctx.accounts.authority.key(),
+            amount,
+            executable_at,
+        });
+        Ok(())
+    }
+
+    // Governance: drop a queued fee sweep.
+    pub fn cancel_fee_sweep(ctx: Context<CancelFeeSweep>) -> Result<()> {
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(FeeSweepCancelled {
+            seq,
+            state: ctx.accounts.state.key(),
+            fee_vault: ctx.accounts.proposal.fee_vault,
+            proposer: ctx.accounts.proposal.proposer,
+            amount: ctx.accounts.proposal.amount,
+        });
+        Ok(())
+    }
+
+    // Fee collector: move `amount` of fee revenue to the treasury. Amounts above
+    // `fee_sweep_cosign_threshold` also need the state owner as co-signer, or a matured proposal
+    // for at least `amount`, which is consumed.
+    pub fn sweep_fees(ctx: Context<SweepFees>, amount: u64) -> Result<()> {
+        require!(amount > 0, CustomError::InvalidParameter);
+        if amount > ctx.accounts.state.fee_sweep_cosign_threshold
+            && ctx.accounts.co_signer.is_none()
+        {
+            let proposal = ctx.accounts.proposal.as_ref().ok_or(CustomError::SweepNeedsCosign)?;
+            require!(
+                amount <= proposal.amount
+                    && Clock::get()?.unix_timestamp >= proposal.executable_at,
+                CustomError::SweepNeedsCosign
+            );
+        }
+
+        let fee_mint = ctx.accounts.fee_vault.mint;
+        if fee_mint == ctx.accounts.state.base_mint {
+            let state = &mut ctx.accounts.state;
+            state.base_fees_accrued = state
+                .base_fees_accrued
+                .checked_sub(amount)
+                .ok_or(CustomError::MathOverflow)?;
+            ledger::post(
+                &ctx.accounts.ledger,
+                LedgerAccount::FeeRevenue,
+                LedgerAccount::Vault,
+                amount,
+            )?;
+        }
+        transfer_from_vault(
+            &ctx.accounts.state,
+            &ctx.accounts.state_owner,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.treasury_token_account,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(FeesSwept {
+            seq,
+            state: ctx.accounts.state.key(),
+            mint: fee_mint,
+            collector: ctx.accounts.authority.key(),
+            amount,
+            co_signed: ctx.accounts.co_signer.is_some(),
+        });
+        Ok(())
+    }
+
+    // Return tokens of `mint` sent to the vault PDA by mistake (e.g. dust airdrops) to the state
+    // owner. The base mint, registered collateral mints and any mint the state PDA mints ($DNT
+    // rewards, shares) are refused, so user funds and the rewards pool can never be drained.
+    pub fn rescue_tokens(ctx: Context<RescueTokens>, mint: Pubkey) -> Result<()> {
+        let state = &ctx.accounts.state;
+        require!(
+            mint != state.base_mint
+                && mint != state.share_mint
+                && ctx.accounts.rescue_mint.mint_authority != Some(state.key()).into()
+                && !ctx.accounts.collateral_registry.collaterals.iter().any(|c| c.mint == mint),
+            CustomError::RescueMintProtected
+        );
+        let amount = ctx.accounts.source.amount;
+        require!(amount > 0, CustomError::InvalidParameter);
+
+        transfer_from_vault(
+            &ctx.accounts.state,
+            &ctx.accounts.state_owner.to_account_info(),
+            &ctx.accounts.source,
+            &ctx.accounts.destination,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(TokensRescued {
+            seq,
+            state: ctx.accounts.state.key(),
+            mint,
+            source: ctx.accounts.source.key(),
+            amount,
+        });
+        Ok(())
+    }
+
+    // Create the delta telemetry account for this state.
+    pub fn initialize_delta_report(ctx: Context<InitializeDeltaReport>) -> Result<()> {
+        let report = &mut ctx.accounts.delta_report;
+        report.bump = ctx.bumps.delta_report;
+        report.state = ctx.accounts.state.key();
+        report.spot_exposure = 0;
+        report.perp_exposure = 0;
+        report.net_delta = 0;
+        report.updated_at = 0;
+        report.reporter = Pubkey::default();
+        Ok(())
+    }
+
+    // Keeper posts current spot and perp exposure; net delta is derived on-chain.
+    pub fn update_delta_report(
+        ctx: Context<UpdateDeltaReport>,
+        nonce: u64,
+        spot_exposure: i64,
+        perp_exposure: i64,
+    ) -> Result<()> {
+        consume_keeper_nonce(&mut ctx.accounts.state, nonce)?;
+        let report = &mut ctx.accounts.delta_report;
+        report.spot_exposure = spot_exposure;
+        report.perp_exposure = perp_exposure;
+        report.net_delta = spot_exposure
+            .checked_add(perp_exposure)
+            .ok_or(CustomError::MathOverflow)?;
+        report.updated_at = Clock::get()?.unix_timestamp;
+        report.reporter = ctx.accounts.keeper.key();
+        // An in-band report is the keeper's heartbeat while there is nothing to rebalance.
+        let state = &mut ctx.accounts.state;
+        if report.net_delta.unsigned_abs() <= state.allowed_delta_threshold {
+            state.last_hedged_at = report.updated_at;
+        }
+        Ok(())
+    }
+
+    // Keeper: report lending yield (or a loss) realized on idle capital since the last report.
+    // It is spread over the lending bucket's shares through `State::lending_pnl_index`, so
+    // hedged-only stakers never share in lending-protocol losses.
+    pub fn report_lending_pnl(ctx: Context<ReportLendingPnl>, nonce: u64, pnl: i64) -> Result<()> {
+        consume_keeper_nonce(&mut ctx.accounts.state, nonce)?;
+        let state = &mut ctx.accounts.state;
+        require!(state.lending_staked > 0, CustomError::InvalidParameter);
+        let index_delta = q64_from_ratio(pnl.unsigned_abs(), state.lending_staked, Rounding::Down)
+            .and_then(|d| i128::try_from(d).ok())
+            .ok_or(CustomError::MathOverflow)?;
+        let index_delta = if pnl < 0 { -index_delta } else { index_delta };
+        state.lending_pnl_index = state
+            .lending_pnl_index
+            .checked_add(index_delta)
+            .ok_or(CustomError::MathOverflow)?;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(LendingPnlReported {
+            seq,
+            state: ctx.accounts.state.key(),
+            pnl,
+            lending_staked: ctx.accounts.state.lending_staked,
+            lending_pnl_index: ctx.accounts.state.lending_pnl_index,
+        });
+        Ok(())
+    }
+
+    // Strategy manager: announce a discretionary hedge trade. It may be executed after
+    // TRADE_INTENT_DELAY_SECS unless the guardian vetoes it first.
+    pub fn post_trade_intent(
+        ctx: Context<PostTradeIntent>,
+        venue: HedgeVenue,
+        side: TradeSide,
+        size: u64,
+        max_slippage_bps: u64,
+    ) -> Result<()> {
+        trade_intents::post(ctx, venue, side, size, max_slippage_bps)
+    }
+
+    // Guardian: cancel a pending trade intent.
+    pub fn veto_trade_intent(ctx: Context<VetoTradeIntent>) -> Result<()> {
+        trade_intents::veto(ctx)
+    }
+
+    // Strategy manager: execute a matured trade intent, provided the mark has stayed within its
+    // slippage bound.
+    pub fn execute_trade_intent(ctx: Context<ExecuteTradeIntent>) -> Result<()> {
+        trade_intents::execute(ctx)
+    }
+
+    // Settle closed hedges: realized PnL is booked and the remaining book is re-marked.
+    pub fn settle_hedge(ctx: Context<SettleHedge>) -> Result<()> {
+        let venue = ctx.accounts.state.hedge_venue;
+        let hedge_program = ctx.accounts.hedge_program.to_account_info();
+        let realized_pnl = venue.settled_pnl(&hedge_program)?;
+        let bucket = &mut ctx.accounts.pnl_bucket;
+        bucket.basis_pnl = bucket
+            .basis_pnl
+            .checked_add(realized_pnl)
+            .ok_or(CustomError::MathOverflow)?;
+        bucket.fees_paid = bucket
+            .fees_paid
+            .checked_add(venue.fees_paid(&hedge_program)?)
+            .ok_or(CustomError::MathOverflow)?;
+        bucket.last_update = Clock::get()?.unix_timestamp;
+        let state = &mut ctx.accounts.state;
+        state.cumulative_realized_pnl = state
+            .cumulative_realized_pnl
+            .checked_add(realized_pnl)
+            .ok_or(CustomError::MathOverflow)?;
+        state.unrealized_pnl = venue.unrealized_pnl(&hedge_program)?;
+        check_drawdown(state)?;
+        let (debit, credit) = if realized_pnl >= 0 {
+            (LedgerAccount::Vault, LedgerAccount::HedgePnl)
+        } else {
+            (LedgerAccount::HedgePnl, LedgerAccount::Vault)
+        };
+        ledger::post(&ctx.accounts.ledger, debit, credit, realized_pnl.unsigned_abs())?;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(HedgeSettled {
+            seq,
+            state: ctx.accounts.state.key(),
+            realized_pnl,
+            cumulative_realized_pnl: ctx.accounts.state.cumulative_realized_pnl,
+        });
+        Ok(())
+    }
+
+    // Distribute rewards to staked participants.
+    // Emissions accrue into the reward index at every stake change (see `checkpoint_rewards`), so
+    // each staker earns in proportion to both amount and time staked. This crank accrues the
+    // tail since the last checkpoint and mints everything accrued since the previous
+    // distribution into the state-owned rewards pool; previously swept rewards are folded back in.
+    pub fn distribute_rewards(ctx: Context<DistributeRewards>) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            current_time >= ctx.accounts.state.next_distribution_due()?,
+            CustomError::CrankTooSoon
+        );
+        // By default the annualized rate follows the utilization curve: incentives rise as more
+        // capital is put to work. Governance can switch to a flat rate or a fixed APY on NAV.
+        // The fresh utilization prices accruals until the next distribution.
+        let utilization = utilization_bps(&ctx.accounts.delta_report, &ctx.accounts.state)?;
+        let state = &mut ctx.accounts.state;
+        checkpoint_rewards(state, current_time)?;
+        let reward_amount = std::mem::take(&mut state.rewards_accrued_unminted);
+        let redirected = std::mem::take(&mut state.rewards_redirected_pending);
+        state.accrual_utilization_bps = utilization;
+
+        mint_rewards(
+            &mut ctx.accounts.state,
+            &ctx.accounts.state_owner,
+            &ctx.accounts.token_mint,
+            &ctx.accounts.rewards_account,
+            &ctx.accounts.token_program,
+            reward_amount,
+        )?;
+        ledger::post(
+            &ctx.accounts.ledger,
+            LedgerAccount::RewardsPool,
+            LedgerAccount::RewardsOwed,
+            reward_amount,
+        )?;
+
+        let state = &mut ctx.accounts.state;
+        state.last_update = current_time;
+        state.refresh_next_crank_due()?;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(RewardsDistributed {
+            seq,
+            state: ctx.accounts.state.key(),
+            amount: reward_amount,
+            reward_index: ctx.accounts.state.reward_index,
+            dust_carry: ctx.accounts.state.dust_carry,
+        });
+
+        if redirected > 0 {
+            transfer_from_vault(
+                &ctx.accounts.state,
+                &ctx.accounts.state_owner,
+                &ctx.accounts.rewards_account,
+                &ctx.accounts.treasury_token_account,
+                &ctx.accounts.token_program,
+                redirected,
+            )?;
+            ledger::post(
+                &ctx.accounts.ledger,
+                LedgerAccount::RewardsOwed,
+                LedgerAccount::RewardsPool,
+                redirected,
+            )?;
+            let seq = next_event_seq(&mut ctx.accounts.state)?;
+            emit!(RewardsRedirected {
+                seq,
+                state: ctx.accounts.state.key(),
+                amount: redirected,
+                principal_only_staked: ctx.accounts.state.principal_only_staked,
+            });
+        }
+        Ok(())
+    }
+
+    // Governance: set up LP rewards for holders of `lp_mint`. Their share of emissions is set by
+    // the `LpEmissionBps` parameter.
+    pub fn initialize_lp_incentive(
+        ctx: Context<InitializeLpIncentive>,
+        lp_mint: Pubkey,
+    ) -> Result<()> {
+        lp_rewards::initialize(ctx, lp_mint)
+    }
+
+    // Register as an LP holder, snapshotting the current LP balance.
+    pub fn register_lp(ctx: Context<RegisterLp>) -> Result<()> {
+        lp_rewards::register(ctx)
+    }
+
+    // Permissionless: re-snapshot a registered holder's LP balance.
+    pub fn refresh_lp_snapshot(ctx: Context<RefreshLpSnapshot>) -> Result<()> {
+        lp_rewards::refresh(ctx)
+    }
+
+    // Pay out the caller's accrued LP rewards from the rewards pool.
+    pub fn claim_lp_rewards(ctx: Context<ClaimLpRewards>) -> Result<()> {
+        lp_rewards::claim(ctx)
+    }
+
+    // Governance: create a gauge controller splitting `weekly_emission` across vault gauges.
+    pub fn initialize_gauge_controller(
+        ctx: Context<InitializeGaugeController>,
+        weekly_emission: u64,
+    ) -> Result<()> {
+        gauges::initialize_controller(ctx, weekly_emission)
+    }
+
+    // Governance: change the emission split across gauges each epoch.
+    pub fn set_gauge_emission(ctx: Context<SetGaugeEmission>, weekly_emission: u64) -> Result<()> {
+        gauges::set_emission(ctx, weekly_emission)
+    }
+
+    // Governance: give a vault a gauge on the controller.
+    pub fn add_gauge(ctx: Context<AddGauge>) -> Result<()> {
+        gauges::add_gauge(ctx)
+    }
+
+    // Create the account tracking how a vote-escrow lock's power is split across gauges.
+    pub fn register_gauge_voter(ctx: Context<RegisterGaugeVoter>) -> Result<()> {
+        gauges::register_voter(ctx)
+    }
+
+    // Allocate `weight_bps` of a vote-escrow lock's power to a gauge, replacing any earlier
+    // allocation to it; zero withdraws it. Takes effect from the next epoch.
+    pub fn vote_gauge_weight(ctx: Context<VoteGaugeWeight>, weight_bps: u64) -> Result<()> {
+        gauges::vote(ctx, weight_bps)
+    }
+
+    // Permissionless: re-price a voter's allocation to a gauge at the lock's current, decayed
+    // power, removing it once the lock has expired or been unlocked.
+    pub fn kick_gauge_voter(ctx: Context<KickGaugeVoter>) -> Result<()> {
+        gauges::kick(ctx)
+    }
+
+    // Permissionless crank: set the vault's `reward_per_second` to its gauge's share of this
+    // epoch's emission. Drives rewards while the vault uses `EmissionMode::PerSecond`.
+    pub fn apply_gauge_rate(ctx: Context<ApplyGaugeRate>) -> Result<()> {
+        gauges::apply_rate(ctx)
+    }
+
+    // Opt in or out of principal-only mode, for stakers who cannot hold the reward token.
+    // Rewards accrued so far stay claimable; from now on the stake's share goes to the treasury.
+    pub fn set_principal_only(ctx: Context<SetPrincipalOnly>, enabled: bool) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        let user_stake = &mut ctx.accounts.user_stake;
+        require!(user_stake.principal_only != enabled, CustomError::InvalidParameter);
+        checkpoint_rewards(state, Clock::get()?.unix_timestamp)?;
+        settle_user_rewards(state, user_stake)?;
+        if enabled {
+            user_stake.principal_only = true;
+            track_principal_only(state, user_stake, user_stake.amount, 0)?;
+        } else {
+            track_principal_only(state, user_stake, 0, user_stake.amount)?;
+            user_stake.principal_only = false;
+        }
+
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(PrincipalOnlySet {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            enabled,
+            principal_only_staked: ctx.accounts.state.principal_only_staked,
+        });
+        Ok(())
+    }
+
+    // Governance: open an NFT boost campaign. Stakers holding an NFT from the verified
+    // `collection_mint` collection earn `multiplier_bps` extra on claimed rewards until `end_ts`.
+    pub fn register_boost_collection(
+        ctx: Context<RegisterBoostCollection>,
+        collection_mint: Pubkey,
+        multiplier_bps: u64,
+        end_ts: i64,
+    ) -> Result<()> {
+        require!(
+            multiplier_bps > 0
+                && multiplier_bps <= MAX_NFT_BOOST_BPS
+                && end_ts > Clock::get()?.unix_timestamp,
+            CustomError::InvalidParameter
+        );
+        let campaign = &mut ctx.accounts.boost_collection;
+        campaign.bump = ctx.bumps.boost_collection;
+        campaign.state = ctx.accounts.state.key();
+        campaign.collection_mint = collection_mint;
+        campaign.multiplier_bps = multiplier_bps;
+        campaign.end_ts = end_ts;
+        Ok(())
+    }
+
+    // Prove ownership of an NFT from a boost campaign's collection to apply the campaign's
+    // multiplier to the user's stake. Each NFT can be linked once per vault.
+    pub fn link_nft(ctx: Context<LinkNft>) -> Result<()> {
+        let campaign = &ctx.accounts.boost_collection;
+        let now = Clock::get()?.unix_timestamp;
+        require!(now < campaign.end_ts, CustomError::BoostCampaignEnded);
+        require!(
+            read_verified_collection(&ctx.accounts.nft_metadata)? == Some(campaign.collection_mint),
+            CustomError::NftNotInCollection
+        );
+
+        let link = &mut ctx.accounts.nft_link;
+        link.bump = ctx.bumps.nft_link;
+        link.owner = ctx.accounts.user.key();
+        link.collection_mint = campaign.collection_mint;
+
+        let user_stake = &mut ctx.accounts.user_stake;
+        user_stake.nft_boost_bps = campaign.multiplier_bps;
+        user_stake.nft_boost_expires_at = campaign.end_ts;
+        Ok(())
+    }
+
+    // Pay out the user's accrued rewards from the rewards pool: `amount` of them (before any
+    // boost) if given, otherwise all. The rest stays pending.
+    pub fn claim_rewards(ctx: Context<ClaimRewards>, amount: Option<u64>) -> Result<()> {
+        require_not_blocked(&ctx.accounts.blocked)?;
+        let amount = take_pending_rewards(
+            &ctx.accounts.state,
+            &mut ctx.accounts.user_stake,
+            &mut ctx.accounts.reward_stats,
+            amount,
+        )?;
+
+        transfer_from_vault(
+            &ctx.accounts.state,
+            &ctx.accounts.state_owner,
+            &ctx.accounts.rewards_account,
+            &ctx.accounts.user_token_account,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        ledger::post(
+            &ctx.accounts.ledger,
+            LedgerAccount::RewardsOwed,
+            LedgerAccount::RewardsPool,
+            amount,
+        )?;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(RewardsClaimed {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            amount,
+        });
+        Ok(())
+    }
+
+    // Set the pending-rewards threshold above which anyone may deliver the caller's rewards with
+    // `auto_claim_for`. Zero turns auto-claiming off.
+    pub fn set_auto_claim_threshold(
+        ctx: Context<SetAutoClaimThreshold>,
+        threshold: u64,
+    ) -> Result<()> {
+        ctx.accounts.user_stake.auto_claim_threshold = threshold;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(AutoClaimThresholdSet {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            threshold,
+        });
+        Ok(())
+    }
+
+    // Permissionless: pay out all of `user`'s pending rewards to their token account once they
+    // exceed the user's auto-claim threshold. The caller keeps an AUTO_CLAIM_TIP_BPS tip.
+    pub fn auto_claim_for(ctx: Context<AutoClaimFor>) -> Result<()> {
+        require_not_blocked(&ctx.accounts.blocked)?;
+        let user_stake = &mut ctx.accounts.user_stake;
+        settle_user_rewards(&ctx.accounts.state, user_stake)?;
+        require!(
+            user_stake.auto_claim_threshold > 0
+                && user_stake.pending_rewards > user_stake.auto_claim_threshold,
+            CustomError::AutoClaimBelowThreshold
+        );
+        let amount = take_pending_rewards(
+            &ctx.accounts.state,
+            user_stake,
+            &mut ctx.accounts.reward_stats,
+            None,
+        )?;
+        let tip = bps_of(amount, AUTO_CLAIM_TIP_BPS)?;
+        let delivered = amount - tip;
+
+        transfer_from_vault(
+            &ctx.accounts.state,
+            &ctx.accounts.state_owner,
+            &ctx.accounts.rewards_account,
+            &ctx.accounts.user_token_account,
+            &ctx.accounts.token_program,
+            delivered,
+        )?;
+        if tip > 0 {
+            transfer_from_vault(
+                &ctx.accounts.state,
+                &ctx.accounts.state_owner,
+                &ctx.accounts.rewards_account,
+                &ctx.accounts.caller_token_account,
+                &ctx.accounts.token_program,
+                tip,
+            )?;
+        }
+        ledger::post(
+            &ctx.accounts.ledger,
+            LedgerAccount::RewardsOwed,
+            LedgerAccount::RewardsPool,
+            amount,
+        )?;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(RewardsAutoClaimed {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            caller: ctx.accounts.caller.key(),
+            amount: delivered,
+            tip,
+        });
+        Ok(())
+    }
+
+    // Restake pending rewards at the current NAV instead of paying them out.
+    // Only available when the reward token is the vault's base mint.
+    pub fn compound_rewards(ctx: Context<CompoundRewards>) -> Result<()> {
+        compound_pending_rewards(ctx.accounts)?;
+        Ok(())
+    }
+
+    // Compound pending rewards and add the new shares to the user's vote-escrow lock, extended
+    // to at least `tier` from now, so rewards are retained without passing through the wallet.
+    // Requires an existing lock; see `lock_stake`.
+    pub fn claim_and_lock(ctx: Context<ClaimAndLock>, tier: LockTier) -> Result<()> {
+        let new_stake = compound_pending_rewards(&mut ctx.accounts.compound)?;
+        require!(new_stake > 0, CustomError::InsufficientRewards);
+        let user_stake = &mut ctx.accounts.compound.user_stake;
+        user_stake.escrowed = user_stake
+            .escrowed
+            .checked_add(new_stake)
+            .ok_or(CustomError::MathOverflow)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let escrow = &mut ctx.accounts.vote_escrow;
+        escrow.amount = escrow.amount.checked_add(new_stake).ok_or(CustomError::MathOverflow)?;
+        if now >= escrow.unlock_at {
+            escrow.locked_at = now;
+        }
+        let unlock_at = now.checked_add(tier.duration_secs()).ok_or(CustomError::MathOverflow)?;
+        escrow.unlock_at = escrow.unlock_at.max(unlock_at);
+        Ok(())
+    }
+
+    // Create the per-user reward history account.
+    pub fn initialize_reward_stats(ctx: Context<InitializeRewardStats>) -> Result<()> {
+        let stats = &mut ctx.accounts.reward_stats;
+        stats.bump = ctx.bumps.reward_stats;
+        stats.state = ctx.accounts.state.key();
+        stats.user = ctx.accounts.user.key();
+        stats.lifetime_earned = 0;
+        stats.lifetime_claimed = 0;
+        stats.lifetime_compounded = 0;
+        stats.last_claim = 0;
+        Ok(())
+    }
+
+    // Permissionless: forfeit rewards left unclaimed past `unclaimed_expiry_secs` and return them
+    // to the pool for the next distribution. The window runs from the later of the last claim and
+    // the last stake.
+    pub fn sweep_expired_rewards(ctx: Context<SweepExpiredRewards>) -> Result<()> {
+        let user_stake = &mut ctx.accounts.user_stake;
+        settle_user_rewards(&ctx.accounts.state, user_stake)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let last_activity = user_stake.last_claim.max(user_stake.last_update);
+        require!(
+            now.checked_sub(last_activity).ok_or(CustomError::MathOverflow)?
+                > ctx.accounts.state.unclaimed_expiry_secs,
+            CustomError::RewardsNotExpired
+        );
+
+        let forfeited = user_stake.pending_rewards;
+        user_stake.pending_rewards = 0;
+        let state = &mut ctx.accounts.state;
+        state.undistributed_rewards = state
+            .undistributed_rewards
+            .checked_add(forfeited)
+            .ok_or(CustomError::MathOverflow)?;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(RewardsSwept {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            amount: forfeited,
+        });
+        Ok(())
+    }
+
+    // Create the funding observation ring buffer for a vault.
+    pub fn initialize_funding_history(ctx: Context<InitializeFundingHistory>) -> Result<()> {
+        let history = &mut ctx.accounts.funding_history;
+        history.bump = ctx.bumps.funding_history;
+        history.state = ctx.accounts.state.key();
+        history.head = 0;
+        history.count = 0;
+        history.observations = [FundingObservation::default(); FUNDING_HISTORY_LEN];
+        Ok(())
+    }
+
+    // Create the funding publisher allowlist with its quorum and freshness window.
+    pub fn initialize_funding_publishers(
+        ctx: Context<InitializeFundingPublishers>,
+        quorum: u8,
+        max_age: i64,
+    ) -> Result<()> {
+        funding_oracle::initialize(ctx, quorum, max_age)
+    }
+
+    // Governance: change how many fresh submissions a funding rate needs, and how fresh.
+    pub fn set_funding_quorum(
+        ctx: Context<ManageFundingPublishers>,
+        quorum: u8,
+        max_age: i64,
+    ) -> Result<()> {
+        funding_oracle::set_quorum(ctx, quorum, max_age)
+    }
+
+    // Governance: allow `publisher` to submit funding rates.
+    pub fn add_funding_publisher(
+        ctx: Context<ManageFundingPublishers>,
+        publisher: Pubkey,
+    ) -> Result<()> {
+        funding_oracle::add_publisher(ctx, publisher)
+    }
+
+    // Governance: drop `publisher` and its latest submission.
+    pub fn remove_funding_publisher(
+        ctx: Context<ManageFundingPublishers>,
+        publisher: Pubkey,
+    ) -> Result<()> {
+        funding_oracle::remove_publisher(ctx, publisher)
+    }
+
+    // Devnet: create the vault's mock price feed. The mock instructions fail unless the program
+    // was built with the `mock-oracles` feature.
+    pub fn initialize_mock_feed(ctx: Context<InitializeMockFeed>) -> Result<()> {
+        mock_oracle::initialize(ctx)
+    }
+
+    // Devnet: override the mark price margin and liquidation checks see; zero clears it.
+    pub fn set_mock_price(ctx: Context<SetMockFeed>, mark_price: u64) -> Result<()> {
+        mock_oracle::set_price(ctx, mark_price)
+    }
+
+    // Devnet: accrue funding at `rate` since the last accrual, bypassing the publishers.
+    pub fn set_mock_funding(ctx: Context<SetMockFeed>, rate: u64) -> Result<()> {
+        mock_oracle::set_funding(ctx, rate)
+    }
+
+    // Publisher: post the current funding rate.
+    pub fn submit_funding_rate(ctx: Context<SubmitFundingRate>, rate: u64) -> Result<()> {
+        funding_oracle::submit(ctx, rate)
+    }
+
+    // Permissionless crank: append the publishers' median funding rate to the ring buffer.
+    // Fails until a quorum of publishers has submitted within the freshness window.
+    pub fn record_funding_observation(ctx: Context<RecordFundingObservation>) -> Result<()> {
+        let timestamp = Clock::get()?.unix_timestamp;
+        let rate = ctx.accounts.funding_publishers.median_rate(timestamp)?;
+        accrue_funding(&mut ctx.accounts.state, rate, timestamp)?;
+        let history = &mut ctx.accounts.funding_history;
+        let head = history.head as usize;
+        history.observations[head] = FundingObservation { rate, timestamp };
+        history.head = ((head + 1) % FUNDING_HISTORY_LEN) as u8;
+        history.count = (history.count as usize + 1).min(FUNDING_HISTORY_LEN) as u8;
+        Ok(())
+    }
+
+    // 1️⃣ Dynamic Funding Rate Distribution.
+    // Adjust rewards based on funding rates from the perpetual futures market, smoothed as a
+    // time-weighted average over the recorded observations to resist noisy or manipulated prints.
+    pub fn update_rewards_based_on_funding(ctx: Context<UpdateRewards>) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            current_time >= ctx.accounts.state.next_distribution_due()?,
+            CustomError::CrankTooSoon
+        );
+        let funding_rate = funding_twap(&ctx.accounts.funding_history, current_time)?;
+        let reward_amount = ctx.accounts.state
+            .nav()
+            .checked_mul(funding_rate as u64)
+            .ok_or(CustomError::MathOverflow)?
+            .checked_div(100)
+            .ok_or(CustomError::MathOverflow)?;
+        mint_rewards(
+            &mut ctx.accounts.state,
+            &ctx.accounts.state_owner,
+            &ctx.accounts.token_mint,
+            &ctx.accounts.rewards_account,
+            &ctx.accounts.token_program,
+            reward_amount,
+        )?;
+        let state = &mut ctx.accounts.state;
+        state.last_update = current_time;
+        state.refresh_next_crank_due()?;
+        Ok(())
+    }
+
+    // 3️⃣ Vault Profit Sharing.
+    // Distribute arbitrage profits from the vault to $DNT holders.
+    pub fn distribute_arbitrage_profits(ctx: Context<DistributeProfits>) -> Result<()> {
+        // Basis captured by closing hedge legs below their entry basis.
+        let mut total_profits = ctx.accounts.strategy_state.take_distributable_basis();
+        // Realized market-making spread is paid out alongside arbitrage profits.
+        if let Some(strategy) = ctx.accounts.market_making.as_mut() {
+            total_profits = total_profits
+                .checked_add(strategy.take_distributable_pnl())
+                .ok_or(CustomError::MathOverflow)?;
+        }
+        mint_rewards(
+            &mut ctx.accounts.state,
+            &ctx.accounts.state_owner,
+            &ctx.accounts.token_mint,
+            &ctx.accounts.rewards_account,
+            &ctx.accounts.token_program,
+            total_profits,
+        )?;
+        Ok(())
+    }
+
+    // Publish a merkle root of off-chain computed retroactive rewards.
+    // Each leaf is keccak(index, claimant, amount); `max_claims` sizes the claimed bitmap.
+    pub fn create_reward_distribution(
+        ctx: Context<CreateRewardDistribution>,
+        distribution_id: u64,
+        merkle_root: [u8; 32],
+        max_claims: u32,
+    ) -> Result<()> {
+        let distribution = &mut ctx.accounts.distribution;
+        distribution.bump = ctx.bumps.distribution;
+        distribution.state = ctx.accounts.state.key();
+        distribution.distribution_id = distribution_id;
+        distribution.merkle_root = merkle_root;
+        distribution.total_claimed = 0;
+        distribution.max_claims = max_claims;
+        distribution.claimed_bitmap = vec![0u8; (max_claims as usize).div_ceil(8)];
+        Ok(())
+    }
+
+    // Claim a retroactive reward by proving inclusion in the distribution's merkle tree.
+    pub fn claim_airdrop(
+        ctx: Context<ClaimAirdrop>,
+        index: u32,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let distribution = &mut ctx.accounts.distribution;
+        require!(index < distribution.max_claims, CustomError::ClaimIndexOutOfRange);
+
+        let byte = (index / 8) as usize;
+        let mask = 1u8 << (index % 8);
+        require!(
+            distribution.claimed_bitmap[byte] & mask == 0,
+            CustomError::AirdropAlreadyClaimed
+        );
+
+        let leaf = keccak::hashv(&[
+            &index.to_le_bytes(),
+            ctx.accounts.claimant.key.as_ref(),
+            &amount.to_le_bytes(),
+        ])
+        .0;
+        require!(
+            verify_merkle_proof(&proof, distribution.merkle_root, leaf),
+            CustomError::InvalidMerkleProof
+        );
+
+        distribution.claimed_bitmap[byte] |= mask;
+        distribution.total_claimed = distribution
+            .total_claimed
+            .checked_add(amount)
+            .ok_or(CustomError::MathOverflow)?;
+
+        mint_rewards(
+            &mut ctx.accounts.state,
+            &ctx.accounts.state_owner,
+            &ctx.accounts.token_mint,
+            &ctx.accounts.rewards_account,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(AirdropClaimed {
+            seq,
+            state: ctx.accounts.state.key(),
+            distribution: ctx.accounts.distribution.key(),
+            claimant: ctx.accounts.claimant.key(),
+            index,
+            amount,
+        });
+        Ok(())
+    }
+
+    // 4️⃣ Liquidity Incentives for Market Makers.
+    // Reward market makers who provide deep liquidity.
+    pub fn reward_liquidity_providers(ctx: Context<RewardMakers>) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            current_time >= ctx.accounts.state.next_distribution_due()?,
+            CustomError::CrankTooSoon
+        );
+        let maker_volume = get_maker_trading_volume()?;
+        let reward_amount = maker_volume.checked_div(1000).ok_or(CustomError::MathOverflow)?;
+        mint_rewards(
+            &mut ctx.accounts.state,
+            &ctx.accounts.state_owner,
+            &ctx.accounts.token_mint,
+            &ctx.accounts.rewards_account,
+            &ctx.accounts.token_program,
+            reward_amount,
+        )?;
+        let state = &mut ctx.accounts.state;
+        state.last_update = current_time;
+        state.refresh_next_crank_due()?;
+        Ok(())
+    }
+
+    // Create the maker leaderboard; `attestor` posts the per-epoch scores.
+    pub fn initialize_leaderboard(ctx: Context<InitializeLeaderboard>, attestor: Pubkey) -> Result<()> {
+        leaderboard::initialize(ctx, attestor)
+    }
+
+    // Attestor: post this epoch's maker metrics and re-rank the board.
+    pub fn post_maker_scores(
+        ctx: Context<PostMakerScores>,
+        scores: Vec<MakerScoreInput>,
+    ) -> Result<()> {
+        leaderboard::post_scores(ctx, scores)
+    }
+
+    // Permissionless after the epoch ends: pay rank bonuses and open the next epoch.
+    pub fn close_leaderboard_epoch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseLeaderboardEpoch<'info>>,
+    ) -> Result<()> {
+        leaderboard::close_epoch(ctx)
+    }
+
+    // Create the vault's double-entry ledger.
+    pub fn initialize_ledger(ctx: Context<InitializeLedger>) -> Result<()> {
+        ledger::initialize(ctx)
+    }
+
+    // Permissionless audit: fails unless the ledger's debits and credits balance and the vault's
+    // base token accounts cover what the books say they hold.
+    pub fn verify_ledger(ctx: Context<VerifyLedger>) -> Result<()> {
+        ledger::verify(ctx)
+    }
+
+    // Permissionless: create the next page of the stake index.
+    pub fn add_stake_index_page(ctx: Context<AddStakeIndexPage>) -> Result<()> {
+        stake_index::add_page(ctx)
+    }
+
+    // Permissionless repair crank: list or delist a `UserStake` so the index matches its
+    // balance. Stake paths keep the index current; this backfills stakes made before it existed.
+    pub fn sync_stake_index(ctx: Context<SyncStakeIndex>) -> Result<()> {
+        stake_index::sync_stake(ctx)
+    }
+
+    // Governance: mint a `total` $DNT allocation for `beneficiary` into a vesting escrow. It vests
+    // linearly over `duration` seconds, with nothing releasable before `cliff` seconds.
+    pub fn create_vesting(
+        ctx: Context<CreateVesting>,
+        beneficiary: Pubkey,
+        total: u64,
+        cliff: i64,
+        duration: i64,
+    ) -> Result<()> {
+        vesting::create(ctx, beneficiary, total, cliff, duration)
+    }
+
+    // Beneficiary: withdraw everything vested and not yet released.
+    pub fn release_vested(ctx: Context<ReleaseVested>) -> Result<()> {
+        vesting::release(ctx)
+    }
+
+    // Open a perp position backed by base-mint collateral held in the vault.
+    // The position must meet the initial margin: leverage within the base collateral's limit.
+    pub fn open_position(ctx: Context<OpenPosition>, collateral: u64, size: i64) -> Result<()> {
+        require!(collateral > 0 && size != 0, CustomError::InvalidParameter);
+        let max_leverage_bps =
+            base_max_leverage_bps(&ctx.accounts.collateral_registry, &ctx.accounts.state);
+        require!(
+            within_leverage(collateral, size, max_leverage_bps),
+            CustomError::LeverageTooHigh
+        );
+        let position = &mut ctx.accounts.position;
+        position.bump = ctx.bumps.position;
+        position.state = ctx.accounts.state.key();
+        position.owner = ctx.accounts.owner.key();
+        position.collateral = collateral;
+        position.size = size;
+        position.entry_price = get_mark_price()?;
+        position.margin_call_deadline = 0;
+        position.last_funding_index = ctx.accounts.state.cumulative_funding_index;
+        position.adl_score = 0;
+        position.liquidated_size = 0;
+        position.liquidation_chunks = 0;
+        position.take_profit_price = 0;
+        position.stop_loss_price = 0;
+        position.refresh_liquidation_price(ctx.accounts.state.max_allowed_loss_bps);
+        position.record(PositionActionKind::Open, size)?;
+
+        let state = &mut ctx.accounts.state;
+        state.total_position_collateral = state
+            .total_position_collateral
+            .checked_add(collateral)
+            .ok_or(CustomError::MathOverflow)?;
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.owner_token_account.to_account_info(),
+            to: ctx.accounts.vault_account.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            collateral,
+        )?;
+
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(PositionOpened {
+            seq,
+            state: ctx.accounts.state.key(),
+            owner: ctx.accounts.owner.key(),
+            collateral,
+            size,
+            entry_price: ctx.accounts.position.entry_price,
+            liquidation_price: ctx.accounts.position.liquidation_price,
+        });
+        Ok(())
+    }
+
+    // Set or clear (with zero) the position's take-profit and stop-loss prices. Neither may
+    // already be hit at the current mark.
+    pub fn set_tp_sl(ctx: Context<SetTpSl>, tp_price: u64, sl_price: u64) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        require!(position.size != 0, CustomError::InvalidParameter);
+        position.take_profit_price = tp_price;
+        position.stop_loss_price = sl_price;
+        require!(!position.trigger_hit(get_mark_price()?), CustomError::InvalidParameter);
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(TpSlSet {
+            seq,
+            state: ctx.accounts.state.key(),
+            owner: ctx.accounts.owner.key(),
+            take_profit_price: tp_price,
+            stop_loss_price: sl_price,
+        });
+        Ok(())
+    }
+
+    // Permissionless: close a position whose take-profit or stop-loss the mark has crossed.
+    // PnL is realized, and the collateral is returned to the owner less a
+    // TRIGGER_EXECUTION_TIP_BPS tip to the executor.
+    pub fn execute_trigger(ctx: Context<ExecuteTrigger>) -> Result<()> {
+        let funding = settle_position_funding(&mut ctx.accounts.state, &mut ctx.accounts.position)?;
+        ctx.accounts.pnl_bucket.record_funding(funding)?;
+        let mark_price = get_mark_price()?;
+        let position = &mut ctx.accounts.position;
+        require!(position.trigger_hit(mark_price), CustomError::TriggerNotHit);
+
+        let pnl = position_pnl(position, mark_price)?;
+        let state = &mut ctx.accounts.state;
+        book_position_payment(state, position, -pnl)?;
+        let collateral = position.collateral;
+        let tip = bps_of(collateral, TRIGGER_EXECUTION_TIP_BPS)?;
+        let returned = collateral - tip;
+        state.total_position_collateral = state
+            .total_position_collateral
+            .checked_sub(collateral)
+            .ok_or(CustomError::MathOverflow)?;
+        position.collateral = 0;
+        position.size = 0;
+        position.margin_call_deadline = 0;
+        position.take_profit_price = 0;
+        position.stop_loss_price = 0;
+        position.liquidation_price = 0;
+        let realized_pnl = i64::try_from(pnl).map_err(|_| CustomError::MathOverflow)?;
+        position.record(PositionActionKind::TriggerClose, realized_pnl)?;
+
+        transfer_from_vault(
+            &ctx.accounts.state,
+            &ctx.accounts.state_owner,
+            &ctx.accounts.vault_account,
+            &ctx.accounts.owner_token_account,
+            &ctx.accounts.token_program,
+            returned,
+        )?;
+        transfer_from_vault(
+            &ctx.accounts.state,
+            &ctx.accounts.state_owner,
+            &ctx.accounts.vault_account,
+            &ctx.accounts.executor_token_account,
+            &ctx.accounts.token_program,
+            tip,
+        )?;
+
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(TriggerExecuted {
+            seq,
+            state: ctx.accounts.state.key(),
+            owner: ctx.accounts.position.owner,
+            executor: ctx.accounts.executor.key(),
+            mark_price,
+            realized_pnl,
+            collateral_returned: returned,
+            tip,
+        });
+        Ok(())
+    }
+
+    // Permissionless: flag an unhealthy position and start its grace window. The owner can
+    // restore health with `add_margin` before the deadline to avoid liquidation.
+    pub fn margin_call(ctx: Context<MarginCall>) -> Result<()> {
+        let funding = settle_position_funding(&mut ctx.accounts.state, &mut ctx.accounts.position)?;
+        ctx.accounts.pnl_bucket.record_funding(funding)?;
+        let position = &mut ctx.accounts.position;
+        require!(position.margin_call_deadline == 0, CustomError::AlreadyMarginCalled);
+        require!(
+            !is_position_healthy(
+                position,
+                risk_mark_price(&ctx.accounts.state.key(), ctx.remaining_accounts)?,
+                ctx.accounts.state.max_allowed_loss_bps
+            )?,
+            CustomError::PositionHealthy
+        );
+        let deadline = Clock::get()?
+            .unix_timestamp
+            .checked_add(MARGIN_CALL_GRACE_PERIOD)
+            .ok_or(CustomError::MathOverflow)?;
+        position.margin_call_deadline = deadline;
+        position.record(PositionActionKind::MarginCall, 0)?;
+
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(MarginCalled {
+            seq,
+            state: ctx.accounts.state.key(),
+            owner: ctx.accounts.position.owner,
+            deadline,
+            liquidation_price: ctx.accounts.position.liquidation_price,
+        });
+        Ok(())
+    }
+
+    // Top up a position's collateral. Clears a pending margin call once health is restored.
+    pub fn add_margin(ctx: Context<AdjustMargin>, amount: u64) -> Result<()> {
+        let funding = settle_position_funding(&mut ctx.accounts.state, &mut ctx.accounts.position)?;
+        ctx.accounts.pnl_bucket.record_funding(funding)?;
+        let position = &mut ctx.accounts.position;
+        position.collateral = position
+            .collateral
+            .checked_add(amount)
+            .ok_or(CustomError::MathOverflow)?;
+        let max_loss_bps = ctx.accounts.state.max_allowed_loss_bps;
+        let mark_price = risk_mark_price(&ctx.accounts.state.key(), ctx.remaining_accounts)?;
+        position.refresh_liquidation_price(max_loss_bps);
+        if position.margin_call_deadline != 0
+            && is_position_healthy(position, mark_price, max_loss_bps)?
+        {
+            position.margin_call_deadline = 0;
+        }
+        position.record(
+            PositionActionKind::AddMargin,
+            i64::try_from(amount).map_err(|_| CustomError::MathOverflow)?,
+        )?;
+
+        let state = &mut ctx.accounts.state;
+        state.total_position_collateral = state
+            .total_position_collateral
+            .checked_add(amount)
+            .ok_or(CustomError::MathOverflow)?;
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.owner_token_account.to_account_info(),
+            to: ctx.accounts.vault_account.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            amount,
+        )?;
+
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(MarginAdjusted {
+            seq,
+            state: ctx.accounts.state.key(),
+            owner: ctx.accounts.owner.key(),
+            delta: amount as i64,
+            collateral: ctx.accounts.position.collateral,
+            liquidation_price: ctx.accounts.position.liquidation_price,
+        });
+        Ok(())
+    }
+
+    // Withdraw collateral from a position. The position must stay healthy afterwards and may
+    // not be under a margin call.
+    pub fn remove_margin(ctx: Context<AdjustMargin>, amount: u64) -> Result<()> {
+        let funding = settle_position_funding(&mut ctx.accounts.state, &mut ctx.accounts.position)?;
+        ctx.accounts.pnl_bucket.record_funding(funding)?;
+        let position = &mut ctx.accounts.position;
+        require!(position.margin_call_deadline == 0, CustomError::AlreadyMarginCalled);
+        position.collateral = position
+            .collateral
+            .checked_sub(amount)
+            .ok_or(CustomError::InsufficientCollateral)?;
+        position.refresh_liquidation_price(ctx.accounts.state.max_allowed_loss_bps);
+        require!(
+            is_position_healthy(
+                position,
+                risk_mark_price(&ctx.accounts.state.key(), ctx.remaining_accounts)?,
+                ctx.accounts.state.max_allowed_loss_bps
+            )?,
+            CustomError::PositionUnhealthy
+        );
+        let max_leverage_bps =
+            base_max_leverage_bps(&ctx.accounts.collateral_registry, &ctx.accounts.state);
+        require!(
+            within_leverage(position.collateral, position.size, max_leverage_bps),
+            CustomError::LeverageTooHigh
+        );
+        position.record(
+            PositionActionKind::RemoveMargin,
+            i64::try_from(amount).map_err(|_| CustomError::MathOverflow)?,
+        )?;
+
+        let state = &mut ctx.accounts.state;
+        state.total_position_collateral = state
+            .total_position_collateral
+            .checked_sub(amount)
+            .ok_or(CustomError::MathOverflow)?;
+
+        transfer_from_vault(
+            &ctx.accounts.state,
+            &ctx.accounts.state_owner,
+            &ctx.accounts.vault_account,
+            &ctx.accounts.owner_token_account,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(MarginAdjusted {
+            seq,
+            state: ctx.accounts.state.key(),
+            owner: ctx.accounts.owner.key(),
+            delta: -(amount as i64),
+            collateral: ctx.accounts.position.collateral,
+            liquidation_price: ctx.accounts.position.liquidation_price,
+        });
+        Ok(())
+    }
+
+    // Voluntarily close `amount` of the position's size at the mark price. PnL on the closed part
+    // is realized and a matching share of collateral is returned with no liquidation penalty; the
+    // remaining position must stay healthy.
+    pub fn deleverage(ctx: Context<AdjustMargin>, amount: u64) -> Result<()> {
+        let funding = settle_position_funding(&mut ctx.accounts.state, &mut ctx.accounts.position)?;
+        ctx.accounts.pnl_bucket.record_funding(funding)?;
+        let position = &mut ctx.accounts.position;
+        let abs_size = position.size.unsigned_abs();
+        require!(amount > 0 && amount <= abs_size, CustomError::InvalidParameter);
+
+        let mark_price = risk_mark_price(&ctx.accounts.state.key(), ctx.remaining_accounts)?;
+        // Trader PnL on the closed part; the vault books the opposite side.
+        let closed_pnl = position_pnl(position, mark_price)?
+            .checked_mul(amount as i128)
+            .ok_or(CustomError::MathOverflow)?
+            / abs_size as i128;
+        let state = &mut ctx.accounts.state;
+        book_position_payment(state, position, -closed_pnl)?;
+
+        let returned = position.collateral as u128 * amount as u128 / abs_size as u128;
+        let returned = u64::try_from(returned).map_err(|_| CustomError::MathOverflow)?;
+        position.collateral -= returned;
+        let remaining = abs_size - amount;
+        position.size = if position.size > 0 {
+            remaining as i64
+        } else {
+            -(remaining as i64)
+        };
+        position.refresh_liquidation_price(state.max_allowed_loss_bps);
+        if position.size != 0 {
+            require!(
+                is_position_healthy(position, mark_price, state.max_allowed_loss_bps)?,
+                CustomError::PositionUnhealthy
+            );
+        }
+        position.margin_call_deadline = 0;
+        position.record(PositionActionKind::Deleverage, amount as i64)?;
+        state.total_position_collateral = state
+            .total_position_collateral
+            .checked_sub(returned)
+            .ok_or(CustomError::MathOverflow)?;
+
+        transfer_from_vault(
+            &ctx.accounts.state,
+            &ctx.accounts.state_owner,
+            &ctx.accounts.vault_account,
+            &ctx.accounts.owner_token_account,
+            &ctx.accounts.token_program,
+            returned,
+        )?;
+
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(PositionDeleveraged {
+            seq,
+            state: ctx.accounts.state.key(),
+            owner: ctx.accounts.owner.key(),
+            size_closed: amount,
+            realized_pnl: i64::try_from(closed_pnl).map_err(|_| CustomError::MathOverflow)?,
+            collateral_returned: returned,
+            size: ctx.accounts.position.size,
+            liquidation_price: ctx.accounts.position.liquidation_price,
+        });
+        Ok(())
+    }
+
+    // 6️⃣ Automated Liquidations & Risk Management.
+    // Liquidate traders if their loss exceeds the maximum allowed threshold. The position must
+    // have been margin called and still be unhealthy after the grace window.
+    pub fn auto_liquidate(ctx: Context<Liquidate>) -> Result<()> {
+        let funding = settle_position_funding(&mut ctx.accounts.state, &mut ctx.accounts.position)?;
+        ctx.accounts.pnl_bucket.record_funding(funding)?;
+        let position = &ctx.accounts.position;
+        require!(position.margin_call_deadline != 0, CustomError::NotMarginCalled);
+        require!(
+            Clock::get()?.unix_timestamp >= position.margin_call_deadline,
+            CustomError::GracePeriodActive
+        );
+        require!(
+            !is_position_healthy(
+                position,
+                risk_mark_price(&ctx.accounts.state.key(), ctx.remaining_accounts)?,
+                ctx.accounts.state.max_allowed_loss_bps
+            )?,
+            CustomError::PositionHealthy
+        );
+
+        liquidators::settle_job(
+            &ctx.accounts.liquidation_job.to_account_info(),
+            ctx.accounts.liquidator_record.as_mut(),
+            &ctx.accounts.liquidator.to_account_info(),
+        )?;
+        force_close_position(&ctx)?;
+        let seized = update_state_after_liquidation(
+            &mut ctx.accounts.state,
+            &mut ctx.accounts.position,
+        )?;
+
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(PositionLiquidated {
+            seq,
+            state: ctx.accounts.state.key(),
+            owner: ctx.accounts.position.owner,
+            liquidator: ctx.accounts.liquidator.key(),
+            collateral_seized: seized,
+        });
+        Ok(())
+    }
+
+    // Liquidate an oversized position incrementally: close up to `max_notional` of size at the
+    // mark, realizing its PnL into collateral, then re-check health. Once the remainder is
+    // healthy the liquidation ends and the margin call clears; once the size reaches zero the
+    // remaining collateral is seized as in `auto_liquidate`.
+    pub fn liquidate_chunk(ctx: Context<Liquidate>, max_notional: u64) -> Result<()> {
+        require!(max_notional > 0, CustomError::InvalidParameter);
+        let funding = settle_position_funding(&mut ctx.accounts.state, &mut ctx.accounts.position)?;
+        ctx.accounts.pnl_bucket.record_funding(funding)?;
+        let position = &ctx.accounts.position;
+        require!(position.margin_call_deadline != 0, CustomError::NotMarginCalled);
+        require!(
+            Clock::get()?.unix_timestamp >= position.margin_call_deadline,
+            CustomError::GracePeriodActive
+        );
+        let mark_price = risk_mark_price(&ctx.accounts.state.key(), ctx.remaining_accounts)?;
+        let max_loss_bps = ctx.accounts.state.max_allowed_loss_bps;
+        require!(
+            !is_position_healthy(position, mark_price, max_loss_bps)?,
+            CustomError::PositionHealthy
+        );
+        liquidators::check_job(
+            &ctx.accounts.liquidation_job.to_account_info(),
+            &ctx.accounts.liquidator.to_account_info(),
+        )?;
+
+        let position = &mut ctx.accounts.position;
+        let is_long = position.size > 0;
+        let abs_size = position.size.unsigned_abs();
+        let closed = max_notional.min(abs_size);
+        let closed_pnl = position_pnl(position, mark_price)?
+            .checked_mul(closed as i128)
+            .ok_or(CustomError::MathOverflow)?
+            / abs_size as i128;
+        book_position_payment(&mut ctx.accounts.state, position, -closed_pnl)?;
+        let remaining = (abs_size - closed) as i64;
+        position.size = if is_long { remaining } else { -remaining };
+        position.refresh_liquidation_price(max_loss_bps);
+        position.liquidated_size = position
+            .liquidated_size
+            .checked_add(closed)
+            .ok_or(CustomError::MathOverflow)?;
+        position.liquidation_chunks = position
+            .liquidation_chunks
+            .checked_add(1)
+            .ok_or(CustomError::MathOverflow)?;
+        position.record(PositionActionKind::LiquidationChunk, closed as i64)?;
+        let recovered =
+            remaining != 0 && is_position_healthy(position, mark_price, max_loss_bps)?;
+
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(PositionChunkLiquidated {
+            seq,
+            state: ctx.accounts.state.key(),
+            owner: ctx.accounts.position.owner,
+            liquidator: ctx.accounts.liquidator.key(),
+            size_closed: closed,
+            realized_pnl: i64::try_from(closed_pnl).map_err(|_| CustomError::MathOverflow)?,
+            size_remaining: ctx.accounts.position.size,
+            liquidated_size: ctx.accounts.position.liquidated_size,
+            liquidation_price: ctx.accounts.position.liquidation_price,
+        });
+
+        if recovered {
+            let position = &mut ctx.accounts.position;
+            position.margin_call_deadline = 0;
+            position.liquidated_size = 0;
+            position.liquidation_chunks = 0;
+        } else if remaining == 0 {
+            liquidators::settle_job(
+                &ctx.accounts.liquidation_job.to_account_info(),
+                ctx.accounts.liquidator_record.as_mut(),
+                &ctx.accounts.liquidator.to_account_info(),
+            )?;
+            let seized = update_state_after_liquidation(
+                &mut ctx.accounts.state,
+                &mut ctx.accounts.position,
+            )?;
+            let seq = next_event_seq(&mut ctx.accounts.state)?;
+            emit!(PositionLiquidated {
+                seq,
+                state: ctx.accounts.state.key(),
+                owner: ctx.accounts.position.owner,
+                liquidator: ctx.accounts.liquidator.key(),
+                collateral_seized: seized,
+            });
+        }
+        Ok(())
+    }
+
+    // Register as a liquidator by posting a SOL bond.
+    pub fn register_liquidator(ctx: Context<RegisterLiquidator>) -> Result<()> {
+        liquidators::register(ctx)
+    }
+
+    // Leave the liquidator registry and reclaim the bond; requires no open jobs.
+    pub fn deregister_liquidator(ctx: Context<DeregisterLiquidator>) -> Result<()> {
+        liquidators::deregister(ctx)
+    }
+
+    // Registered liquidator: claim a liquidatable position, reserving it for
+    // LIQUIDATION_JOB_TIMEOUT_SLOTS.
+    pub fn claim_liquidation_job(ctx: Context<ClaimLiquidationJob>) -> Result<()> {
+        liquidators::claim_job(ctx)
+    }
+
+    // Permissionless: slash a liquidator whose claimed job timed out; the bond goes to the caller.
+    pub fn slash_liquidator(ctx: Context<SlashLiquidator>) -> Result<()> {
+        liquidators::slash(ctx)
+    }
+
+    // Create the auto-deleveraging ladder for this vault.
+    pub fn initialize_adl_ladder(ctx: Context<InitializeAdlLadder>) -> Result<()> {
+        adl::initialize(ctx)
+    }
+
+    // Permissionless: refresh a position's ADL score at the current mark and re-rank it.
+    pub fn update_adl_rank(ctx: Context<UpdateAdlRank>) -> Result<()> {
+        adl::update_rank(ctx)
+    }
+
+    // Keeper: once protocol first-loss capital is exhausted, reduce the top-ranked profitable
+    // position on its side by up to `amount`.
+    pub fn auto_deleverage(ctx: Context<AutoDeleverage>, nonce: u64, amount: u64) -> Result<()> {
+        consume_keeper_nonce(&mut ctx.accounts.state, nonce)?;
+        adl::auto_deleverage(ctx, amount)
+    }
+
+    // Dry run of `auto_liquidate`: applies pending funding to a copy of the position and reports
+    // whether it is liquidatable now and what would be seized. Nothing is written.
+    pub fn preview_liquidation(ctx: Context<PreviewLiquidation>) -> Result<LiquidationPreview> {
+        let mut state = (*ctx.accounts.state).clone();
+        let mut position = (*ctx.accounts.position).clone();
+        let collateral_before = position.collateral;
+        settle_position_funding(&mut state, &mut position)?;
+
+        let mark_price = risk_mark_price(&ctx.accounts.state.key(), ctx.remaining_accounts)?;
+        let now = Clock::get()?.unix_timestamp;
+        let eligible = position.margin_call_deadline != 0
+            && now >= position.margin_call_deadline
+            && !is_position_healthy(&position, mark_price, state.max_allowed_loss_bps)?;
+        let funding_paid = collateral_before as i128 - position.collateral as i128;
+        Ok(LiquidationPreview {
+            eligible,
+            mark_price,
+            funding_paid: i64::try_from(funding_paid).map_err(|_| CustomError::MathOverflow)?,
+            collateral_seized: if eligible { position.collateral } else { 0 },
+        })
+    }
+
+    // Governance vote to lift a drawdown pause. The high-water mark restarts from the current NAV.
+    pub fn vote_resume_after_drawdown(ctx: Context<ResumeAfterDrawdown>) -> Result<()> {
+        require!(ctx.accounts.state.drawdown_paused, CustomError::InvalidParameter);
+        require_vote_passed(&ctx.accounts.vote_tally)?;
+        let state = &mut ctx.accounts.state;
+        state.drawdown_paused = false;
+        state.peak_nav_per_share = state.nav_per_share()?;
+        Ok(())
+    }
+
+    // Guardian: pause the vault immediately. Only governance can lift the pause.
+    pub fn guardian_pause(ctx: Context<GuardianPause>) -> Result<()> {
+        ctx.accounts.state.guardian_paused = true;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(GuardianPauseChanged {
+            seq,
+            state: ctx.accounts.state.key(),
+            paused: true,
+            by: ctx.accounts.guardian.key(),
+        });
+        Ok(())
+    }
+
+    // Governance: lift a guardian pause.
+    pub fn lift_guardian_pause(ctx: Context<LiftGuardianPause>) -> Result<()> {
+        require!(ctx.accounts.state.guardian_paused, CustomError::InvalidParameter);
+        ctx.accounts.state.guardian_paused = false;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(GuardianPauseChanged {
+            seq,
+            state: ctx.accounts.state.key(),
+            paused: false,
+            by: ctx.accounts.state_owner.key(),
+        });
+        Ok(())
+    }
+
+    // First step of a guardian rotation: the current guardian, or governance (e.g. to replace a
+    // lost or compromised key), nominates a successor.
+    pub fn nominate_guardian(ctx: Context<NominateGuardian>, nominee: Pubkey) -> Result<()> {
+        require_keys_neq!(nominee, Pubkey::default(), CustomError::InvalidParameter);
+        ctx.accounts.state.pending_guardian = nominee;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(GuardianNominated {
+            seq,
+            state: ctx.accounts.state.key(),
+            nominee,
+            by: ctx.accounts.authority.key(),
+        });
+        Ok(())
+    }
+
+    // Second step: the nominee accepts and becomes the guardian.
+    pub fn accept_guardian(ctx: Context<AcceptGuardian>) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        let previous = state.guardian;
+        state.guardian = state.pending_guardian;
+        state.pending_guardian = Pubkey::default();
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(GuardianRotated {
+            seq,
+            state: ctx.accounts.state.key(),
+            previous,
+            guardian: ctx.accounts.state.guardian,
+        });
+        Ok(())
+    }
+
+    // Create the per-vault tally that `cast_vote` records weighted votes into.
+    pub fn initialize_vote_tally(ctx: Context<InitializeVoteTally>) -> Result<()> {
+        let tally = &mut ctx.accounts.vote_tally;
+        tally.bump = ctx.bumps.vote_tally;
+        tally.state = ctx.accounts.state.key();
+        tally.epoch = Clock::get()?.epoch;
+        tally.yes_weight = 0;
+        tally.no_weight = 0;
+        tally.executed_epoch = None;
+        Ok(())
+    }
+
+    // Lock `amount` of staked shares for one of the `LockTier` durations (up to four years) in
+    // exchange for up to 4x voting weight. Locked shares cannot be unstaked, transferred or
+    // migrated until unlock.
+    pub fn lock_stake(ctx: Context<LockStake>, amount: u64, tier: LockTier) -> Result<()> {
+        require!(amount > 0, CustomError::InvalidParameter);
+        let lock_secs = tier.duration_secs();
+        let user_stake = &mut ctx.accounts.user_stake;
+        require!(user_stake.unlocked() >= amount, CustomError::InsufficientStake);
+        user_stake.escrowed = amount;
+
+        let now = Clock::get()?.unix_timestamp;
+        let escrow = &mut ctx.accounts.vote_escrow;
+        escrow.bump = ctx.bumps.vote_escrow;
+        escrow.state = ctx.accounts.state.key();
+        escrow.owner = ctx.accounts.user.key();
+        escrow.amount = amount;
+        escrow.locked_at = now;
+        escrow.unlock_at = now.checked_add(lock_secs).ok_or(CustomError::MathOverflow)?;
+        let unlock_at = escrow.unlock_at;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(StakeLocked {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            amount,
+            unlock_at,
+        });
+        Ok(())
+    }
+
+    // Push an active lock's unlock time out to `tier` from now, raising its voting bonus. The
+    // escrowed shares stay staked, so reward accounting is untouched.
+    pub fn extend_lock(ctx: Context<Relock>, tier: LockTier) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let escrow = &mut ctx.accounts.vote_escrow;
+        require!(now < escrow.unlock_at, CustomError::LockExpired);
+        let unlock_at = now.checked_add(tier.duration_secs()).ok_or(CustomError::MathOverflow)?;
+        require!(unlock_at > escrow.unlock_at, CustomError::InvalidParameter);
+        escrow.unlock_at = unlock_at;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(LockExtended {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            unlock_at,
+        });
+        Ok(())
+    }
+
+    // Lock an expired escrow again for `tier`, instead of unlocking and locking anew.
+    pub fn relock(ctx: Context<Relock>, tier: LockTier) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let escrow = &mut ctx.accounts.vote_escrow;
+        require!(now >= escrow.unlock_at, CustomError::StakeStillLocked);
+        escrow.locked_at = now;
+        escrow.unlock_at = now.checked_add(tier.duration_secs()).ok_or(CustomError::MathOverflow)?;
+        let (amount, unlock_at) = (escrow.amount, escrow.unlock_at);
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(StakeRelocked {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            amount,
+            unlock_at,
+        });
+        Ok(())
+    }
+
+    // Release an expired vote-escrow lock and close the escrow account. Gauge allocations made
+    // with it no longer count once anyone calls `kick_gauge_voter` on them.
+    pub fn unlock_stake(ctx: Context<UnlockStake>) -> Result<()> {
+        require!(
+            Clock::get()?.unix_timestamp >= ctx.accounts.vote_escrow.unlock_at,
+            CustomError::StakeStillLocked
+        );
+        ctx.accounts.user_stake.escrowed = 0;
+        let seq = next_event_seq(&mut ctx.accounts.state)?;
+        emit!(StakeUnlocked {
+            seq,
+            state: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            amount: ctx.accounts.vote_escrow.amount,
+        });
+        Ok(())
+    }
+
+    // 8️⃣ Staked Voting (Governance).
+    // Allow staked $DNT holders to vote on protocol risk parameters.
+    // Record the voter's weight for or against this epoch's proposal. Stake counts once; shares
+    // in a vote escrow add a bonus that decays linearly to their unlock time.
+    pub fn cast_vote(ctx: Context<CastVote>, support: bool) -> Result<()> {
+        let clock = Clock::get()?;
+        let tally = &mut ctx.accounts.vote_tally;
+        if tally.epoch != clock.epoch {
+            tally.epoch = clock.epoch;
+            tally.yes_weight = 0;
+            tally.no_weight = 0;
+        }
+        let user_stake = &mut ctx.accounts.user_stake;
+        require!(
+            user_stake.last_cast_epoch != Some(clock.epoch),
+            CustomError::AlreadyVoted
+        );
+        let weight = voting_weight(
+            user_stake,
+            ctx.accounts.vote_escrow.as_deref(),
+            clock.unix_timestamp,
+        )?;
+        require!(weight > 0, CustomError::InsufficientStake);
+        if support {
+            tally.yes_weight = tally.yes_weight.checked_add(weight).ok_or(CustomError::MathOverflow)?;
+        } else {
+            tally.no_weight = tally.no_weight.checked_add(weight).ok_or(CustomError::MathOverflow)?;
+        }
+
+        user_stake.last_cast_epoch = Some(clock.epoch);
+        if user_stake.vote_epoch != clock.epoch {
+            user_stake.vote_epoch = clock.epoch;
+            user_stake.votes_cast_this_epoch = 0;
+        }
+        user_stake.votes_cast_this_epoch = user_stake
+            .votes_cast_this_epoch
+            .checked_add(1)
+            .ok_or(CustomError::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn vote_on_risk_params(ctx: Context<Vote>, new_threshold: u64) -> Result<()> {
+        require_vote_passed(&ctx.accounts.vote_tally)?;
+        // Only a voter from the passing epoch may execute it, and only once.
+        let epoch = Clock::get()?.epoch;
+        require!(
+            ctx.accounts.user_stake.last_cast_epoch == Some(epoch),
+            CustomError::Unauthorized
+        );
+        let tally = &mut ctx.accounts.vote_tally;
+        require!(tally.executed_epoch != Some(epoch), CustomError::VoteAlreadyExecuted);
+        tally.executed_epoch = Some(epoch);
+
+        // Passed votes go through the same timelock as any other parameter change.
+        queue_param_change(
+            &ctx.accounts.state,
+            &mut ctx.accounts.param_registry,
+            ParamKey::AllowedDeltaThreshold,
+            ParamValue::U64(new_threshold),
+        )?;
+        Ok(())
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Global State & User Stake Accounts
+// -----------------------------------------------------------------------------
+
+/// Program-wide singleton at `[b"config"]`, listing the canonical vaults.
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub bump: u8,
+    /// Upgrade authority at creation; registers vaults.
+    pub admin: Pubkey,
+    #[max_len(MAX_REGISTERED_VAULTS)]
+    pub vaults: Vec<Pubkey>,
+    /// Programs the vault may CPI into for hedging or quoting; see `program_whitelist`.
+    #[max_len(MAX_WHITELISTED_PROGRAMS)]
+    pub whitelisted_programs: Vec<Pubkey>,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct State {
+    pub bump: u8,
+    pub vault_id: u64,
+    /// The stakeable token. Fixed at `create_vault`; a vault for another mint is a new vault.
+    pub base_mint: Pubkey,
+    pub share_mint: Pubkey,
+    pub total_staked: u64,
+    pub last_update: i64,
+    pub last_rebalance: i64,
+    pub allowed_delta_threshold: u64,
+    pub launch_phase: LaunchPhase,
+    pub whitelist_root: [u8; 32],
+    pub capped_phase_max_stake: u64,
+    pub cumulative_realized_pnl: i64,
+    pub unrealized_pnl: i64,
+    /// Rewards per share ever distributed, in Q64.64.
+    pub reward_index: u128,
+    pub undistributed_rewards: u64,
+    pub unclaimed_expiry_secs: i64,
+    pub keeper: Pubkey,
+    pub max_delta_report_age: i64,
+    pub max_outflow_bps_per_epoch: u64,
+    pub outflow_epoch: u64,
+    pub epoch_start_staked: u64,
+    pub epoch_outflow: u64,
+    pub queued_withdrawals: u64,
+    /// Incremented for every emitted event so indexers can detect gaps and order events.
+    pub event_seq: u64,
+    /// Trader position collateral held in the vault, kept apart from staked principal.
+    pub total_position_collateral: u64,
+    /// Protocol-owned first-loss capital; earns no rewards.
+    pub protocol_stake: u64,
+    pub max_rebalance_staleness: i64,
+    /// Cumulative funding per unit of long notional, in basis points scaled by PRICE_PRECISION.
+    pub cumulative_funding_index: i128,
+    pub last_funding_accrual: i64,
+    /// Reward curve: annualized emission rate (bps of NAV) as a function of utilization.
+    pub reward_rate_base_bps: u64,
+    pub reward_rate_slope1_bps: u64,
+    pub reward_rate_slope2_bps: u64,
+    pub reward_kink_utilization_bps: u64,
+    /// Drawdown breaker: high-water mark of NAV per share (PRICE_PRECISION) and the allowed drop
+    /// from it. Tripping pauses stakes and hedging until governance votes to resume.
+    pub max_drawdown_bps: u64,
+    pub peak_nav_per_share: u64,
+    pub drawdown_paused: bool,
+    /// Owner the state PDA was derived from; checked with `has_one` in every context.
+    pub state_owner: Pubkey,
+    /// Minimum spacing between `rebalance` calls and between `distribute_rewards` calls, so rapid
+    /// repeated cranks cannot be used to steer the reward rate.
+    pub min_rebalance_interval: i64,
+    pub min_distribution_interval: i64,
+    /// Earliest time either crank may next run; kept for keepers to schedule against.
+    pub next_crank_due: i64,
+    /// Minimum time stake must stay in before it can be withdrawn, against flash-loan stakes.
+    pub min_stake_duration: i64,
+    /// Loss, as bps of collateral, beyond which a position is unhealthy and can be liquidated.
+    pub max_allowed_loss_bps: u64,
+    /// Base-mint fees held in the base fee vault.
+    pub base_fees_accrued: u64,
+    /// Shortfall against the books, in bps of book value, that `reconcile` tolerates.
+    pub reconcile_tolerance_bps: u64,
+    pub reconcile_paused: bool,
+    /// Stakes below this many shares unstake without the cooldown or outflow queue.
+    pub small_balance_threshold: u64,
+    /// Buyback-and-burn toggle and the fee revenue it may spend per epoch.
+    pub buyback_enabled: bool,
+    pub buyback_cap_per_epoch: u64,
+    pub buyback_epoch: u64,
+    pub buyback_spent_this_epoch: u64,
+    /// Lifetime deposit cap per user, indexed by `Tier`.
+    pub tier_caps: [u64; Tier::COUNT],
+    /// Venue the rebalance engine routes hedges to.
+    pub hedge_venue: HedgeVenue,
+    /// Set once the vault is registered in the global `Config`; required to mint rewards.
+    pub registered: bool,
+    /// How `distribute_rewards` sizes emissions, with the inputs of the non-default modes.
+    pub emission_mode: EmissionMode,
+    pub reward_per_second: u64,
+    pub emission_apy_bps: u64,
+    /// Shares held by principal-only stakers, whose reward share goes to the treasury.
+    pub principal_only_staked: u64,
+    /// Security council key that can pause the vault but not unpause it or change parameters,
+    /// and its nominated successor awaiting acceptance.
+    pub guardian: Pubkey,
+    pub pending_guardian: Pubkey,
+    pub guardian_paused: bool,
+    /// Pages of the stake index created so far; see `StakeIndexPage`.
+    pub stake_index_pages: u32,
+    /// Largest fee sweep a fee collector may make alone; larger sweeps need the state owner to
+    /// co-sign or a matured `FeeSweepProposal`.
+    pub fee_sweep_cosign_threshold: u64,
+    /// Nonce the next keeper-signed maintenance instruction must carry; see
+    /// `consume_keeper_nonce`.
+    pub keeper_nonce: u64,
+    /// Share of each reward emission set aside for LP token holders, and the amount set aside
+    /// but not yet folded into the LP reward index.
+    pub lp_emission_bps: u64,
+    pub lp_rewards_pending: u64,
+    /// $DNT minted through `mint_rewards` over the vault's lifetime, and the most it may ever
+    /// mint (zero for no cap).
+    pub total_rewards_minted: u64,
+    pub reward_mint_cap: u64,
+    /// Time rewards were last accrued into the reward index; see `checkpoint_rewards`.
+    pub reward_checkpoint_at: i64,
+    /// Utilization, in bps, that prices accruals until the next distribution.
+    pub accrual_utilization_bps: u64,
+    /// Rewards accrued into the index, and principal-only shares of them owed to the treasury,
+    /// that the next distribution mints and pays.
+    pub rewards_accrued_unminted: u64,
+    pub rewards_redirected_pending: u64,
+    /// Trade intents posted so far; the next intent's id.
+    pub trade_intent_count: u64,
+    /// Shares in the `HedgedLending` risk bucket, and lending PnL per such share over the
+    /// vault's lifetime, in signed Q64.64.
+    pub lending_staked: u64,
+    pub lending_pnl_index: i128,
+    /// Largest hedge notional, in base-asset value, one stake may require at once (zero for no
+    /// limit); the rest of a larger stake waits as a pending tranche. `pending_tranches` is the
+    /// base tokens held in such tranches.
+    pub max_single_hedge_notional: u64,
+    pub pending_tranches: u64,
+    /// Rewards already minted into the pool that the last checkpoint's rounded-down index
+    /// increment left uncredited; folded into the next checkpoint's distribution.
+    pub dust_carry: u64,
+    /// Stake, in base tokens, beyond which the strategy's returns dilute (zero for no limit).
+    /// Deposits over it wait unhedged and unearning in the `PendingDeposit` queue;
+    /// `pending_deposits` is the base tokens queued, and entries `pending_deposit_head..tail`
+    /// are outstanding.
+    pub strategy_capacity: u64,
+    pub pending_deposits: u64,
+    pub pending_deposit_head: u64,
+    pub pending_deposit_tail: u64,
+    /// Last time the keeper confirmed the hedge: a `rebalance`, or a delta report inside
+    /// `allowed_delta_threshold`. Keeper liveness and pending tranches key off it, since
+    /// `rebalance` refuses to run while delta is in band.
+    pub last_hedged_at: i64,
+    /// Queue-jump fees per share paid by `expedite_withdrawal` over the vault's lifetime, in
+    /// Q64.64 base tokens; stakes collect their part as new shares.
+    pub queue_fee_index: u128,
+    /// Realized PnL the vault has booked against trader positions: funding, closes and
+    /// liquidations. The trader book sits outside the ledger, so `verify_ledger` sets it aside.
+    pub position_realized_pnl: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+impl State {
+    /// True while the drawdown breaker, the reconciliation guard or the guardian has paused the
+    /// vault.
+    pub fn is_paused(&self) -> bool {
+        self.drawdown_paused || self.reconcile_paused || self.guardian_paused
+    }
+
+    /// Base-asset value the vault's token accounts should hold according to the books.
+    pub fn book_value(&self) -> Result<i128> {
+        let principal = (self.total_staked as i128)
+            + self.queued_withdrawals as i128
+            + self.total_position_collateral as i128
+            + self.protocol_stake as i128
+            + self.base_fees_accrued as i128
+            + self.pending_tranches as i128
+            + self.pending_deposits as i128;
+        principal
+            .checked_add(self.cumulative_realized_pnl as i128)
+            .ok_or(error!(CustomError::MathOverflow))
+    }
+
+    /// Earliest time `rebalance` may run again.
+    pub fn next_rebalance_due(&self) -> Result<i64> {
+        Ok(self
+            .last_rebalance
+            .checked_add(self.min_rebalance_interval)
+            .ok_or(CustomError::MathOverflow)?)
+    }
+
+    /// Earliest time `distribute_rewards` may run again.
+    pub fn next_distribution_due(&self) -> Result<i64> {
+        Ok(self
+            .last_update
+            .checked_add(self.min_distribution_interval)
+            .ok_or(CustomError::MathOverflow)?)
+    }
+
+    pub fn refresh_next_crank_due(&mut self) -> Result<()> {
+        self.next_crank_due = self.next_rebalance_due()?.min(self.next_distribution_due()?);
+        Ok(())
+    }
+
+    /// $DNT `mint_rewards` may still mint under `reward_mint_cap`; `u64::MAX` when uncapped.
+    pub fn remaining_emissions(&self) -> u64 {
+        if self.reward_mint_cap == 0 {
+            return u64::MAX;
+        }
+        self.reward_mint_cap.saturating_sub(self.total_rewards_minted)
+    }
+
+    /// Net asset value: staked principal plus realized and unrealized hedge PnL, floored at zero.
+    /// Losses are absorbed by the protocol stake first, so stakers only see the part that exceeds it.
+    pub fn nav(&self) -> u64 {
+        let pnl = self.cumulative_realized_pnl as i128 + self.unrealized_pnl as i128;
+        let staker_pnl = if pnl < 0 { (pnl + self.protocol_stake as i128).min(0) } else { pnl };
+        let nav = self.total_staked as i128 + staker_pnl;
+        nav.clamp(0, u64::MAX as i128) as u64
+    }
+
+    /// Annualized reward rate in bps at `utilization` (bps), following the kink curve.
+    pub fn reward_rate_bps(&self, utilization: u64) -> u64 {
+        let kink = self.reward_kink_utilization_bps;
+        let below = utilization.min(kink);
+        let above = utilization.saturating_sub(kink);
+        let rate = self.reward_rate_base_bps as u128
+            + self.reward_rate_slope1_bps as u128 * below as u128 / kink.max(1) as u128
+            + self.reward_rate_slope2_bps as u128 * above as u128
+                / BPS_DENOMINATOR.saturating_sub(kink).max(1) as u128;
+        rate.min(u64::MAX as u128) as u64
+    }
+
+    /// Rewards to emit for `elapsed` seconds under the vault's emission mode.
+    pub fn emission(&self, utilization: u64, elapsed: u64) -> Result<u64> {
+        let amount = match self.emission_mode {
+            EmissionMode::PerSecond => (self.reward_per_second as u128)
+                .checked_mul(elapsed as u128)
+                .ok_or(CustomError::MathOverflow)?,
+            EmissionMode::UtilizationCurve | EmissionMode::NavApy => {
+                let apy_bps = if self.emission_mode == EmissionMode::NavApy {
+                    self.emission_apy_bps
+                } else {
+                    self.reward_rate_bps(utilization)
+                };
+                (self.nav() as u128)
+                    .checked_mul(apy_bps as u128)
+                    .ok_or(CustomError::MathOverflow)?
+                    .checked_mul(elapsed as u128)
+                    .ok_or(CustomError::MathOverflow)?
+                    / (BPS_DENOMINATOR as u128 * SECONDS_PER_YEAR as u128)
+            }
+        };
+        u64::try_from(amount).map_err(|_| error!(CustomError::MathOverflow))
+    }
+
+    /// NAV per share scaled by PRICE_PRECISION; par when nothing is staked. Unlike raw NAV it is
+    /// unaffected by deposits and withdrawals.
+    pub fn nav_per_share(&self) -> Result<u64> {
+        if self.total_staked == 0 {
+            return Ok(PRICE_PRECISION);
+        }
+        let value = (self.nav() as u128)
+            .checked_mul(PRICE_PRECISION as u128)
+            .ok_or(CustomError::MathOverflow)?
+            / self.total_staked as u128;
+        u64::try_from(value).map_err(|_| error!(CustomError::MathOverflow))
+    }
+
+    /// Protocol stake remaining after absorbing losses.
+    pub fn protocol_equity(&self) -> u64 {
+        let pnl = self.cumulative_realized_pnl as i128 + self.unrealized_pnl as i128;
+        (self.protocol_stake as i128 + pnl.min(0)).clamp(0, u64::MAX as i128) as u64
+    }
+
+    /// Value of `shares` of this vault at the current NAV. Withdrawals pay principal 1:1 and
+    /// do not use this; it backs the stake-value read.
+    pub fn shares_to_value(&self, shares: u64) -> Result<u64> {
+        if self.total_staked == 0 {
+            return Ok(shares);
+        }
+        // Round down: never report more than the exact pro-rata value.
+        mul_div_u64(shares, self.nav(), self.total_staked, Rounding::Down)
+            .ok_or(error!(CustomError::MathOverflow))
+    }
+
+    /// Shares of this vault worth `value` at the current NAV. Deposits mint 1:1 and do not use
+    /// this; it prices compounded rewards, lending PnL and queue fees.
+    pub fn value_to_shares(&self, value: u64) -> Result<u64> {
+        let nav = self.nav();
+        if self.total_staked == 0 || nav == 0 {
+            return Ok(value);
+        }
+        // Round down: never credit more shares than the value buys.
+        mul_div_u64(value, self.total_staked, nav, Rounding::Down)
+            .ok_or(error!(CustomError::MathOverflow))
+    }
+}
+
+/// One user's deposit in a `batch_stake` call.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BatchStakeEntry {
+    pub user: Pubkey,
+    pub amount: u64,
+    /// The user's whitelist merkle proof; only checked during the whitelist phase.
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// Result of `quote_stake`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct StakeQuote {
+    pub fee: u64,
+    pub shares_out: u64,
+    pub nav: u64,
+}
+
+/// Result of `quote_unstake`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct UnstakeQuote {
+    pub fee: u64,
+    pub tokens_out: u64,
+    pub nav: u64,
+}
+
+/// Result of `read_nav`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct NavView {
+    pub nav: u64,
+    /// NAV per share, scaled by PRICE_PRECISION.
+    pub nav_per_share: u64,
+    pub total_shares: u64,
+    /// Time of the last hedge mark that NAV reflects.
+    pub marked_at: i64,
+}
+
+/// Return data of `remaining_emissions`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct EmissionsView {
+    pub total_minted: u64,
+    /// Zero when uncapped.
+    pub cap: u64,
+    /// `u64::MAX` when uncapped.
+    pub remaining: u64,
+}
+
+/// Number of governed vault parameters.
+pub const PARAM_COUNT: usize = 32;
+
+/// Vault parameters that can only change through the timelocked `ParamRegistry`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum ParamKey {
+    AllowedDeltaThreshold,
+    Keeper,
+    MaxDeltaReportAge,
+    MaxOutflowBpsPerEpoch,
+    UnclaimedExpirySecs,
+    MaxRebalanceStaleness,
+    RewardRateBaseBps,
+    RewardRateSlope1Bps,
+    RewardRateSlope2Bps,
+    RewardKinkUtilizationBps,
+    MaxDrawdownBps,
+    MinRebalanceInterval,
+    MinDistributionInterval,
+    MinStakeDuration,
+    MaxAllowedLossBps,
+    ReconcileToleranceBps,
+    SmallBalanceThreshold,
+    BuybackEnabled,
+    BuybackCapPerEpoch,
+    TierCap(Tier),
+    HedgeVenue,
+    EmissionMode,
+    RewardPerSecond,
+    EmissionApyBps,
+    FeeSweepCosignThreshold,
+    LpEmissionBps,
+    RewardMintCap,
+    MaxSingleHedgeNotional,
+    StrategyCapacity,
+}
+
+impl ParamKey {
+    pub const ALL: [ParamKey; PARAM_COUNT] = [
+        ParamKey::AllowedDeltaThreshold,
+        ParamKey::Keeper,
+        ParamKey::MaxDeltaReportAge,
+        ParamKey::MaxOutflowBpsPerEpoch,
+        ParamKey::UnclaimedExpirySecs,
+        ParamKey::MaxRebalanceStaleness,
+        ParamKey::RewardRateBaseBps,
+        ParamKey::RewardRateSlope1Bps,
+        ParamKey::RewardRateSlope2Bps,
+        ParamKey::RewardKinkUtilizationBps,
+        ParamKey::MaxDrawdownBps,
+        ParamKey::MinRebalanceInterval,
+        ParamKey::MinDistributionInterval,
+        ParamKey::MinStakeDuration,
+        ParamKey::MaxAllowedLossBps,
+        ParamKey::ReconcileToleranceBps,
+        ParamKey::SmallBalanceThreshold,
+        ParamKey::BuybackEnabled,
+        ParamKey::BuybackCapPerEpoch,
+        ParamKey::TierCap(Tier::Unverified),
+        ParamKey::TierCap(Tier::Basic),
+        ParamKey::TierCap(Tier::Verified),
+        ParamKey::TierCap(Tier::Institutional),
+        ParamKey::HedgeVenue,
+        ParamKey::EmissionMode,
+        ParamKey::RewardPerSecond,
+        ParamKey::EmissionApyBps,
+        ParamKey::FeeSweepCosignThreshold,
+        ParamKey::LpEmissionBps,
+        ParamKey::RewardMintCap,
+        ParamKey::MaxSingleHedgeNotional,
+        ParamKey::StrategyCapacity,
+    ];
+
+    /// Current value of this parameter on `state`.
+    pub fn read(self, state: &State) -> ParamValue {
+        match self {
+            ParamKey::AllowedDeltaThreshold => ParamValue::U64(state.allowed_delta_threshold),
+            ParamKey::Keeper => ParamValue::Pubkey(state.keeper),
+            ParamKey::MaxDeltaReportAge => ParamValue::U64(state.max_delta_report_age as u64),
+            ParamKey::MaxOutflowBpsPerEpoch => ParamValue::Bps(state.max_outflow_bps_per_epoch),
+            ParamKey::UnclaimedExpirySecs => ParamValue::U64(state.unclaimed_expiry_secs as u64),
+            ParamKey::MaxRebalanceStaleness => ParamValue::U64(state.max_rebalance_staleness as u64),
+            ParamKey::RewardRateBaseBps => ParamValue::Bps(state.reward_rate_base_bps),
+            ParamKey::RewardRateSlope1Bps => ParamValue::Bps(state.reward_rate_slope1_bps),
+            ParamKey::RewardRateSlope2Bps => ParamValue::Bps(state.reward_rate_slope2_bps),
+            ParamKey::RewardKinkUtilizationBps => ParamValue::Bps(state.reward_kink_utilization_bps),
+            ParamKey::MaxDrawdownBps => ParamValue::Bps(state.max_drawdown_bps),
+            ParamKey::MinRebalanceInterval => ParamValue::U64(state.min_rebalance_interval as u64),
+            ParamKey::MinDistributionInterval => {
+                ParamValue::U64(state.min_distribution_interval as u64)
+            }
+            ParamKey::MinStakeDuration => ParamValue::U64(state.min_stake_duration as u64),
+            ParamKey::MaxAllowedLossBps => ParamValue::Bps(state.max_allowed_loss_bps),
+            ParamKey::ReconcileToleranceBps => ParamValue::Bps(state.reconcile_tolerance_bps),
+            ParamKey::SmallBalanceThreshold => ParamValue::U64(state.small_balance_threshold),
+            ParamKey::BuybackEnabled => ParamValue::Bool(state.buyback_enabled),
+            ParamKey::BuybackCapPerEpoch => ParamValue::U64(state.buyback_cap_per_epoch),
+            ParamKey::TierCap(tier) => ParamValue::U64(state.tier_caps[tier as usize]),
+            ParamKey::HedgeVenue => ParamValue::Venue(state.hedge_venue),
+            ParamKey::EmissionMode => ParamValue::Emission(state.emission_mode),
+            ParamKey::RewardPerSecond => ParamValue::U64(state.reward_per_second),
+            ParamKey::EmissionApyBps => ParamValue::Bps(state.emission_apy_bps),
+            ParamKey::FeeSweepCosignThreshold => {
+                ParamValue::U64(state.fee_sweep_cosign_threshold)
+            }
+            ParamKey::LpEmissionBps => ParamValue::Bps(state.lp_emission_bps),
+            ParamKey::RewardMintCap => ParamValue::U64(state.reward_mint_cap),
+            ParamKey::MaxSingleHedgeNotional => ParamValue::U64(state.max_single_hedge_notional),
+            ParamKey::StrategyCapacity => ParamValue::U64(state.strategy_capacity),
+        }
+    }
+
+    /// Rejects values of the wrong type or outside the parameter's allowed range.
+    pub fn validate(self, value: ParamValue) -> Result<()> {
+        let valid = match (self, value) {
+            (ParamKey::AllowedDeltaThreshold, ParamValue::U64(_))
+            | (ParamKey::SmallBalanceThreshold, ParamValue::U64(_))
+            | (ParamKey::BuybackCapPerEpoch, ParamValue::U64(_))
+            | (ParamKey::TierCap(_), ParamValue::U64(_))
+            | (ParamKey::RewardPerSecond, ParamValue::U64(_))
+            | (ParamKey::FeeSweepCosignThreshold, ParamValue::U64(_))
+            | (ParamKey::RewardMintCap, ParamValue::U64(_))
+            | (ParamKey::MaxSingleHedgeNotional, ParamValue::U64(_))
+            | (ParamKey::StrategyCapacity, ParamValue::U64(_)) => true,
+            (ParamKey::BuybackEnabled, ParamValue::Bool(_)) => true,
+            (ParamKey::HedgeVenue, ParamValue::Venue(_)) => true,
+            (ParamKey::EmissionMode, ParamValue::Emission(_)) => true,
+            (ParamKey::Keeper, ParamValue::Pubkey(_)) => true,
+            (ParamKey::MaxDeltaReportAge, ParamValue::U64(v))
+            | (ParamKey::UnclaimedExpirySecs, ParamValue::U64(v)) => v > 0 && v <= i64::MAX as u64,
+            (ParamKey::MaxRebalanceStaleness, ParamValue::U64(v))
+            | (ParamKey::MinRebalanceInterval, ParamValue::U64(v))
+            | (ParamKey::MinDistributionInterval, ParamValue::U64(v))
+            | (ParamKey::MinStakeDuration, ParamValue::U64(v)) => v <= i64::MAX as u64,
+            (ParamKey::MaxAllowedLossBps, ParamValue::Bps(v)) => v > 0 && v <= BPS_DENOMINATOR,
+            (ParamKey::MaxOutflowBpsPerEpoch, ParamValue::Bps(v)) => v > 0 && v <= BPS_DENOMINATOR,
+            (ParamKey::RewardRateBaseBps, ParamValue::Bps(v))
+            | (ParamKey::RewardRateSlope1Bps, ParamValue::Bps(v))
+            | (ParamKey::RewardRateSlope2Bps, ParamValue::Bps(v))
+            | (ParamKey::EmissionApyBps, ParamValue::Bps(v))
+            | (ParamKey::LpEmissionBps, ParamValue::Bps(v)) => v <= BPS_DENOMINATOR,
+            (ParamKey::RewardKinkUtilizationBps, ParamValue::Bps(v)) => v > 0 && v < BPS_DENOMINATOR,
+            (ParamKey::MaxDrawdownBps, ParamValue::Bps(v))
+            | (ParamKey::ReconcileToleranceBps, ParamValue::Bps(v)) => v <= BPS_DENOMINATOR,
+            _ => false,
+        };
+        require!(valid, CustomError::InvalidParameter);
+        Ok(())
+    }
+
+    /// Writes an activated value into `state`.
+    pub fn write(self, state: &mut State, value: ParamValue) -> Result<()> {
+        self.validate(value)?;
+        match (self, value) {
+            (ParamKey::AllowedDeltaThreshold, ParamValue::U64(v)) => {
+                state.allowed_delta_threshold = v
+            }
+            (ParamKey::Keeper, ParamValue::Pubkey(k)) => state.keeper = k,
+            (ParamKey::MaxDeltaReportAge, ParamValue::U64(v)) => {
+                state.max_delta_report_age = v as i64
+            }
+            (ParamKey::MaxOutflowBpsPerEpoch, ParamValue::Bps(v)) => {
+                state.max_outflow_bps_per_epoch = v;
+                // Restart the window so the cap applies to the current stake immediately.
+                state.outflow_epoch = Clock::get()?.epoch;
+                state.epoch_start_staked = state
+                    .total_staked
+                    .checked_add(state.queued_withdrawals)
+                    .ok_or(CustomError::MathOverflow)?;
+                state.epoch_outflow = 0;
+            }
+            (ParamKey::UnclaimedExpirySecs, ParamValue::U64(v)) => {
+                state.unclaimed_expiry_secs = v as i64
+            }
+            (ParamKey::MaxRebalanceStaleness, ParamValue::U64(v)) => {
+                state.max_rebalance_staleness = v as i64
+            }
+            (ParamKey::RewardRateBaseBps, ParamValue::Bps(v)) => state.reward_rate_base_bps = v,
+            (ParamKey::RewardRateSlope1Bps, ParamValue::Bps(v)) => state.reward_rate_slope1_bps = v,
+            (ParamKey::RewardRateSlope2Bps, ParamValue::Bps(v)) => state.reward_rate_slope2_bps = v,
+            (ParamKey::RewardKinkUtilizationBps, ParamValue::Bps(v)) => {
+                state.reward_kink_utilization_bps = v
+            }
+            (ParamKey::MaxDrawdownBps, ParamValue::Bps(v)) => state.max_drawdown_bps = v,
+            (ParamKey::MinRebalanceInterval, ParamValue::U64(v)) => {
+                state.min_rebalance_interval = v as i64;
+                state.refresh_next_crank_due()?;
+            }
+            (ParamKey::MinDistributionInterval, ParamValue::U64(v)) => {
+                state.min_distribution_interval = v as i64;
+                state.refresh_next_crank_due()?;
+            }
+            (ParamKey::MinStakeDuration, ParamValue::U64(v)) => state.min_stake_duration = v as i64,
+            (ParamKey::MaxAllowedLossBps, ParamValue::Bps(v)) => state.max_allowed_loss_bps = v,
+            (ParamKey::ReconcileToleranceBps, ParamValue::Bps(v)) => {
+                state.reconcile_tolerance_bps = v
+            }
+            (ParamKey::SmallBalanceThreshold, ParamValue::U64(v)) => {
+                state.small_balance_threshold = v
+            }
+            (ParamKey::BuybackEnabled, ParamValue::Bool(v)) => state.buyback_enabled = v,
+            (ParamKey::BuybackCapPerEpoch, ParamValue::U64(v)) => state.buyback_cap_per_epoch = v,
+            (ParamKey::TierCap(tier), ParamValue::U64(v)) => state.tier_caps[tier as usize] = v,
+            // Hedges already open on the old venue are unwound there by the keeper.
+            (ParamKey::HedgeVenue, ParamValue::Venue(v)) => state.hedge_venue = v,
+            (ParamKey::EmissionMode, ParamValue::Emission(v)) => state.emission_mode = v,
+            (ParamKey::RewardPerSecond, ParamValue::U64(v)) => state.reward_per_second = v,
+            (ParamKey::EmissionApyBps, ParamValue::Bps(v)) => state.emission_apy_bps = v,
+            (ParamKey::FeeSweepCosignThreshold, ParamValue::U64(v)) => {
+                state.fee_sweep_cosign_threshold = v
+            }
+            (ParamKey::LpEmissionBps, ParamValue::Bps(v)) => state.lp_emission_bps = v,
+            (ParamKey::RewardMintCap, ParamValue::U64(v)) => state.reward_mint_cap = v,
+            (ParamKey::MaxSingleHedgeNotional, ParamValue::U64(v)) => {
+                state.max_single_hedge_notional = v
+            }
+            (ParamKey::StrategyCapacity, ParamValue::U64(v)) => state.strategy_capacity = v,
+            _ => return err!(CustomError::InvalidParameter),
+        }
+        Ok(())
+    }
+}
+
+/// Risk-engine parameters that must stay mutually consistent; see `check`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct RiskParams {
+    /// Loss, in bps of collateral, past which a position is liquidatable.
+    pub max_allowed_loss_bps: u64,
+    /// Initial-margin leverage limit, as notional in bps of collateral.
+    pub max_leverage_bps: u64,
+    pub max_drawdown_bps: u64,
+    pub reconcile_tolerance_bps: u64,
+}
+
+impl RiskParams {
+    /// The vault's current bundle, at the loosest leverage any collateral may be given.
+    pub fn of(state: &State) -> Self {
+        RiskParams {
+            max_allowed_loss_bps: state.max_allowed_loss_bps,
+            max_leverage_bps: MAX_LEVERAGE_LIMIT_BPS,
+            max_drawdown_bps: state.max_drawdown_bps,
+            reconcile_tolerance_bps: state.reconcile_tolerance_bps,
+        }
+    }
+
+    /// The bundle with a proposed parameter change applied; other keys leave it unchanged.
+    pub fn with_change(mut self, key: ParamKey, value: ParamValue) -> Self {
+        match (key, value) {
+            (ParamKey::MaxAllowedLossBps, ParamValue::Bps(v)) => self.max_allowed_loss_bps = v,
+            (ParamKey::MaxDrawdownBps, ParamValue::Bps(v)) => self.max_drawdown_bps = v,
+            (ParamKey::ReconcileToleranceBps, ParamValue::Bps(v)) => {
+                self.reconcile_tolerance_bps = v
+            }
+            _ => {}
+        }
+        self
+    }
+
+    /// Initial margin in bps of notional.
+    pub fn initial_margin_bps(&self) -> u64 {
+        BPS_DENOMINATOR * BPS_DENOMINATOR / self.max_leverage_bps.max(1)
+    }
+
+    /// Maintenance margin in bps of notional: the equity a position opened at full leverage has
+    /// left when it becomes liquidatable.
+    pub fn maintenance_margin_bps(&self) -> u64 {
+        let remaining_bps = BPS_DENOMINATOR.saturating_sub(self.max_allowed_loss_bps);
+        self.initial_margin_bps() * remaining_bps / BPS_DENOMINATOR
+    }
+
+    pub fn check(&self) -> Result<()> {
+        require!(
+            (BPS_DENOMINATOR..=MAX_LEVERAGE_LIMIT_BPS).contains(&self.max_leverage_bps)
+                && self.max_allowed_loss_bps <= BPS_DENOMINATOR,
+            CustomError::InvalidParameter
+        );
+        // A position opened at the limit must start healthy, and still hold equity when it
+        // becomes liquidatable.
+        let maintenance = self.maintenance_margin_bps();
+        require!(
+            maintenance > 0 && maintenance < self.initial_margin_bps(),
+            CustomError::InconsistentRiskParams
+        );
+        // Reconciliation must flag an accounting gap before it is large enough to trip the
+        // drawdown breaker.
+        require!(
+            self.max_drawdown_bps == 0 || self.reconcile_tolerance_bps < self.max_drawdown_bps,
+            CustomError::InconsistentRiskParams
+        );
+        Ok(())
+    }
+}
+
+/// Typed parameter value.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum ParamValue {
+    U64(u64),
+    Bps(u64),
+    Pubkey(Pubkey),
+    Bool(bool),
+    Venue(HedgeVenue),
+    Emission(EmissionMode),
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct ParamEntry {
+    pub key: ParamKey,
+    /// Value currently in force.
+    pub value: ParamValue,
+    /// Queued value and the time from which it may be activated.
+    pub pending: Option<ParamValue>,
+    pub activates_at: i64,
+}
+
+/// Result of `preview_liquidation`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct LiquidationPreview {
+    pub eligible: bool,
+    pub mark_price: u64,
+    /// Funding the position would pay (negative: receive) when next touched.
+    pub funding_paid: i64,
+    pub collateral_seized: u64,
+}
+
+/// Guarded launch phases, advanced in order by the state owner.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum LaunchPhase {
+    /// Only addresses in the whitelist merkle tree may stake, up to the per-wallet cap.
+    Whitelist,
+    /// Anyone may stake, up to the per-wallet cap.
+    Capped,
+    /// No launch restrictions.
+    Open,
+}
+
+/// NAV per share at the start of an epoch, at `[b"epoch_snapshot", state, epoch]`.
+#[account]
+#[derive(InitSpace)]
+pub struct EpochSnapshot {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub epoch: u64,
+    /// NAV per share, scaled by PRICE_PRECISION.
+    pub nav_per_share: u64,
+    pub recorded_at: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct UserStake {
+    pub amount: u64,
+    pub last_update: i64,
+    pub reward_index_snapshot: u128,
+    pub pending_rewards: u64,
+    pub last_claim: i64,
+    pub queued_withdrawal: u64,
+    pub lifetime_accrued: u64,
+    pub votes_cast_this_epoch: u32,
+    pub vote_epoch: u64,
+    /// Shares locked in the user's vote escrow.
+    pub escrowed: u64,
+    /// Epoch of the user's last `cast_vote`.
+    pub last_cast_epoch: Option<u64>,
+    /// Extra rewards from a linked NFT boost campaign, and when that boost ends.
+    pub nft_boost_bps: u64,
+    pub nft_boost_expires_at: i64,
+    pub tier: Tier,
+    /// Total ever staked by the user, checked against their tier's cap.
+    pub lifetime_deposited: u64,
+    /// Opted out of rewards: the stake accrues nothing and its share is sent to the treasury.
+    pub principal_only: bool,
+    /// Whether the stake is listed in the stake index, and on which page.
+    pub indexed: bool,
+    pub index_page: u32,
+    /// Shares credited for deposits of non-base collateral, by mint, that `unstake_asset` can
+    /// return in kind. Unused slots have the default mint.
+    pub asset_deposits: [AssetDeposit; USER_ASSET_SLOTS],
+    /// Risk bucket chosen at stake time, and the stake's snapshot of `State::lending_pnl_index`.
+    pub risk_bucket: RiskBucket,
+    pub lending_pnl_snapshot: i128,
+    /// Base tokens deposited beyond `State::max_single_hedge_notional`, credited as stake once
+    /// `State::last_hedged_at` moves past `pending_tranche_after`.
+    pub pending_tranche: u64,
+    pub pending_tranche_after: i64,
+    /// Pending rewards above which anyone may deliver them with `auto_claim_for`; zero opts out.
+    pub auto_claim_threshold: u64,
+    /// The stake's snapshot of `State::queue_fee_index`.
+    pub queue_fee_snapshot: u128,
+    /// `PendingDeposit`s of this stake still in the deposit queue; `exit` waits for them.
+    pub queued_deposits: u32,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+/// Shares a stake holds from deposits of one collateral mint.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct AssetDeposit {
+    pub mint: Pubkey,
+    pub shares: u64,
+    /// Tokens of `mint` deposited for those shares, net of fees.
+    pub tokens: u64,
+}
+
+impl UserStake {
+    /// Shares free to unstake, transfer or migrate.
+    pub fn unlocked(&self) -> u64 {
+        self.amount.saturating_sub(self.escrowed)
+    }
+
+    /// Sets the stake's risk bucket. An existing stake must stay in the bucket it was opened in.
+    fn choose_risk_bucket(&mut self, bucket: RiskBucket) -> Result<()> {
+        if self.amount == 0 {
+            self.risk_bucket = bucket;
+        }
+        require!(self.risk_bucket == bucket, CustomError::RiskBucketMismatch);
+        Ok(())
+    }
+
+    /// Shares redeemable in `mint`, capped by the stake's current balance.
+    pub fn asset_deposit(&self, mint: Pubkey) -> u64 {
+        self.asset_deposits
+            .iter()
+            .find(|d| d.mint == mint)
+            .map_or(0, |d| d.shares.min(self.amount))
+    }
+
+    /// Adds `shares`, bought with `tokens` of `mint`, to its slot, claiming a free slot for a new
+    /// mint.
+    fn record_asset_deposit(&mut self, mint: Pubkey, shares: u64, tokens: u64) -> Result<()> {
+        let slot = match self.asset_deposits.iter().position(|d| d.mint == mint) {
+            Some(i) => i,
+            None => self
+                .asset_deposits
+                .iter()
+                .position(|d| d.shares == 0)
+                .ok_or(CustomError::AssetSlotsFull)?,
+        };
+        let deposit = &mut self.asset_deposits[slot];
+        deposit.mint = mint;
+        deposit.shares = deposit.shares.checked_add(shares).ok_or(CustomError::MathOverflow)?;
+        deposit.tokens = deposit.tokens.checked_add(tokens).ok_or(CustomError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Removes up to `shares` from `mint`'s slot, freeing it once empty. Returns the deposited
+    /// tokens released with them, pro rata and rounded down.
+    fn release_asset_deposit(&mut self, mint: Pubkey, shares: u64) -> Result<u64> {
+        let Some(deposit) = self.asset_deposits.iter_mut().find(|d| d.mint == mint) else {
+            return Ok(0);
+        };
+        let shares = shares.min(deposit.shares);
+        let tokens = if shares == deposit.shares {
+            deposit.tokens
+        } else {
+            mul_div_u64(deposit.tokens, shares, deposit.shares, Rounding::Down)
+                .ok_or(CustomError::MathOverflow)?
+        };
+        deposit.shares -= shares;
+        deposit.tokens -= tokens;
+        if deposit.shares == 0 {
+            *deposit = AssetDeposit::default();
+        }
+        Ok(tokens)
+    }
+}
+
+/// Staked shares locked for extra voting weight (veDNT).
+#[account]
+#[derive(InitSpace)]
+pub struct VoteEscrow {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub locked_at: i64,
+    pub unlock_at: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+/// A governance-approved NFT collection whose holders earn boosted rewards until `end_ts`.
+#[account]
+#[derive(InitSpace)]
+pub struct BoostCollection {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub collection_mint: Pubkey,
+    pub multiplier_bps: u64,
+    pub end_ts: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+/// Marks an NFT as already linked to a stake in this vault.
+#[account]
+#[derive(InitSpace)]
+pub struct NftLink {
+    pub bump: u8,
+    pub owner: Pubkey,
+    pub collection_mint: Pubkey,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+/// Weighted votes cast in the current epoch; reset on the first vote of a new epoch.
+#[account]
+#[derive(InitSpace)]
+pub struct VoteTally {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub epoch: u64,
+    pub yes_weight: u128,
+    pub no_weight: u128,
+    /// Epoch whose passed result was last queued by `vote_on_risk_params`.
+    pub executed_epoch: Option<u64>,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+/// Lifetime reward history for one user in one vault, for wallets and tax tooling.
+#[account]
+#[derive(InitSpace)]
+pub struct UserRewardStats {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub lifetime_earned: u64,
+    pub lifetime_claimed: u64,
+    pub lifetime_compounded: u64,
+    pub last_claim: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+/// A merkle-committed batch of retroactive rewards with a bitmap of claimed leaves.
+#[account]
+#[derive(InitSpace)]
+pub struct RewardDistribution {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub distribution_id: u64,
+    pub merkle_root: [u8; 32],
+    pub total_claimed: u64,
+    pub max_claims: u32,
+    /// One bit per claim; sized from `max_claims` at creation.
+    #[max_len(0)]
+    pub claimed_bitmap: Vec<u8>,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+/// Record of a referenced stake deposit, at `[b"deposit_receipt", state, reference]`, so payment
+/// processors can match on-chain deposits to their own order IDs.
+#[account]
+#[derive(InitSpace)]
+pub struct DepositReceipt {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub reference: [u8; 32],
+    pub mint: Pubkey,
+    pub amount: u64,
+    /// `seq` of the matching `Staked` event.
+    pub event_seq: u64,
+    pub deposited_at: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+/// A deposit waiting for strategy capacity, at `[b"pending_deposit", state, id]`. Queued tokens
+/// sit in the vault unhedged and earn nothing until admitted.
+#[account]
+#[derive(InitSpace)]
+pub struct PendingDeposit {
+    pub bump: u8,
+    pub state: Pubkey,
+    /// Position in the queue; admitted once `State::pending_deposit_head` reaches it.
+    pub id: u64,
+    pub user: Pubkey,
+    /// Stake to credit on admission, in base-token value.
+    pub amount: u64,
+    pub queued_at: i64,
+    /// Deposited mint (the base mint, or a registered collateral) and the tokens of it held for
+    /// this deposit, which `cancel_pending_deposit` refunds.
+    pub mint: Pubkey,
+    pub deposited: u64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+/// Privileged roles that can be delegated by the state owner.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum Role {
+    /// Assigns staker tiers and maintains compliance controls.
+    Compliance,
+    /// Runs operational cranks.
+    Operator,
+    /// Manages risk limits and emergency responses.
+    RiskManager,
+    /// Moves protocol-owned funds.
+    Treasury,
+    /// Sweeps fee revenue to the treasury; see `sweep_fees`.
+    FeeCollector,
+    /// Posts and executes discretionary hedge trades; see `TradeIntent`.
+    StrategyManager,
+}
+
+/// Grant of `role` to `holder`, at `[b"role", state, role, holder]`; revoking closes it.
+#[account]
+#[derive(InitSpace)]
+pub struct RoleAssignment {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub role: Role,
+    pub holder: Pubkey,
+    pub granted_by: Pubkey,
+    pub granted_at: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+/// Blocklist entry; its existence at `[b"blocked", state, address]` blocks the address.
+#[account]
+#[derive(InitSpace)]
+pub struct Blocked {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub address: Pubkey,
+    pub blocked_at: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+/// Compliance tier assigned to a staker; each tier has its own lifetime deposit cap.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum Tier {
+    Unverified,
+    Basic,
+    Verified,
+    Institutional,
+}
+
+impl Tier {
+    pub const COUNT: usize = 4;
+}
+
+/// Vote-escrow lock durations offered by `lock_stake`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum LockTier {
+    ThreeMonths,
+    SixMonths,
+    OneYear,
+    TwoYears,
+    FourYears,
+}
+
+impl LockTier {
+    /// Lock length in seconds; `FourYears` is `VOTE_ESCROW_MAX_LOCK_SECS`.
+    pub fn duration_secs(self) -> i64 {
+        match self {
+            LockTier::ThreeMonths => VOTE_ESCROW_MAX_LOCK_SECS / 16,
+            LockTier::SixMonths => VOTE_ESCROW_MAX_LOCK_SECS / 8,
+            LockTier::OneYear => VOTE_ESCROW_MAX_LOCK_SECS / 4,
+            LockTier::TwoYears => VOTE_ESCROW_MAX_LOCK_SECS / 2,
+            LockTier::FourYears => VOTE_ESCROW_MAX_LOCK_SECS,
+        }
+    }
+}
+
+/// Where a staker's idle capital may be deployed. Lending yield and losses are booked only to
+/// `HedgedLending` shares.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum RiskBucket {
+    /// Delta-neutral hedge only.
+    HedgedOnly,
+    /// The hedge plus idle capital lent out on lending protocols.
+    HedgedLending,
+}
+
+/// How a vault sizes its reward emissions.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum EmissionMode {
+    /// NAV times the utilization-curve rate.
+    UtilizationCurve,
+    /// A flat `reward_per_second`, independent of TVL.
+    PerSecond,
+    /// NAV times a fixed `emission_apy_bps`, so emissions scale with TVL.
+    NavApy,
+}
+
+/// Vault strategies that book PnL separately.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum StrategyKind {
+    Hedge,
+    MarketMaking,
+}
+
+/// Basis-trade book for the hedge strategy: the vault holds spot and shorts the perp, earning the
+/// basis when it converges. Notionals are in base units; rebalances keep them in step with the
+/// delta report and realize basis on the perp notional they close.
+#[account]
+#[derive(InitSpace)]
+pub struct StrategyState {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub spot_notional: u64,
+    pub perp_notional: u64,
+    /// Notional-weighted perp premium over spot at which the open hedge was entered.
+    pub entry_basis_bps: i64,
+    /// Realized basis not yet paid out by `distribute_arbitrage_profits`; losses carry forward.
+    pub pending_basis_pnl: i64,
+    pub lifetime_basis_pnl: i64,
+    pub last_update: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+impl StrategyState {
+    /// Moves the book to new leg sizes at `basis_bps`. Added perp notional blends into the entry
+    /// basis; closed perp notional realizes `entry - current` basis on the closed amount.
+    pub fn record_hedge(
+        &mut self,
+        spot_notional: u64,
+        perp_notional: u64,
+        basis_bps: i64,
+    ) -> Result<()> {
+        if perp_notional > self.perp_notional {
+            let blended = (self.entry_basis_bps as i128 * self.perp_notional as i128
+                + basis_bps as i128 * (perp_notional - self.perp_notional) as i128)
+                / perp_notional as i128;
+            self.entry_basis_bps = i64::try_from(blended).map_err(|_| CustomError::MathOverflow)?;
+        } else if perp_notional < self.perp_notional {
+            let closed = (self.perp_notional - perp_notional) as i128;
+            let captured = closed * (self.entry_basis_bps as i128 - basis_bps as i128)
+                / BPS_DENOMINATOR as i128;
+            let captured = i64::try_from(captured).map_err(|_| CustomError::MathOverflow)?;
+            self.pending_basis_pnl = self
+                .pending_basis_pnl
+                .checked_add(captured)
+                .ok_or(CustomError::MathOverflow)?;
+            self.lifetime_basis_pnl = self
+                .lifetime_basis_pnl
+                .checked_add(captured)
+                .ok_or(CustomError::MathOverflow)?;
+            if perp_notional == 0 {
+                self.entry_basis_bps = 0;
+            }
+        }
+        self.spot_notional = spot_notional;
+        self.perp_notional = perp_notional;
+        self.last_update = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    /// Takes the positive part of the pending basis PnL for distribution.
+    pub fn take_distributable_basis(&mut self) -> u64 {
+        if self.pending_basis_pnl <= 0 {
+            return 0;
+        }
+        let amount = self.pending_basis_pnl as u64;
+        self.pending_basis_pnl = 0;
+        amount
+    }
+}
+
+/// Per-strategy PnL attribution, so NAV changes can be decomposed on-chain. Amounts are in base
+/// units; positive values are gains to the vault.
+#[account]
+#[derive(InitSpace)]
+pub struct PnlBucket {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub strategy: StrategyKind,
+    pub funding_income: i64,
+    pub basis_pnl: i64,
+    pub spread_pnl: i64,
+    pub maker_rebates: i64,
+    pub fees_paid: i64,
+    pub last_update: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+impl PnlBucket {
+    pub fn record_funding(&mut self, funding: i64) -> Result<()> {
+        self.funding_income = self
+            .funding_income
+            .checked_add(funding)
+            .ok_or(CustomError::MathOverflow)?;
+        self.last_update = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ParamRegistry {
+    pub bump: u8,
+    pub state: Pubkey,
+    #[max_len(PARAM_COUNT)]
+    pub entries: Vec<ParamEntry>,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+impl ParamRegistry {
+    pub fn entry_mut(&mut self, key: ParamKey) -> Result<&mut ParamEntry> {
+        self.entries
+            .iter_mut()
+            .find(|e| e.key == key)
+            .ok_or(CustomError::InvalidParameter.into())
+    }
+}
+
+/// Registry of accepted collateral types for a vault, grown with `realloc`.
+#[account]
+#[derive(InitSpace)]
+pub struct CollateralRegistry {
+    pub bump: u8,
+    pub state: Pubkey,
+    /// Sized by `realloc`; see `CollateralRegistry::space`.
+    #[max_len(0)]
+    pub collaterals: Vec<CollateralConfig>,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+impl CollateralRegistry {
+    /// Account size holding `entries` collateral configs.
+    pub fn space(entries: usize) -> usize {
+        8 + CollateralRegistry::INIT_SPACE + entries * CollateralConfig::INIT_SPACE
+    }
+
+    /// Applies a deposit or withdrawal of `mint` to its tracked spot exposure.
+    pub fn track_deposit(&mut self, mint: Pubkey, added: u64, removed: u64) -> Result<()> {
+        let config = self
+            .collaterals
+            .iter_mut()
+            .find(|c| c.mint == mint)
+            .ok_or(CustomError::UnknownCollateral)?;
+        config.deposited_amount = config
+            .deposited_amount
+            .checked_add(added)
+            .ok_or(CustomError::MathOverflow)?
+            .checked_sub(removed)
+            .ok_or(CustomError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct CollateralConfig {
+    pub mint: Pubkey,
+    pub oracle: Pubkey,
+    /// Risk weight applied to the normalized deposit value.
+    pub weight_bps: u64,
+    /// Cleared when the collateral is deprecated; withdrawals remain possible.
+    pub deposits_enabled: bool,
+    /// SPL stake pool backing a liquid staking token, or the default key for other assets.
+    pub lst_stake_pool: Pubkey,
+    pub deposit_fee_bps: u64,
+    pub withdraw_fee_bps: u64,
+    /// Largest notional-to-collateral ratio, in bps, a position margined in this asset may open
+    /// at or withdraw down to (the initial margin). Liquidation uses the looser maintenance
+    /// threshold, `State::max_allowed_loss_bps`.
+    pub max_leverage_bps: u64,
+    /// Stablecoin priced at a fixed $1 while its oracle stays inside the depeg band.
+    pub is_stable: bool,
+    /// Tokens of this mint held in its collateral vault on behalf of stakers, and their base-asset
+    /// value at the last `mark_collateral`, so hedges can be sized asset by asset.
+    pub deposited_amount: u64,
+    pub usd_value_at_last_mark: u64,
+}
+
+/// How `add_collateral` prices a new collateral type.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CollateralKind {
+    /// Priced from its oracle alone.
+    Standard,
+    /// USD stablecoin priced at a fixed $1 while its oracle stays inside the depeg band.
+    Stable,
+    /// Liquid staking token; `stake_pool` is the SPL stake pool converting it to SOL.
+    LiquidStaking { stake_pool: Pubkey },
+}
+
+/// Ring buffer of recent funding prints used for the funding TWAP.
+#[account]
+#[derive(InitSpace)]
+pub struct FundingHistory {
+    pub bump: u8,
+    pub state: Pubkey,
+    /// Slot the next observation is written to.
+    pub head: u8,
+    pub count: u8,
+    pub observations: [FundingObservation; FUNDING_HISTORY_LEN],
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct FundingObservation {
+    pub rate: u64,
+    pub timestamp: i64,
+}
+
+/// Keeper-posted hedge exposure used to enforce `allowed_delta_threshold`.
+#[account]
+#[derive(InitSpace)]
+pub struct DeltaReport {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub spot_exposure: i64,
+    pub perp_exposure: i64,
+    pub net_delta: i64,
+    pub updated_at: i64,
+    pub reporter: Pubkey,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+/// A trader's perp position, margined with base-mint collateral held in the vault.
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub owner: Pubkey,
+    pub collateral: u64,
+    /// Signed notional in base units: positive is long, negative is short.
+    pub size: i64,
+    /// Mark price at open, scaled by PRICE_PRECISION.
+    pub entry_price: u64,
+    /// Liquidation is allowed after this time if still unhealthy; 0 when not margin called.
+    pub margin_call_deadline: i64,
+    /// `State::cumulative_funding_index` when funding was last applied to this position.
+    pub last_funding_index: i128,
+    /// PnL/leverage ranking used by auto-deleveraging, refreshed by `update_adl_rank`.
+    pub adl_score: u64,
+    /// Progress of an ongoing chunked liquidation: size closed so far and chunks taken.
+    pub liquidated_size: u64,
+    pub liquidation_chunks: u32,
+    /// Mark price at which the position becomes unhealthy, scaled by PRICE_PRECISION; refreshed
+    /// whenever the position is touched. Zero when flat.
+    pub liquidation_price: u64,
+    /// Take-profit and stop-loss trigger prices, scaled by PRICE_PRECISION; zero when unset.
+    pub take_profit_price: u64,
+    pub stop_loss_price: u64,
+    /// Ring buffer of the last POSITION_HISTORY_LEN actions; `history_head` is the slot the next
+    /// one is written to.
+    pub history_head: u8,
+    pub history_count: u8,
+    pub history: [PositionAction; POSITION_HISTORY_LEN],
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+/// What happened to a position, for its history.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, InitSpace)]
+pub enum PositionActionKind {
+    /// Amount is the signed size opened.
+    #[default]
+    Open,
+    /// Amount is the collateral added or removed.
+    AddMargin,
+    RemoveMargin,
+    /// Amount is the signed funding settled: positive when the trader paid.
+    Funding,
+    /// Amount is zero.
+    MarginCall,
+    /// Amount is the size closed voluntarily, by auto-deleveraging, or by a liquidation chunk.
+    Deleverage,
+    AutoDeleverage,
+    LiquidationChunk,
+    /// Amount is the collateral seized.
+    Liquidation,
+    /// Amount is the trader's realized PnL.
+    TriggerClose,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct PositionAction {
+    pub kind: PositionActionKind,
+    pub amount: i64,
+    pub timestamp: i64,
+}
+
+impl Position {
+    /// Appends an action to the history ring, overwriting the oldest once full.
+    pub fn record(&mut self, kind: PositionActionKind, amount: i64) -> Result<()> {
+        let head = self.history_head as usize;
+        self.history[head] = PositionAction {
+            kind,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        };
+        self.history_head = ((head + 1) % POSITION_HISTORY_LEN) as u8;
+        self.history_count = (self.history_count as usize + 1).min(POSITION_HISTORY_LEN) as u8;
+        Ok(())
+    }
+
+    /// True when `mark_price` has reached the take-profit or stop-loss on the position's side.
+    pub fn trigger_hit(&self, mark_price: u64) -> bool {
+        let (tp, sl) = (self.take_profit_price, self.stop_loss_price);
+        if self.size > 0 {
+            (tp != 0 && mark_price >= tp) || (sl != 0 && mark_price <= sl)
+        } else if self.size < 0 {
+            (tp != 0 && mark_price <= tp) || (sl != 0 && mark_price >= sl)
+        } else {
+            false
+        }
+    }
+
+    /// Mark price at which the unrealized loss reaches `max_loss_bps` of collateral (see
+    /// `is_position_healthy`), scaled by PRICE_PRECISION. Zero when flat, or for a long whose
+    /// collateral covers a fall to zero.
+    pub fn compute_liquidation_price(&self, max_loss_bps: u64) -> u64 {
+        let abs_size = self.size.unsigned_abs();
+        if abs_size == 0 {
+            return 0;
+        }
+        let max_loss = (self.collateral as u128).saturating_mul(max_loss_bps as u128)
+            / BPS_DENOMINATOR as u128;
+        let price_move = (self.entry_price as u128).saturating_mul(max_loss) / abs_size as u128;
+        let price = if self.size > 0 {
+            (self.entry_price as u128).saturating_sub(price_move)
+        } else {
+            (self.entry_price as u128).saturating_add(price_move)
+        };
+        price.min(u64::MAX as u128) as u64
+    }
+
+    pub fn refresh_liquidation_price(&mut self, max_loss_bps: u64) {
+        self.liquidation_price = self.compute_liquidation_price(max_loss_bps);
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Accounts Contexts
+// -----------------------------------------------------------------------------
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        seeds = [b"config"],
+        bump,
+        payer = authority,
+        space = 8 + Config::INIT_SPACE,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, program::DntPerpetualToken>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ CustomError::Unauthorized,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ CustomError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_id: u64)]
+pub struct CreateVault<'info> {
+    #[account(
+        init,
+        seeds = [b"state", payer.key().as_ref(), &vault_id.to_le_bytes()],
+        bump,
+        payer = payer,
+        space = 8 + State::INIT_SPACE,
+    )]
+    pub state: Account<'info, State>,
+    pub base_mint: Account<'info, Mint>,
+    // Vault share mint, controlled by the state PDA.
+    #[account(
+        init,
+        seeds = [b"share_mint", state.key().as_ref()],
+        bump,
+        payer = payer,
+        mint::decimals = base_mint.decimals,
+        mint::authority = state,
+    )]
+    pub share_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeVault<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(address = state.base_mint @ CustomError::VaultMintMismatch)]
+    pub base_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        seeds = [b"vault", state.key().as_ref()],
+        bump,
+        payer = state_owner,
+        token::mint = base_mint,
+        token::authority = state,
+    )]
+    pub vault_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeCollateralVault<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(seeds = [b"collateral_registry", state.key().as_ref()], bump = collateral_registry.bump)]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+    pub collateral_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        seeds = [b"collateral_vault", state.key().as_ref(), collateral_mint.key().as_ref()],
+        bump,
+        payer = state_owner,
+        token::mint = collateral_mint,
+        token::authority = state,
+    )]
+    pub collateral_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageLaunch<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    // Only the owner the state PDA is derived from may manage the launch.
+    pub state_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, proof: Vec<[u8; 32]>, reference: Option<[u8; 32]>)]
+pub struct StakeAccounts<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    // Assume the user stake account is already initialized.
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: Blocklist PDA for the user; must not exist.
+    #[account(seeds = [b"blocked", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub blocked: UncheckedAccount<'info>,
+    /// CHECK: This account holds the user's $DNT tokens.
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key(),
+        constraint = user_token_account.mint == state.base_mint @ CustomError::VaultMintMismatch,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"vault", state.key().as_ref()],
+        bump,
+        constraint = vault_account.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = vault_account.mint == state.base_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub vault_account: Account<'info, TokenAccount>,
+    #[account(seeds = [b"delta_report", state.key().as_ref()], bump = delta_report.bump)]
+    pub delta_report: Account<'info, DeltaReport>,
+    #[account(mut, seeds = [b"ledger", state.key().as_ref()], bump = ledger.load()?.bump)]
+    pub ledger: AccountLoader<'info, Ledger>,
+    // Page to list the stake on if it is not indexed yet.
+    #[account(
+        mut,
+        seeds = [
+            b"stake_index",
+            state.key().as_ref(),
+            &stake_index.load()?.page.to_le_bytes(),
+        ],
+        bump = stake_index.load()?.bump,
+    )]
+    pub stake_index: AccountLoader<'info, StakeIndexPage>,
+    /// Required exactly when `reference` is given; a reference can only be used once per vault.
+    #[account(
+        init,
+        seeds = [b"deposit_receipt", state.key().as_ref(), &reference.unwrap_or_default()],
+        bump,
+        payer = user,
+        space = 8 + DepositReceipt::INIT_SPACE,
+    )]
+    pub deposit_receipt: Option<Account<'info, DepositReceipt>>,
+    /// Required when the deposit exceeds the strategy's remaining capacity; queues the excess.
+    #[account(
+        init,
+        seeds = [
+            b"pending_deposit",
+            state.key().as_ref(),
+            &state.pending_deposit_tail.to_le_bytes(),
+        ],
+        bump,
+        payer = user,
+        space = 8 + PendingDeposit::INIT_SPACE,
+    )]
+    pub pending_deposit: Option<Account<'info, PendingDeposit>>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AdmitPendingDeposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    // The queue head; closed back to the depositor once admitted.
+    #[account(
+        mut,
+        seeds = [
+            b"pending_deposit",
+            state.key().as_ref(),
+            &state.pending_deposit_head.to_le_bytes(),
+        ],
+        bump = pending_deposit.bump,
+        has_one = user @ CustomError::Unauthorized,
+        close = user,
+    )]
+    pub pending_deposit: Account<'info, PendingDeposit>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    /// CHECK: Depositor; checked against `pending_deposit.user` and refunded its rent.
+    #[account(mut)]
+    pub user: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"stake_index",
+            state.key().as_ref(),
+            &stake_index.load()?.page.to_le_bytes(),
+        ],
+        bump = stake_index.load()?.bump,
+    )]
+    pub stake_index: AccountLoader<'info, StakeIndexPage>,
+    #[account(mut, seeds = [b"ledger", state.key().as_ref()], bump = ledger.load()?.bump)]
+    pub ledger: AccountLoader<'info, Ledger>,
+}
+
+#[derive(Accounts)]
+pub struct CancelPendingDeposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    // The queue head; closed back to the depositor.
+    #[account(
+        mut,
+        seeds = [
+            b"pending_deposit",
+            state.key().as_ref(),
+            &state.pending_deposit_head.to_le_bytes(),
+        ],
+        bump = pending_deposit.bump,
+        has_one = user @ CustomError::Unauthorized,
+        close = user,
+    )]
+    pub pending_deposit: Account<'info, PendingDeposit>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key(),
+        constraint = user_token_account.mint == pending_deposit.mint
+            @ CustomError::InvalidVaultMint,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    // The vault holding the deposit: the base vault, or the collateral vault for its mint.
+    #[account(mut)]
+    pub vault_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"collateral_registry", state.key().as_ref()],
+        bump = collateral_registry.bump,
+    )]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+    #[account(mut, seeds = [b"ledger", state.key().as_ref()], bump = ledger.load()?.bump)]
+    pub ledger: AccountLoader<'info, Ledger>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ActivatePendingTranche<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    /// CHECK: Staker whose tranche is activated; only used to derive `user_stake`.
+    pub user: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"stake_index",
+            state.key().as_ref(),
+            &stake_index.load()?.page.to_le_bytes(),
+        ],
+        bump = stake_index.load()?.bump,
+    )]
+    pub stake_index: AccountLoader<'info, StakeIndexPage>,
+}
+
+#[derive(Accounts)]
+pub struct StakeWithDelegate<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    /// CHECK: Staker; owner of `user_token_account`, not required to sign.
+    pub user: UncheckedAccount<'info>,
+    /// CHECK: Blocklist PDA for the user; must not exist.
+    #[account(seeds = [b"blocked", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub blocked: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key(),
+        constraint = user_token_account.mint == state.base_mint @ CustomError::VaultMintMismatch,
+        constraint = user_token_account.delegate == Some(state.key()).into()
+            @ CustomError::InsufficientDelegation,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"vault", state.key().as_ref()],
+        bump,
+        constraint = vault_account.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = vault_account.mint == state.base_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub vault_account: Account<'info, TokenAccount>,
+    #[account(seeds = [b"delta_report", state.key().as_ref()], bump = delta_report.bump)]
+    pub delta_report: Account<'info, DeltaReport>,
+    #[account(mut, seeds = [b"ledger", state.key().as_ref()], bump = ledger.load()?.bump)]
+    pub ledger: AccountLoader<'info, Ledger>,
+    // Page to list the stake on if it is not indexed yet.
+    #[account(
+        mut,
+        seeds = [
+            b"stake_index",
+            state.key().as_ref(),
+            &stake_index.load()?.page.to_le_bytes(),
+        ],
+        bump = stake_index.load()?.bump,
+    )]
+    pub stake_index: AccountLoader<'info, StakeIndexPage>,
+    /// Required when the deposit exceeds the strategy's remaining capacity; queues the excess.
+    #[account(
+        init,
+        seeds = [
+            b"pending_deposit",
+            state.key().as_ref(),
+            &state.pending_deposit_tail.to_le_bytes(),
+        ],
+        bump,
+        payer = relayer,
+        space = 8 + PendingDeposit::INIT_SPACE,
+    )]
+    pub pending_deposit: Option<Account<'info, PendingDeposit>>,
+    /// Submits and pays for the transaction.
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BatchStake<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    /// Funds every entry and pays for any `PendingDeposit` they need.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", state.key().as_ref()],
+        bump,
+        constraint = vault_account.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = vault_account.mint == state.base_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub vault_account: Account<'info, TokenAccount>,
+    #[account(seeds = [b"delta_report", state.key().as_ref()], bump = delta_report.bump)]
+    pub delta_report: Account<'info, DeltaReport>,
+    #[account(mut, seeds = [b"ledger", state.key().as_ref()], bump = ledger.load()?.bump)]
+    pub ledger: AccountLoader<'info, Ledger>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BatchUnstake<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"vault", state.key().as_ref()],
+        bump,
+        constraint = vault_account.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = vault_account.mint == state.base_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub vault_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"fee_vault", state.key().as_ref(), state.base_mint.as_ref()],
+        bump,
+        constraint = fee_vault.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = fee_vault.mint == state.base_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(seeds = [b"collateral_registry", state.key().as_ref()], bump = collateral_registry.bump)]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+    #[account(mut, seeds = [b"ledger", state.key().as_ref()], bump = ledger.load()?.bump)]
+    pub ledger: AccountLoader<'info, Ledger>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct StakeSol<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: Blocklist PDA for the user; must not exist.
+    #[account(seeds = [b"blocked", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub blocked: UncheckedAccount<'info>,
+    #[account(address = native_mint::ID)]
+    pub native_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"collateral_vault", state.key().as_ref(), native_mint.key().as_ref()],
+        bump,
+        constraint = sol_vault.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = sol_vault.mint == native_mint::ID @ CustomError::InvalidVaultMint,
+    )]
+    pub sol_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"fee_vault", state.key().as_ref(), native_mint.key().as_ref()],
+        bump,
+        constraint = fee_vault.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = fee_vault.mint == native_mint::ID @ CustomError::InvalidVaultMint,
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"collateral_registry", state.key().as_ref()],
+        bump = collateral_registry.bump,
+    )]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+    #[account(seeds = [b"delta_report", state.key().as_ref()], bump = delta_report.bump)]
+    pub delta_report: Account<'info, DeltaReport>,
+    // Page to list the stake on if it is not indexed yet.
+    #[account(
+        mut,
+        seeds = [
+            b"stake_index",
+            state.key().as_ref(),
+            &stake_index.load()?.page.to_le_bytes(),
+        ],
+        bump = stake_index.load()?.bump,
+    )]
+    pub stake_index: AccountLoader<'info, StakeIndexPage>,
+    /// Required when the deposit exceeds the strategy's remaining capacity; queues the excess.
+    #[account(
+        init,
+        seeds = [
+            b"pending_deposit",
+            state.key().as_ref(),
+            &state.pending_deposit_tail.to_le_bytes(),
+        ],
+        bump,
+        payer = user,
+        space = 8 + PendingDeposit::INIT_SPACE,
+    )]
+    pub pending_deposit: Option<Account<'info, PendingDeposit>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakeSol<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(address = native_mint::ID)]
+    pub native_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"collateral_vault", state.key().as_ref(), native_mint.key().as_ref()],
+        bump,
+        constraint = sol_vault.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = sol_vault.mint == native_mint::ID @ CustomError::InvalidVaultMint,
+    )]
+    pub sol_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"fee_vault", state.key().as_ref(), native_mint.key().as_ref()],
+        bump,
+        constraint = fee_vault.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = fee_vault.mint == native_mint::ID @ CustomError::InvalidVaultMint,
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"collateral_registry", state.key().as_ref()],
+        bump = collateral_registry.bump,
+    )]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+    // Temporary WSOL account used to unwrap the payout; closed within the instruction.
+    #[account(
+        init,
+        seeds = [b"wsol_unwrap", state.key().as_ref(), user.key().as_ref()],
+        bump,
+        payer = user,
+        token::mint = native_mint,
+        token::authority = state,
+    )]
+    pub unwrap_account: Account<'info, TokenAccount>,
+    // The stake's index page, delisted from once the balance reaches zero.
+    #[account(
+        mut,
+        seeds = [
+            b"stake_index",
+            state.key().as_ref(),
+            &stake_index.load()?.page.to_le_bytes(),
+        ],
+        bump = stake_index.load()?.bump,
+    )]
+    pub stake_index: AccountLoader<'info, StakeIndexPage>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakeAsset<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    pub user: Signer<'info>,
+    #[account(mut, constraint = user_token_account.owner == user.key())]
+    pub user_token_account: Account<'info, TokenAccount>,
+    // Per-collateral vault for the withdrawn mint.
+    #[account(
+        mut,
+        seeds = [b"collateral_vault", state.key().as_ref(), user_token_account.mint.as_ref()],
+        bump,
+        constraint = vault_account.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = vault_account.mint == user_token_account.mint @ CustomError::InvalidVaultMint,
+    )]
+    pub vault_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"fee_vault", state.key().as_ref(), user_token_account.mint.as_ref()],
+        bump,
+        constraint = fee_vault.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = fee_vault.mint == user_token_account.mint @ CustomError::InvalidVaultMint,
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"collateral_registry", state.key().as_ref()],
+        bump = collateral_registry.bump,
+    )]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+    /// CHECK: SPL stake pool of an LST collateral; key and owner are validated when pricing.
+    pub lst_stake_pool: Option<UncheckedAccount<'info>>,
+    // The stake's index page, delisted from once the balance reaches zero.
+    #[account(
+        mut,
+        seeds = [
+            b"stake_index",
+            state.key().as_ref(),
+            &stake_index.load()?.page.to_le_bytes(),
+        ],
+        bump = stake_index.load()?.bump,
+    )]
+    pub stake_index: AccountLoader<'info, StakeIndexPage>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct MultiCollateralStakeAccounts<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    // Assume the user stake account is already initialized.
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: Blocklist PDA for the user; must not exist.
+    #[account(seeds = [b"blocked", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub blocked: UncheckedAccount<'info>,
+    /// CHECK: This account holds the user's collateral tokens.
+    #[account(mut, constraint = user_token_account.owner == user.key())]
+    pub user_token_account: Account<'info, TokenAccount>,
+    // Per-collateral vault for the deposited mint.
+    #[account(
+        mut,
+        seeds = [b"collateral_vault", state.key().as_ref(), user_token_account.mint.as_ref()],
+        bump,
+        constraint = vault_account.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = vault_account.mint == user_token_account.mint @ CustomError::InvalidVaultMint,
+    )]
+    pub vault_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"fee_vault", state.key().as_ref(), user_token_account.mint.as_ref()],
+        bump,
+        constraint = fee_vault.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = fee_vault.mint == user_token_account.mint @ CustomError::InvalidVaultMint,
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"collateral_registry", state.key().as_ref()],
+        bump = collateral_registry.bump,
+    )]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+    /// CHECK: SPL stake pool of an LST collateral; key and owner are validated when pricing.
+    pub lst_stake_pool: Option<UncheckedAccount<'info>>,
+    #[account(seeds = [b"delta_report", state.key().as_ref()], bump = delta_report.bump)]
+    pub delta_report: Account<'info, DeltaReport>,
+    // Page to list the stake on if it is not indexed yet.
+    #[account(
+        mut,
+        seeds = [
+            b"stake_index",
+            state.key().as_ref(),
+            &stake_index.load()?.page.to_le_bytes(),
+        ],
+        bump = stake_index.load()?.bump,
+    )]
+    pub stake_index: AccountLoader<'info, StakeIndexPage>,
+    /// Required when the deposit exceeds the strategy's remaining capacity; queues the excess.
+    #[account(
+        init,
+        seeds = [
+            b"pending_deposit",
+            state.key().as_ref(),
+            &state.pending_deposit_tail.to_le_bytes(),
+        ],
+        bump,
+        payer = user,
+        space = 8 + PendingDeposit::INIT_SPACE,
+    )]
+    pub pending_deposit: Option<Account<'info, PendingDeposit>>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeCollateralRegistry<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        seeds = [b"collateral_registry", state.key().as_ref()],
+        bump,
+        payer = state_owner,
+        space = CollateralRegistry::space(0),
+    )]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddCollateral<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        mut,
+        seeds = [b"collateral_registry", state.key().as_ref()],
+        bump = collateral_registry.bump,
+        realloc = CollateralRegistry::space(collateral_registry.collaterals.len() + 1),
+        realloc::payer = state_owner,
+        realloc::zero = false,
+    )]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveCollateral<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        mut,
+        seeds = [b"collateral_registry", state.key().as_ref()],
+        bump = collateral_registry.bump,
+    )]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+    pub state_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetCollateralFees<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        mut,
+        seeds = [b"collateral_registry", state.key().as_ref()],
+        bump = collateral_registry.bump,
+    )]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+    pub state_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFeeVault<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init,
+        seeds = [b"fee_vault", state.key().as_ref(), mint.key().as_ref()],
+        bump,
+        payer = state_owner,
+        token::mint = mint,
+        token::authority = state,
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeParamRegistry<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        seeds = [b"param_registry", state.key().as_ref()],
+        bump,
+        payer = state_owner,
+        space = 8 + ParamRegistry::INIT_SPACE,
+    )]
+    pub param_registry: Account<'info, ParamRegistry>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageParams<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        mut,
+        seeds = [b"param_registry", state.key().as_ref()],
+        bump = param_registry.bump,
+    )]
+    pub param_registry: Account<'info, ParamRegistry>,
+    pub state_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ActivateParamChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"param_registry", state.key().as_ref()],
+        bump = param_registry.bump,
+    )]
+    pub param_registry: Account<'info, ParamRegistry>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteStake<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(seeds = [b"collateral_registry", state.key().as_ref()], bump = collateral_registry.bump)]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+    /// CHECK: SPL stake pool of an LST collateral; key and owner are validated when pricing.
+    pub lst_stake_pool: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct QuoteUnstake<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(seeds = [b"collateral_registry", state.key().as_ref()], bump = collateral_registry.bump)]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+}
+
+#[derive(Accounts)]
+pub struct ValidateRiskParams {}
+
+#[derive(Accounts)]
+pub struct ReadVault<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SnapshotEpoch<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        init,
+        seeds = [
+            b"epoch_snapshot",
+            state.key().as_ref(),
+            &Clock::get()?.epoch.to_le_bytes(),
+        ],
+        bump,
+        payer = payer,
+        space = 8 + EpochSnapshot::INIT_SPACE,
+    )]
+    pub snapshot: Account<'info, EpochSnapshot>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReadUserStake<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    /// CHECK: Staker whose position is read; only used to derive `user_stake`.
+    pub user: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection_mint: Pubkey)]
+pub struct RegisterBoostCollection<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        seeds = [b"boost_collection", state.key().as_ref(), collection_mint.as_ref()],
+        bump,
+        payer = state_owner,
+        space = 8 + BoostCollection::INIT_SPACE,
+    )]
+    pub boost_collection: Account<'info, BoostCollection>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct LinkNft<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        seeds = [
+            b"boost_collection",
+            state.key().as_ref(),
+            boost_collection.collection_mint.as_ref(),
+        ],
+        bump = boost_collection.bump,
+    )]
+    pub boost_collection: Account<'info, BoostCollection>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(
+        init,
+        seeds = [b"nft_link", state.key().as_ref(), nft_mint.key().as_ref()],
+        bump,
+        payer = user,
+        space = 8 + NftLink::INIT_SPACE,
+    )]
+    pub nft_link: Account<'info, NftLink>,
+    #[account(
+        constraint = nft_mint.supply == 1 && nft_mint.decimals == 0
+            @ CustomError::NftNotInCollection,
+    )]
+    pub nft_mint: Account<'info, Mint>,
+    #[account(
+        constraint = nft_token_account.owner == user.key(),
+        constraint = nft_token_account.mint == nft_mint.key(),
+        constraint = nft_token_account.amount == 1 @ CustomError::NftNotInCollection,
+    )]
+    pub nft_token_account: Account<'info, TokenAccount>,
+    /// CHECK: Metaplex metadata PDA of `nft_mint`; the collection is read from it.
+    #[account(
+        seeds = [b"metadata", TOKEN_METADATA_PROGRAM_ID.as_ref(), nft_mint.key().as_ref()],
+        bump,
+        seeds::program = TOKEN_METADATA_PROGRAM_ID,
+        owner = TOKEN_METADATA_PROGRAM_ID,
+    )]
+    pub nft_metadata: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(role: Role, holder: Pubkey)]
+pub struct GrantRole<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        seeds = [b"role", state.key().as_ref(), &[role as u8], holder.as_ref()],
+        bump,
+        payer = state_owner,
+        space = 8 + RoleAssignment::INIT_SPACE,
+    )]
+    pub role_assignment: Account<'info, RoleAssignment>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(role: Role, holder: Pubkey)]
+pub struct RevokeRole<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        mut,
+        seeds = [b"role", state.key().as_ref(), &[role as u8], holder.as_ref()],
+        bump = role_assignment.bump,
+        close = state_owner,
+    )]
+    pub role_assignment: Account<'info, RoleAssignment>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetUserTier<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        seeds = [
+            b"role",
+            state.key().as_ref(),
+            &[Role::Compliance as u8],
+            authority.key().as_ref(),
+        ],
+        bump = role_assignment.bump,
+    )]
+    pub role_assignment: Account<'info, RoleAssignment>,
+    pub authority: Signer<'info>,
+    /// CHECK: Staker whose tier is set; only used to derive `user_stake`.
+    pub user: UncheckedAccount<'info>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+}
+
+#[derive(Accounts)]
+#[instruction(address: Pubkey)]
+pub struct BlockAddress<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        seeds = [b"blocked", state.key().as_ref(), address.as_ref()],
+        bump,
+        payer = state_owner,
+        space = 8 + Blocked::INIT_SPACE,
+    )]
+    pub blocked: Account<'info, Blocked>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(address: Pubkey)]
+pub struct UnblockAddress<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        mut,
+        seeds = [b"blocked", state.key().as_ref(), address.as_ref()],
+        bump = blocked.bump,
+        close = state_owner,
+    )]
+    pub blocked: Account<'info, Blocked>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(strategy: StrategyKind)]
+pub struct InitializePnlBucket<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        seeds = [b"pnl_bucket", state.key().as_ref(), &[strategy as u8]],
+        bump,
+        payer = state_owner,
+        space = 8 + PnlBucket::INIT_SPACE,
+    )]
+    pub pnl_bucket: Account<'info, PnlBucket>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeStrategyState<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        seeds = [b"strategy_state", state.key().as_ref()],
+        bump,
+        payer = state_owner,
+        space = 8 + StrategyState::INIT_SPACE,
+    )]
+    pub strategy_state: Account<'info, StrategyState>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProtocolLiquidity<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    pub state_owner: Signer<'info>,
+    #[account(mut, constraint = treasury_token_account.owner == state_owner.key())]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"vault", state.key().as_ref()],
+        bump,
+        constraint = vault_account.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = vault_account.mint == state.base_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub vault_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Unstake<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: This account holds the user's $DNT tokens.
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key(),
+        constraint = user_token_account.mint == state.base_mint @ CustomError::VaultMintMismatch,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"vault", state.key().as_ref()],
+        bump,
+        constraint = vault_account.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = vault_account.mint == state.base_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub vault_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"fee_vault", state.key().as_ref(), state.base_mint.as_ref()],
+        bump,
+        constraint = fee_vault.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = fee_vault.mint == state.base_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(seeds = [b"collateral_registry", state.key().as_ref()], bump = collateral_registry.bump)]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+    #[account(mut, seeds = [b"ledger", state.key().as_ref()], bump = ledger.load()?.bump)]
+    pub ledger: AccountLoader<'info, Ledger>,
+    // The stake's index page, delisted from once the balance reaches zero.
+    #[account(
+        mut,
+        seeds = [
+            b"stake_index",
+            state.key().as_ref(),
+            &stake_index.load()?.page.to_le_bytes(),
+        ],
+        bump = stake_index.load()?.bump,
+    )]
+    pub stake_index: AccountLoader<'info, StakeIndexPage>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: Instructions sysvar, checked by address; required for withdrawals above
+    /// LARGE_UNSTAKE_BPS of total stake.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakeTo<'info> {
+    pub withdrawal: Unstake<'info>,
+    /// Any token account of the base mint; its owner is not checked.
+    #[account(
+        mut,
+        constraint = destination_token_account.mint == withdrawal.state.base_mint
+            @ CustomError::InvalidVaultMint,
+    )]
+    pub destination_token_account: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct Exit<'info> {
+    pub withdrawal: Unstake<'info>,
+    /// CHECK: Blocklist PDA for the user; must not exist.
+    #[account(
+        seeds = [b"blocked", withdrawal.state.key().as_ref(), withdrawal.user.key().as_ref()],
+        bump,
+    )]
+    pub blocked: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"reward_stats",
+            withdrawal.state.key().as_ref(),
+            withdrawal.user.key().as_ref(),
+        ],
+        bump = reward_stats.bump,
+    )]
+    pub reward_stats: Account<'info, UserRewardStats>,
+    #[account(mut, constraint = rewards_account.owner == withdrawal.state.key())]
+    pub rewards_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = reward_token_account.owner == withdrawal.user.key(),
+        constraint = reward_token_account.mint == rewards_account.mint,
+    )]
+    pub reward_token_account: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ExpediteWithdrawal<'info> {
+    pub withdrawal: Unstake<'info>,
+    #[account(
+        seeds = [b"delta_report", withdrawal.state.key().as_ref()],
+        bump = delta_report.bump,
+    )]
+    pub delta_report: Account<'info, DeltaReport>,
+}
+
+#[derive(Accounts)]
+#[instruction(new_owner: Pubkey)]
+pub struct TransferStake<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub from_user_stake: Account<'info, UserStake>,
+    // Assume the receiving stake account is already initialized.
+    #[account(
+        mut,
+        seeds = [b"user_stake", state.key().as_ref(), new_owner.as_ref()],
+        bump,
+    )]
+    pub to_user_stake: Account<'info, UserStake>,
+    pub user: Signer<'info>,
+    // The sender's index page, delisted from if the whole stake moves.
+    #[account(
+        mut,
+        seeds = [
+            b"stake_index",
+            state.key().as_ref(),
+            &from_stake_index.load()?.page.to_le_bytes(),
+        ],
+        bump = from_stake_index.load()?.bump,
+    )]
+    pub from_stake_index: AccountLoader<'info, StakeIndexPage>,
+    // Page to list the receiving stake on if it is not indexed yet; may be the same page.
+    #[account(
+        mut,
+        seeds = [
+            b"stake_index",
+            state.key().as_ref(),
+            &to_stake_index.load()?.page.to_le_bytes(),
+        ],
+        bump = to_stake_index.load()?.bump,
+    )]
+    pub to_stake_index: AccountLoader<'info, StakeIndexPage>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateStake<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", from_state_owner.key().as_ref(), &from_state.vault_id.to_le_bytes()],
+        bump = from_state.bump,
+        constraint = from_state.state_owner == from_state_owner.key() @ CustomError::StateOwnerMismatch,
+    )]
+    pub from_state: Account<'info, State>,
+    /// CHECK: Source vault owner; checked against `from_state.state_owner`.
+    pub from_state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"state", to_state_owner.key().as_ref(), &to_state.vault_id.to_le_bytes()],
+        bump = to_state.bump,
+        constraint = to_state.state_owner == to_state_owner.key() @ CustomError::StateOwnerMismatch,
+        constraint = to_state.key() != from_state.key() @ CustomError::SameVault,
+        constraint = to_state.base_mint == from_state.base_mint @ CustomError::VaultMintMismatch,
+    )]
+    pub to_state: Account<'info, State>,
+    /// CHECK: Destination vault owner; checked against `to_state.state_owner`.
+    pub to_state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"user_stake", from_state.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub from_user_stake: Account<'info, UserStake>,
+    #[account(
+        mut,
+        seeds = [b"user_stake", to_state.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub to_user_stake: Account<'info, UserStake>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: Blocklist PDA for the user in the destination vault; must not exist.
+    #[account(seeds = [b"blocked", to_state.key().as_ref(), user.key().as_ref()], bump)]
+    pub to_blocked: UncheckedAccount<'info>,
+    #[account(seeds = [b"delta_report", to_state.key().as_ref()], bump = to_delta_report.bump)]
+    pub to_delta_report: Account<'info, DeltaReport>,
+    #[account(
+        mut,
+        seeds = [b"vault", from_state.key().as_ref()],
+        bump,
+        constraint = from_vault_account.owner == from_state.key() @ CustomError::InvalidVaultOwner,
+        constraint = from_vault_account.mint == from_state.base_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub from_vault_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"vault", to_state.key().as_ref()],
+        bump,
+        constraint = to_vault_account.owner == to_state.key() @ CustomError::InvalidVaultOwner,
+        constraint = to_vault_account.mint == to_state.base_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub to_vault_account: Account<'info, TokenAccount>,
+    // The stake's index page in the source vault, delisted from once it is empty.
+    #[account(
+        mut,
+        seeds = [
+            b"stake_index",
+            from_state.key().as_ref(),
+            &from_stake_index.load()?.page.to_le_bytes(),
+        ],
+        bump = from_stake_index.load()?.bump,
+    )]
+    pub from_stake_index: AccountLoader<'info, StakeIndexPage>,
+    // Page in the destination vault to list the stake on if it is not indexed yet.
+    #[account(
+        mut,
+        seeds = [
+            b"stake_index",
+            to_state.key().as_ref(),
+            &to_stake_index.load()?.page.to_le_bytes(),
+        ],
+        bump = to_stake_index.load()?.bump,
+    )]
+    pub to_stake_index: AccountLoader<'info, StakeIndexPage>,
+    #[account(
+        mut,
+        seeds = [b"ledger", from_state.key().as_ref()],
+        bump = from_ledger.load()?.bump,
+    )]
+    pub from_ledger: AccountLoader<'info, Ledger>,
+    #[account(mut, seeds = [b"ledger", to_state.key().as_ref()], bump = to_ledger.load()?.bump)]
+    pub to_ledger: AccountLoader<'info, Ledger>,
+    /// Required when the migrated stake exceeds the destination's remaining capacity; queues
+    /// the excess.
+    #[account(
+        init,
+        seeds = [
+            b"pending_deposit",
+            to_state.key().as_ref(),
+            &to_state.pending_deposit_tail.to_le_bytes(),
+        ],
+        bump,
+        payer = user,
+        space = 8 + PendingDeposit::INIT_SPACE,
+    )]
+    pub pending_deposit: Option<Account<'info, PendingDeposit>>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Rebalance<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(seeds = [b"delta_report", state.key().as_ref()], bump = delta_report.bump)]
+    pub delta_report: Account<'info, DeltaReport>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    /// CHECK: Program of the configured hedge venue; checked against `state.hedge_venue` and
+    /// the config whitelist.
+    #[account(
+        constraint = hedge_program.key() == state.hedge_venue.program_id()
+            @ CustomError::InvalidHedgeVenue,
+        constraint = config.is_whitelisted(hedge_program.key())
+            @ CustomError::ProgramNotWhitelisted,
+    )]
+    pub hedge_program: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"strategy_state", state.key().as_ref()],
+        bump = strategy_state.bump,
+    )]
+    pub strategy_state: Account<'info, StrategyState>,
+    /// CHECK: Instructions sysvar, checked by address.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MarkCollateral<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(constraint = keeper.key() == state.keeper @ CustomError::Unauthorized)]
+    pub keeper: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"collateral_registry", state.key().as_ref()],
+        bump = collateral_registry.bump,
+    )]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+}
+
+#[derive(Accounts)]
+pub struct Reconcile<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        seeds = [b"vault", state.key().as_ref()],
+        bump,
+        constraint = vault_account.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = vault_account.mint == state.base_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub vault_account: Account<'info, TokenAccount>,
+    #[account(
+        seeds = [b"fee_vault", state.key().as_ref(), state.base_mint.as_ref()],
+        bump,
+        constraint = fee_vault.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = fee_vault.mint == state.base_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(seeds = [b"collateral_registry", state.key().as_ref()], bump = collateral_registry.bump)]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+}
+
+/// A timelocked fee sweep above the co-sign threshold, at `[b"fee_sweep", state, fee_vault]`.
 #[account]
-pub struct State {
+#[derive(InitSpace)]
+pub struct FeeSweepProposal {
     pub bump: u8,
-    pub total_staked: u64,
-    pub last_update: i64,
-    pub last_rebalance: i64,
-    pub allowed_delta_threshold: u64,
+    pub state: Pubkey,
+    pub fee_vault: Pubkey,
+    pub proposer: Pubkey,
+    pub amount: u64,
+    pub executable_at: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+#[derive(Accounts)]
+pub struct ProposeFeeSweep<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        seeds = [
+            b"role",
+            state.key().as_ref(),
+            &[Role::FeeCollector as u8],
+            authority.key().as_ref(),
+        ],
+        bump = role_assignment.bump,
+    )]
+    pub role_assignment: Account<'info, RoleAssignment>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"fee_vault", state.key().as_ref(), fee_vault.mint.as_ref()],
+        bump,
+        constraint = fee_vault.owner == state.key() @ CustomError::InvalidVaultOwner,
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        seeds = [b"fee_sweep", state.key().as_ref(), fee_vault.key().as_ref()],
+        bump,
+        payer = authority,
+        space = 8 + FeeSweepProposal::INIT_SPACE,
+    )]
+    pub proposal: Account<'info, FeeSweepProposal>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelFeeSweep<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    pub state_owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"fee_sweep", state.key().as_ref(), proposal.fee_vault.as_ref()],
+        bump = proposal.bump,
+        close = proposer,
+    )]
+    pub proposal: Account<'info, FeeSweepProposal>,
+    /// CHECK: Receives the proposal's rent; must be its proposer.
+    #[account(mut, address = proposal.proposer @ CustomError::InvalidParameter)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SweepFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        seeds = [
+            b"role",
+            state.key().as_ref(),
+            &[Role::FeeCollector as u8],
+            authority.key().as_ref(),
+        ],
+        bump = role_assignment.bump,
+    )]
+    pub role_assignment: Account<'info, RoleAssignment>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    // Required above the co-sign threshold unless a matured proposal is given.
+    #[account(constraint = co_signer.key() == state.state_owner @ CustomError::Unauthorized)]
+    pub co_signer: Option<Signer<'info>>,
+    #[account(
+        mut,
+        seeds = [b"fee_sweep", state.key().as_ref(), fee_vault.key().as_ref()],
+        bump = proposal.bump,
+        close = authority,
+    )]
+    pub proposal: Option<Account<'info, FeeSweepProposal>>,
+    #[account(
+        mut,
+        seeds = [b"fee_vault", state.key().as_ref(), fee_vault.mint.as_ref()],
+        bump,
+        constraint = fee_vault.owner == state.key() @ CustomError::InvalidVaultOwner,
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == state.state_owner,
+        constraint = treasury_token_account.mint == fee_vault.mint @ CustomError::InvalidVaultMint,
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"ledger", state.key().as_ref()], bump = ledger.load()?.bump)]
+    pub ledger: AccountLoader<'info, Ledger>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct RescueTokens<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    pub state_owner: Signer<'info>,
+    #[account(seeds = [b"collateral_registry", state.key().as_ref()], bump = collateral_registry.bump)]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+    #[account(address = mint @ CustomError::InvalidVaultMint)]
+    pub rescue_mint: Account<'info, Mint>,
+    // Any token account of `mint` held by the state PDA.
+    #[account(
+        mut,
+        constraint = source.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = source.mint == mint @ CustomError::InvalidVaultMint,
+    )]
+    pub source: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = destination.owner == state.state_owner,
+        constraint = destination.mint == mint @ CustomError::InvalidVaultMint,
+    )]
+    pub destination: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct BuybackAndBurn<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(constraint = keeper.key() == state.keeper @ CustomError::Unauthorized)]
+    pub keeper: Signer<'info>,
+    // Fee vault holding the revenue being spent, e.g. the stablecoin fee vault.
+    #[account(
+        mut,
+        seeds = [b"fee_vault", state.key().as_ref(), fee_vault.mint.as_ref()],
+        bump,
+        constraint = fee_vault.owner == state.key() @ CustomError::InvalidVaultOwner,
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+    // $DNT, minted by the state PDA.
+    #[account(
+        mut,
+        constraint = dnt_mint.mint_authority == Some(state.key()).into() @ CustomError::Unauthorized,
+    )]
+    pub dnt_mint: Account<'info, Mint>,
+    // State-owned $DNT account the swap pays into and the burn draws from.
+    #[account(
+        mut,
+        constraint = buyback_account.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = buyback_account.mint == dnt_mint.key() @ CustomError::InvalidVaultMint,
+    )]
+    pub buyback_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"ledger", state.key().as_ref()], bump = ledger.load()?.bump)]
+    pub ledger: AccountLoader<'info, Ledger>,
+    /// CHECK: DEX program the swap is routed through.
+    pub dex_program: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeDeltaReport<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        seeds = [b"delta_report", state.key().as_ref()],
+        bump,
+        payer = state_owner,
+        space = 8 + DeltaReport::INIT_SPACE,
+    )]
+    pub delta_report: Account<'info, DeltaReport>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateDeltaReport<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(mut, seeds = [b"delta_report", state.key().as_ref()], bump = delta_report.bump)]
+    pub delta_report: Account<'info, DeltaReport>,
+    #[account(constraint = keeper.key() == state.keeper @ CustomError::Unauthorized)]
+    pub keeper: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReportLendingPnl<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(constraint = keeper.key() == state.keeper @ CustomError::Unauthorized)]
+    pub keeper: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResumeAfterDrawdown<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(seeds = [b"vote_tally", state.key().as_ref()], bump = vote_tally.bump)]
+    pub vote_tally: Account<'info, VoteTally>,
+}
+
+#[derive(Accounts)]
+pub struct GuardianPause<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(constraint = guardian.key() == state.guardian @ CustomError::Unauthorized)]
+    pub guardian: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct LiftGuardianPause<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    pub state_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct NominateGuardian<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        constraint = authority.key() == state.guardian || authority.key() == state.state_owner
+            @ CustomError::Unauthorized,
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptGuardian<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(constraint = nominee.key() == state.pending_guardian @ CustomError::Unauthorized)]
+    pub nominee: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleHedge<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"pnl_bucket", state.key().as_ref(), &[StrategyKind::Hedge as u8]],
+        bump = pnl_bucket.bump,
+    )]
+    pub pnl_bucket: Account<'info, PnlBucket>,
+    #[account(mut, seeds = [b"ledger", state.key().as_ref()], bump = ledger.load()?.bump)]
+    pub ledger: AccountLoader<'info, Ledger>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    /// CHECK: Program of the configured hedge venue; checked against `state.hedge_venue` and
+    /// the config whitelist.
+    #[account(
+        constraint = hedge_program.key() == state.hedge_venue.program_id()
+            @ CustomError::InvalidHedgeVenue,
+        constraint = config.is_whitelisted(hedge_program.key())
+            @ CustomError::ProgramNotWhitelisted,
+    )]
+    pub hedge_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(mut)]
+    pub token_mint: Account<'info, Mint>,
+    // The rewards pool claims are paid from.
+    #[account(mut, constraint = rewards_account.owner == state.key())]
+    pub rewards_account: Account<'info, TokenAccount>,
+    #[account(seeds = [b"delta_report", state.key().as_ref()], bump = delta_report.bump)]
+    pub delta_report: Account<'info, DeltaReport>,
+    #[account(mut, seeds = [b"ledger", state.key().as_ref()], bump = ledger.load()?.bump)]
+    pub ledger: AccountLoader<'info, Ledger>,
+    // Receives the share of principal-only stakers.
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == state_owner.key(),
+        constraint = treasury_token_account.mint == token_mint.key(),
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetPrincipalOnly<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAutoClaimThreshold<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AutoClaimFor<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    /// CHECK: Staker whose rewards are delivered; only used to derive PDAs and check the payee.
+    pub user: UncheckedAccount<'info>,
+    /// CHECK: Blocklist PDA for the user; must not exist.
+    #[account(seeds = [b"blocked", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub blocked: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"reward_stats", state.key().as_ref(), user.key().as_ref()],
+        bump = reward_stats.bump,
+    )]
+    pub reward_stats: Account<'info, UserRewardStats>,
+    #[account(mut, seeds = [b"ledger", state.key().as_ref()], bump = ledger.load()?.bump)]
+    pub ledger: AccountLoader<'info, Ledger>,
+    #[account(mut, constraint = rewards_account.owner == state.key())]
+    pub rewards_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key() @ CustomError::Unauthorized,
+        constraint = user_token_account.mint == rewards_account.mint
+            @ CustomError::InvalidVaultMint,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    pub caller: Signer<'info>,
+    #[account(
+        mut,
+        constraint = caller_token_account.mint == rewards_account.mint
+            @ CustomError::InvalidVaultMint,
+    )]
+    pub caller_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    pub user: Signer<'info>,
+    /// CHECK: Blocklist PDA for the user; must not exist.
+    #[account(seeds = [b"blocked", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub blocked: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"reward_stats", state.key().as_ref(), user.key().as_ref()],
+        bump = reward_stats.bump,
+    )]
+    pub reward_stats: Account<'info, UserRewardStats>,
+    #[account(mut, seeds = [b"ledger", state.key().as_ref()], bump = ledger.load()?.bump)]
+    pub ledger: AccountLoader<'info, Ledger>,
+    #[account(mut, constraint = rewards_account.owner == state.key())]
+    pub rewards_account: Account<'info, TokenAccount>,
+    #[account(mut, constraint = user_token_account.owner == user.key())]
+    pub user_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
 }
 
-#[account]
-pub struct UserStake {
-    pub amount: u64,
-    pub last_update: i64,
+#[derive(Accounts)]
+pub struct CompoundRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"reward_stats", state.key().as_ref(), user.key().as_ref()],
+        bump = reward_stats.bump,
+    )]
+    pub reward_stats: Account<'info, UserRewardStats>,
+    #[account(
+        mut,
+        constraint = rewards_account.owner == state.key(),
+        constraint = rewards_account.mint == state.base_mint @ CustomError::VaultMintMismatch,
+    )]
+    pub rewards_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"vault", state.key().as_ref()],
+        bump,
+        constraint = vault_account.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = vault_account.mint == state.base_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub vault_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"ledger", state.key().as_ref()], bump = ledger.load()?.bump)]
+    pub ledger: AccountLoader<'info, Ledger>,
+    pub token_program: Program<'info, Token>,
 }
 
-// -----------------------------------------------------------------------------
-// Accounts Contexts
-// -----------------------------------------------------------------------------
+#[derive(Accounts)]
+pub struct ClaimAndLock<'info> {
+    pub compound: CompoundRewards<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"vote_escrow",
+            compound.state.key().as_ref(),
+            compound.user.key().as_ref(),
+        ],
+        bump = vote_escrow.bump,
+    )]
+    pub vote_escrow: Account<'info, VoteEscrow>,
+}
 
 #[derive(Accounts)]
-pub struct Initialize<'info> {
+pub struct InitializeRewardStats<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
     #[account(
         init,
-        seeds = [b"state", payer.key().as_ref()],
+        seeds = [b"reward_stats", state.key().as_ref(), user.key().as_ref()],
         bump,
-        payer = payer,
-        space = 8 + 1 + 8 + 8 + 8 + 8,
+        payer = user,
+        space = 8 + UserRewardStats::INIT_SPACE,
     )]
-    pub state: Account<'info, State>,
+    pub reward_stats: Account<'info, UserRewardStats>,
     #[account(mut)]
-    pub payer: Signer<'info>,
+    pub user: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct StakeAccounts<'info> {
-    #[account(mut, seeds = [b"state", state_owner.key().as_ref()], bump = state.bump)]
+pub struct SweepExpiredRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
     pub state: Account<'info, State>,
-    // Assume the user stake account is already initialized.
-    #[account(mut, seeds = [b"user_stake", user.key().as_ref()], bump)]
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
     pub user_stake: Account<'info, UserStake>,
+    /// CHECK: Owner of the swept stake; only used for PDA derivation.
+    pub user: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(seeds = [b"funding_history", state.key().as_ref()], bump = funding_history.bump)]
+    pub funding_history: Account<'info, FundingHistory>,
     #[account(mut)]
-    pub user: Signer<'info>,
-    /// CHECK: This account holds the user's $DNT tokens.
-    #[account(mut, constraint = user_token_account.owner == user.key())]
-    pub user_token_account: Account<'info, TokenAccount>,
-    #[account(mut, seeds = [b"vault", state.key().as_ref()], bump)]
-    pub vault_account: Account<'info, TokenAccount>,
-    /// CHECK: Reference to state owner for PDA derivation.
+    pub token_mint: Account<'info, Mint>,
+    // The rewards pool claims are paid from.
+    #[account(
+        mut,
+        constraint = rewards_account.owner == state.key(),
+        constraint = rewards_account.mint == token_mint.key(),
+    )]
+    pub rewards_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFundingHistory<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        seeds = [b"funding_history", state.key().as_ref()],
+        bump,
+        payer = state_owner,
+        space = 8 + FundingHistory::INIT_SPACE,
+    )]
+    pub funding_history: Account<'info, FundingHistory>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecordFundingObservation<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"funding_history", state.key().as_ref()],
+        bump = funding_history.bump,
+    )]
+    pub funding_history: Account<'info, FundingHistory>,
+    #[account(
+        seeds = [b"funding_publishers", state.key().as_ref()],
+        bump = funding_publishers.bump,
+    )]
+    pub funding_publishers: Account<'info, FundingPublishers>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeProfits<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
     pub state_owner: AccountInfo<'info>,
+    #[account(mut)]
+    pub token_mint: Account<'info, Mint>,
+    // The rewards pool claims are paid from.
+    #[account(
+        mut,
+        constraint = rewards_account.owner == state.key(),
+        constraint = rewards_account.mint == token_mint.key(),
+    )]
+    pub rewards_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"market_making", state.key().as_ref()], bump = market_making.bump)]
+    pub market_making: Option<Account<'info, MarketMakingStrategy>>,
+    #[account(
+        mut,
+        seeds = [b"strategy_state", state.key().as_ref()],
+        bump = strategy_state.bump,
+    )]
+    pub strategy_state: Account<'info, StrategyState>,
     pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(distribution_id: u64, merkle_root: [u8; 32], max_claims: u32)]
+pub struct CreateRewardDistribution<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        seeds = [b"reward_distribution", state.key().as_ref(), &distribution_id.to_le_bytes()],
+        bump,
+        payer = state_owner,
+        space = 8 + RewardDistribution::INIT_SPACE + (max_claims as usize).div_ceil(8),
+    )]
+    pub distribution: Account<'info, RewardDistribution>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
     pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct MultiCollateralStakeAccounts<'info> {
-    #[account(mut, seeds = [b"state", state_owner.key().as_ref()], bump = state.bump)]
+pub struct ClaimAirdrop<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"reward_distribution", state.key().as_ref(), &distribution.distribution_id.to_le_bytes()],
+        bump = distribution.bump,
+    )]
+    pub distribution: Account<'info, RewardDistribution>,
+    pub claimant: Signer<'info>,
+    #[account(mut)]
+    pub token_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = rewards_account.owner == claimant.key(),
+        constraint = rewards_account.mint == token_mint.key(),
+    )]
+    pub rewards_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RewardMakers<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(mut)]
+    pub token_mint: Account<'info, Mint>,
+    // The rewards pool claims are paid from.
+    #[account(
+        mut,
+        constraint = rewards_account.owner == state.key(),
+        constraint = rewards_account.mint == token_mint.key(),
+    )]
+    pub rewards_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        init,
+        seeds = [b"position", state.key().as_ref(), owner.key().as_ref()],
+        bump,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(mut, constraint = owner_token_account.owner == owner.key())]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"vault", state.key().as_ref()],
+        bump,
+        constraint = vault_account.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = vault_account.mint == state.base_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub vault_account: Account<'info, TokenAccount>,
+    #[account(seeds = [b"collateral_registry", state.key().as_ref()], bump = collateral_registry.bump)]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MarginCall<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", state.key().as_ref(), position.owner.as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        seeds = [b"pnl_bucket", state.key().as_ref(), &[StrategyKind::Hedge as u8]],
+        bump = pnl_bucket.bump,
+    )]
+    pub pnl_bucket: Account<'info, PnlBucket>,
+}
+
+#[derive(Accounts)]
+pub struct AdjustMargin<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", state.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        seeds = [b"pnl_bucket", state.key().as_ref(), &[StrategyKind::Hedge as u8]],
+        bump = pnl_bucket.bump,
+    )]
+    pub pnl_bucket: Account<'info, PnlBucket>,
+    pub owner: Signer<'info>,
+    #[account(mut, constraint = owner_token_account.owner == owner.key())]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"vault", state.key().as_ref()],
+        bump,
+        constraint = vault_account.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = vault_account.mint == state.base_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub vault_account: Account<'info, TokenAccount>,
+    #[account(seeds = [b"collateral_registry", state.key().as_ref()], bump = collateral_registry.bump)]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetTpSl<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", state.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteTrigger<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", state.key().as_ref(), position.owner.as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        seeds = [b"pnl_bucket", state.key().as_ref(), &[StrategyKind::Hedge as u8]],
+        bump = pnl_bucket.bump,
+    )]
+    pub pnl_bucket: Account<'info, PnlBucket>,
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == position.owner @ CustomError::Unauthorized,
+        constraint = owner_token_account.mint == state.base_mint @ CustomError::VaultMintMismatch,
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    pub executor: Signer<'info>,
+    #[account(
+        mut,
+        constraint = executor_token_account.mint == state.base_mint
+            @ CustomError::VaultMintMismatch,
+    )]
+    pub executor_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"vault", state.key().as_ref()],
+        bump,
+        constraint = vault_account.owner == state.key() @ CustomError::InvalidVaultOwner,
+        constraint = vault_account.mint == state.base_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub vault_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Liquidate<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"position", state.key().as_ref(), position.owner.as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        seeds = [b"pnl_bucket", state.key().as_ref(), &[StrategyKind::Hedge as u8]],
+        bump = pnl_bucket.bump,
+    )]
+    pub pnl_bucket: Account<'info, PnlBucket>,
+    /// CHECK: Liquidation job PDA for the position; may be empty when nobody claimed it.
+    #[account(mut, seeds = [b"liquidation_job", position.key().as_ref()], bump)]
+    pub liquidation_job: UncheckedAccount<'info>,
+    /// The liquidator's registration; required when completing a job they claimed.
+    #[account(
+        mut,
+        seeds = [b"liquidator", state.key().as_ref(), liquidator.key().as_ref()],
+        bump = liquidator_record.bump,
+    )]
+    pub liquidator_record: Option<Account<'info, Liquidator>>,
+    #[account(mut)]
+    pub liquidator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PreviewLiquidation<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        seeds = [b"position", state.key().as_ref(), position.owner.as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+}
+
+#[derive(Accounts)]
+pub struct Vote<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"param_registry", state.key().as_ref()],
+        bump = param_registry.bump,
+    )]
+    pub param_registry: Account<'info, ParamRegistry>,
+    #[account(mut, seeds = [b"vote_tally", state.key().as_ref()], bump = vote_tally.bump)]
+    pub vote_tally: Account<'info, VoteTally>,
+    #[account(seeds = [b"user_stake", state.key().as_ref(), voter.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    pub voter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeVoteTally<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
     pub state: Account<'info, State>,
-    // Assume the user stake account is already initialized.
-    #[account(mut, seeds = [b"user_stake", user.key().as_ref()], bump)]
-    pub user_stake: Account<'info, UserStake>,
+    #[account(
+        init,
+        seeds = [b"vote_tally", state.key().as_ref()],
+        bump,
+        payer = state_owner,
+        space = 8 + VoteTally::INIT_SPACE,
+    )]
+    pub vote_tally: Account<'info, VoteTally>,
     #[account(mut)]
-    pub user: Signer<'info>,
-    /// CHECK: This account holds the user's collateral tokens.
-    #[account(mut, constraint = user_token_account.owner == user.key())]
-    pub user_token_account: Account<'info, TokenAccount>,
-    #[account(mut, seeds = [b"vault", state.key().as_ref()], bump)]
-    pub vault_account: Account<'info, TokenAccount>,
-    /// CHECK: Reference to state owner.
-    pub state_owner: AccountInfo<'info>,
-    pub token_program: Program<'info, Token>,
+    pub state_owner: Signer<'info>,
     pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct Unstake<'info> {
-    #[account(mut, seeds = [b"state", state_owner.key().as_ref()], bump = state.bump)]
+pub struct LockStake<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
     pub state: Account<'info, State>,
-    #[account(mut, seeds = [b"user_stake", user.key().as_ref()], bump)]
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
     pub user_stake: Account<'info, UserStake>,
+    #[account(
+        init,
+        seeds = [b"vote_escrow", state.key().as_ref(), user.key().as_ref()],
+        bump,
+        payer = user,
+        space = 8 + VoteEscrow::INIT_SPACE,
+    )]
+    pub vote_escrow: Account<'info, VoteEscrow>,
     #[account(mut)]
     pub user: Signer<'info>,
-    /// CHECK: This account holds the user's $DNT tokens.
-    #[account(mut, constraint = user_token_account.owner == user.key())]
-    pub user_token_account: Account<'info, TokenAccount>,
-    #[account(mut, seeds = [b"vault", state.key().as_ref()], bump)]
-    pub vault_account: Account<'info, TokenAccount>,
-    /// CHECK: Reference to state owner for PDA derivation.
-    pub state_owner: AccountInfo<'info>,
-    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct Rebalance<'info> {
-    #[account(mut, seeds = [b"state", state_owner.key().as_ref()], bump = state.bump)]
+pub struct Relock<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
     pub state: Account<'info, State>,
-    /// CHECK: Reference to state owner.
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
     pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"vote_escrow", state.key().as_ref(), user.key().as_ref()],
+        bump = vote_escrow.bump,
+    )]
+    pub vote_escrow: Account<'info, VoteEscrow>,
+    pub user: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct DistributeRewards<'info> {
-    #[account(mut, seeds = [b"state", state_owner.key().as_ref()], bump = state.bump)]
+pub struct UnlockStake<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
     pub state: Account<'info, State>,
-    /// CHECK: Reference to state owner.
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
     pub state_owner: AccountInfo<'info>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(
+        mut,
+        seeds = [b"vote_escrow", state.key().as_ref(), user.key().as_ref()],
+        bump = vote_escrow.bump,
+        close = user,
+    )]
+    pub vote_escrow: Account<'info, VoteEscrow>,
     #[account(mut)]
-    pub token_mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub rewards_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    pub user: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateRewards<'info> {
-    #[account(mut, seeds = [b"state", state_owner.key().as_ref()], bump = state.bump)]
+pub struct CastVote<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
     pub state: Account<'info, State>,
-    /// CHECK: Reference to state owner.
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
     pub state_owner: AccountInfo<'info>,
-    #[account(mut)]
-    pub token_mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub rewards_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    #[account(mut, seeds = [b"vote_tally", state.key().as_ref()], bump = vote_tally.bump)]
+    pub vote_tally: Account<'info, VoteTally>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), voter.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(
+        seeds = [b"vote_escrow", state.key().as_ref(), voter.key().as_ref()],
+        bump = vote_escrow.bump,
+    )]
+    pub vote_escrow: Option<Account<'info, VoteEscrow>>,
+    pub voter: Signer<'info>,
 }
 
-#[derive(Accounts)]
-pub struct DistributeProfits<'info> {
-    #[account(mut, seeds = [b"state", state_owner.key().as_ref()], bump = state.bump)]
-    pub state: Account<'info, State>,
-    /// CHECK: Reference to state owner.
-    pub state_owner: AccountInfo<'info>,
-    #[account(mut)]
-    pub token_mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub rewards_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+// -----------------------------------------------------------------------------
+// Helper Functions & Placeholders
+// -----------------------------------------------------------------------------
+
+fn swap_fees_for_dnt(
+    _ctx: &Context<BuybackAndBurn>,
+    _amount_in: u64,
+    min_dnt_out: u64,
+) -> Result<u64> {
+    // Placeholder: CPI into the DEX to swap fee revenue for $DNT into the buyback account, signed
+    // by the state PDA, and return the $DNT received.
+    Ok(min_dnt_out)
+}
+
+fn get_maker_trading_volume() -> Result<u64> {
+    // Placeholder: Return dummy maker trading volume.
+    Ok(5_000)
+}
+
+/// Value of one collateral unit in base units, scaled by PRICE_PRECISION. LST collateral composes
+/// the stake pool's LST/SOL exchange rate with the SOL/USD oracle price.
+fn get_conversion_rate(config: &CollateralConfig, stake_pool: Option<&AccountInfo>) -> Result<u64> {
+    if config.is_stable {
+        return get_stable_price(&config.oracle);
+    }
+    let price = get_collateral_price(&config.oracle)?;
+    if config.lst_stake_pool == Pubkey::default() {
+        return Ok(price);
+    }
+    let stake_pool = stake_pool.ok_or(CustomError::InvalidStakePool)?;
+    require_keys_eq!(*stake_pool.key, config.lst_stake_pool, CustomError::InvalidStakePool);
+    require_keys_eq!(*stake_pool.owner, SPL_STAKE_POOL_PROGRAM_ID, CustomError::InvalidStakePool);
+
+    let (total_lamports, pool_token_supply) = read_stake_pool_rate(stake_pool)?;
+    require!(pool_token_supply > 0, CustomError::StaleOracle);
+    let rate = (price as u128)
+        .checked_mul(total_lamports as u128)
+        .ok_or(CustomError::MathOverflow)?
+        / pool_token_supply as u128;
+    u64::try_from(rate).map_err(|_| error!(CustomError::MathOverflow))
+}
+
+/// Base-asset value of `config`'s deposited amount at current prices.
+fn collateral_value(config: &CollateralConfig, stake_pool: Option<&AccountInfo>) -> Result<u64> {
+    let value = (config.deposited_amount as u128)
+        .checked_mul(get_conversion_rate(config, stake_pool)? as u128)
+        .ok_or(CustomError::MathOverflow)?
+        / PRICE_PRECISION as u128;
+    u64::try_from(value).map_err(|_| error!(CustomError::MathOverflow))
+}
+
+/// Reads the verified collection from a Metaplex metadata account, if it has one. Walks the
+/// borsh layout up to the `collection` field: key, update authority, mint, name, symbol, uri,
+/// seller fee, creators, sale and mutability flags, edition nonce and token standard.
+fn read_verified_collection(metadata: &AccountInfo) -> Result<Option<Pubkey>> {
+    let data = metadata.try_borrow_data()?;
+    let mut offset = 1 + 32 + 32;
+    let take = |offset: &mut usize, len: usize| -> Result<&[u8]> {
+        let bytes = data
+            .get(*offset..*offset + len)
+            .ok_or(CustomError::NftNotInCollection)?;
+        *offset += len;
+        Ok(bytes)
+    };
+    for _ in 0..3 {
+        let len = u32::from_le_bytes(take(&mut offset, 4)?.try_into().unwrap()) as usize;
+        take(&mut offset, len)?;
+    }
+    take(&mut offset, 2)?;
+    if take(&mut offset, 1)?[0] == 1 {
+        let creators = u32::from_le_bytes(take(&mut offset, 4)?.try_into().unwrap()) as usize;
+        take(&mut offset, creators * (32 + 1 + 1))?;
+    }
+    take(&mut offset, 2)?;
+    for _ in 0..2 {
+        if take(&mut offset, 1)?[0] == 1 {
+            take(&mut offset, 1)?;
+        }
+    }
+    if take(&mut offset, 1)?[0] == 0 {
+        return Ok(None);
+    }
+    let verified = take(&mut offset, 1)?[0] == 1;
+    let key = Pubkey::try_from(take(&mut offset, 32)?).unwrap();
+    Ok(verified.then_some(key))
+}
+
+/// Extra rewards, in bps, a claim earns now: the governance voting boost plus any live NFT boost.
+fn reward_boost_bps(user_stake: &UserStake, clock: &Clock) -> u64 {
+    let mut boost = 0;
+    if user_stake.vote_epoch == clock.epoch && user_stake.votes_cast_this_epoch > 0 {
+        boost += GOVERNANCE_REWARD_BOOST_BPS;
+    }
+    if clock.unix_timestamp < user_stake.nft_boost_expires_at {
+        boost += user_stake.nft_boost_bps;
+    }
+    boost
+}
+
+/// Reads `total_lamports` and `pool_token_supply` from an SPL stake pool account.
+fn read_stake_pool_rate(stake_pool: &AccountInfo) -> Result<(u64, u64)> {
+    let data = stake_pool.try_borrow_data()?;
+    let read_u64 = |offset: usize| -> Result<u64> {
+        let bytes = data
+            .get(offset..offset + 8)
+            .ok_or(CustomError::InvalidStakePool)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    Ok((
+        read_u64(STAKE_POOL_TOTAL_LAMPORTS_OFFSET)?,
+        read_u64(STAKE_POOL_TOKEN_SUPPLY_OFFSET)?,
+    ))
+}
+
+fn get_collateral_price(_feed: &Pubkey) -> Result<u64> {
+    // Placeholder: Return the oracle price of one collateral unit in base units.
+    Ok(PRICE_PRECISION)
+}
+
+/// Fixed $1 price for stablecoin collateral. The oracle is only a sanity bound: a price outside
+/// the depeg band halts pricing instead of being used.
+fn get_stable_price(feed: &Pubkey) -> Result<u64> {
+    let oracle_price = get_collateral_price(feed)?;
+    let lower = bps_of(PRICE_PRECISION, STABLE_DEPEG_LOWER_BPS)?;
+    let upper = bps_of(PRICE_PRECISION, STABLE_DEPEG_UPPER_BPS)?;
+    require!(
+        (lower..=upper).contains(&oracle_price),
+        CustomError::StablecoinDepegged
+    );
+    Ok(PRICE_PRECISION)
+}
+
+fn force_close_position(_ctx: &Context<Liquidate>) -> Result<()> {
+    // Placeholder for force-closing a user's position.
+    Ok(())
+}
+
+/// Seizes the liquidated position's remaining collateral into the vault's PnL and clears the
+/// position. Returns the collateral seized.
+fn update_state_after_liquidation(state: &mut State, position: &mut Position) -> Result<u64> {
+    let seized = position.collateral;
+    state.total_position_collateral = state
+        .total_position_collateral
+        .checked_sub(seized)
+        .ok_or(CustomError::MathOverflow)?;
+    let pnl = i64::try_from(seized).map_err(|_| CustomError::MathOverflow)?;
+    state.cumulative_realized_pnl = state
+        .cumulative_realized_pnl
+        .checked_add(pnl)
+        .ok_or(CustomError::MathOverflow)?;
+    state.position_realized_pnl = state
+        .position_realized_pnl
+        .checked_add(pnl)
+        .ok_or(CustomError::MathOverflow)?;
+    position.collateral = 0;
+    position.size = 0;
+    position.margin_call_deadline = 0;
+    position.liquidated_size = 0;
+    position.liquidation_chunks = 0;
+    position.liquidation_price = 0;
+    position.record(
+        PositionActionKind::Liquidation,
+        i64::try_from(seized).map_err(|_| CustomError::MathOverflow)?,
+    )?;
+    Ok(seized)
+}
+
+/// Mark price for margin and liquidation checks. Taken from the vault's `PriceCache` when that is
+/// passed as a remaining account, and builds with the `mock-oracles` feature take it from the
+/// vault's `MockFeed` ahead of both.
+fn risk_mark_price(state: &Pubkey, remaining_accounts: &[AccountInfo]) -> Result<u64> {
+    #[cfg(feature = "mock-oracles")]
+    if let Some(price) = mock_oracle::mark_price(state, remaining_accounts)? {
+        return Ok(price);
+    }
+    if let Some(cache) = price_cache::load(state, remaining_accounts)? {
+        return Ok(cache.mark_price);
+    }
+    get_mark_price()
+}
+
+fn get_mark_price() -> Result<u64> {
+    // Placeholder: Return the perp mark price, scaled by PRICE_PRECISION.
+    Ok(PRICE_PRECISION)
+}
+
+fn get_spot_price() -> Result<u64> {
+    // Placeholder: Return the spot index price, scaled by PRICE_PRECISION.
+    Ok(PRICE_PRECISION)
+}
+
+/// Perp premium over spot, in basis points; negative when the perp trades at a discount.
+fn current_basis_bps() -> Result<i64> {
+    let spot = get_spot_price()? as i128;
+    require!(spot > 0, CustomError::InvalidParameter);
+    let basis = (get_mark_price()? as i128 - spot) * BPS_DENOMINATOR as i128 / spot;
+    i64::try_from(basis).map_err(|_| CustomError::MathOverflow.into())
+}
+
+/// Accrues emissions since `state.reward_checkpoint_at` into the reward index at the current
+/// stake, so a stake change only affects rewards from now on. Called before every change to
+/// `total_staked` or `principal_only_staked`. The LP share is set aside, principal-only stakers'
+/// share is owed to the treasury, and the accrual is capped by the emissions left under
+/// `reward_mint_cap`; the next `distribute_rewards` mints it all.
+fn checkpoint_rewards(state: &mut State, now: i64) -> Result<()> {
+    let elapsed = now.saturating_sub(state.reward_checkpoint_at);
+    if elapsed <= 0 {
+        return Ok(());
+    }
+    state.reward_checkpoint_at = now;
+    let headroom = state
+        .remaining_emissions()
+        .saturating_sub(state.rewards_accrued_unminted);
+    let emission = state
+        .emission(state.accrual_utilization_bps, elapsed as u64)?
+        .min(headroom);
+    state.rewards_accrued_unminted = state
+        .rewards_accrued_unminted
+        .checked_add(emission)
+        .ok_or(CustomError::MathOverflow)?;
+
+    // The LP holders' share stays in the rewards pool until they claim it.
+    let lp_share = bps_of(emission, state.lp_emission_bps)?;
+    state.lp_rewards_pending = state
+        .lp_rewards_pending
+        .checked_add(lp_share)
+        .ok_or(CustomError::MathOverflow)?;
+    let distributable = (emission - lp_share)
+        .checked_add(state.undistributed_rewards)
+        .and_then(|v| v.checked_add(std::mem::take(&mut state.dust_carry)))
+        .ok_or(CustomError::MathOverflow)?;
+    if state.total_staked == 0 {
+        // Nobody to credit; carry the rewards into the next accrual.
+        state.undistributed_rewards = distributable;
+        return Ok(());
+    }
+    // Principal-only stakers sit outside the index; their pro-rata share goes to the treasury
+    // instead.
+    let redirected = mul_div_u64(
+        distributable,
+        state.principal_only_staked,
+        state.total_staked,
+        Rounding::Down,
+    )
+    .ok_or(CustomError::MathOverflow)?;
+    state.rewards_redirected_pending = state
+        .rewards_redirected_pending
+        .checked_add(redirected)
+        .ok_or(CustomError::MathOverflow)?;
+    let accruing = state
+        .total_staked
+        .checked_sub(state.principal_only_staked)
+        .ok_or(CustomError::MathOverflow)?;
+    if accruing > 0 {
+        let indexed = distributable - redirected;
+        let index_delta = q64_from_ratio(indexed, accruing, Rounding::Down)
+            .ok_or(CustomError::MathOverflow)?;
+        state.reward_index = state
+            .reward_index
+            .checked_add(index_delta)
+            .ok_or(CustomError::MathOverflow)?;
+        // The index rounds down; what it leaves uncredited rolls into the next checkpoint rather
+        // than staying stranded in the rewards pool.
+        let credited = q64_mul(index_delta, accruing, Rounding::Down)
+            .ok_or(CustomError::MathOverflow)?;
+        state.dust_carry = indexed.saturating_sub(credited);
+    }
+    state.undistributed_rewards = 0;
+    Ok(())
+}
+
+/// Credits rewards accrued since the user's last snapshot of the global reward index.
+fn settle_user_rewards(state: &State, user_stake: &mut UserStake) -> Result<()> {
+    let index_delta = state
+        .reward_index
+        .checked_sub(user_stake.reward_index_snapshot)
+        .ok_or(CustomError::MathOverflow)?;
+    // Principal-only stakes are outside the index and accrue nothing.
+    let shares = if user_stake.principal_only { 0 } else { user_stake.amount };
+    let accrued = q64_mul(index_delta, shares, Rounding::Down).ok_or(CustomError::MathOverflow)?;
+    user_stake.pending_rewards = user_stake
+        .pending_rewards
+        .checked_add(accrued)
+        .ok_or(CustomError::MathOverflow)?;
+    user_stake.lifetime_accrued = user_stake
+        .lifetime_accrued
+        .checked_add(accrued)
+        .ok_or(CustomError::MathOverflow)?;
+    user_stake.reward_index_snapshot = state.reward_index;
+    Ok(())
+}
+
+/// Restakes the user's pending rewards at the current NAV, moving them from the rewards pool
+/// into the vault. Returns the shares credited.
+fn compound_pending_rewards(accounts: &mut CompoundRewards) -> Result<u64> {
+    let now = Clock::get()?.unix_timestamp;
+    checkpoint_rewards(&mut accounts.state, now)?;
+    let user_stake = &mut accounts.user_stake;
+    settle_user_rewards(&accounts.state, user_stake)?;
+    settle_lending_pnl(&mut accounts.state, &mut accounts.user_stake)?;
+    settle_queue_fees(&mut accounts.state, &mut accounts.user_stake)?;
+    let user_stake = &mut accounts.user_stake;
+    let amount = user_stake.pending_rewards;
+    let new_stake = accounts.state.value_to_shares(amount)?;
+
+    user_stake.pending_rewards = 0;
+    user_stake.last_claim = now;
+    user_stake.amount = user_stake
+        .amount
+        .checked_add(new_stake)
+        .ok_or(CustomError::MathOverflow)?;
+
+    let state = &mut accounts.state;
+    state.total_staked = state
+        .total_staked
+        .checked_add(new_stake)
+        .ok_or(CustomError::MathOverflow)?;
+    state.cumulative_realized_pnl = state
+        .cumulative_realized_pnl
+        .checked_add(amount as i64 - new_stake as i64)
+        .ok_or(CustomError::MathOverflow)?;
+    track_principal_only(state, user_stake, new_stake, 0)?;
+    track_risk_bucket(state, user_stake, new_stake, 0)?;
+
+    let stats = &mut accounts.reward_stats;
+    stats.lifetime_earned = user_stake.lifetime_accrued;
+    stats.lifetime_compounded = stats
+        .lifetime_compounded
+        .checked_add(amount)
+        .ok_or(CustomError::MathOverflow)?;
+    stats.last_claim = now;
+
+    transfer_from_vault(
+        &accounts.state,
+        &accounts.state_owner,
+        &accounts.rewards_account,
+        &accounts.vault_account,
+        &accounts.token_program,
+        amount,
+    )?;
+    // Paid out of the rewards pool and straight back in as principal.
+    ledger::post(&accounts.ledger, LedgerAccount::RewardsOwed, LedgerAccount::RewardsPool, amount)?;
+    ledger::post(&accounts.ledger, LedgerAccount::Vault, LedgerAccount::StakerPrincipal, amount)?;
+    let seq = next_event_seq(&mut accounts.state)?;
+    emit!(RewardsCompounded {
+        seq,
+        state: accounts.state.key(),
+        user: accounts.user.key(),
+        amount,
+        stake_credited: new_stake,
+    });
+    Ok(new_stake)
+}
+
+/// Settles the user's pending rewards and takes `limit` of them, or all when `None`, applying
+/// any reward boost, and records the claim in their stats. Returns the amount to pay out.
+fn take_pending_rewards(
+    state: &State,
+    user_stake: &mut UserStake,
+    stats: &mut UserRewardStats,
+    limit: Option<u64>,
+) -> Result<u64> {
+    settle_user_rewards(state, user_stake)?;
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+    let mut amount = limit.unwrap_or(user_stake.pending_rewards);
+    require!(amount <= user_stake.pending_rewards, CustomError::InsufficientRewards);
+    user_stake.pending_rewards -= amount;
+    let boost_bps = reward_boost_bps(user_stake, &clock);
+    if boost_bps > 0 {
+        let boost = (amount as u128)
+            .checked_mul(boost_bps as u128)
+            .ok_or(CustomError::MathOverflow)?
+            / BPS_DENOMINATOR as u128;
+        amount = amount
+            .checked_add(boost as u64)
+            .ok_or(CustomError::MathOverflow)?;
+    }
+    user_stake.last_claim = now;
+
+    stats.lifetime_earned = user_stake.lifetime_accrued;
+    stats.lifetime_claimed = stats
+        .lifetime_claimed
+        .checked_add(amount)
+        .ok_or(CustomError::MathOverflow)?;
+    stats.last_claim = now;
+    Ok(amount)
+}
+
+/// Applies a change to a user's shares to `state.principal_only_staked` if they opted out of
+/// rewards.
+fn track_principal_only(
+    state: &mut State,
+    user_stake: &UserStake,
+    added: u64,
+    removed: u64,
+) -> Result<()> {
+    if user_stake.principal_only {
+        state.principal_only_staked = state
+            .principal_only_staked
+            .checked_add(added)
+            .ok_or(CustomError::MathOverflow)?
+            .checked_sub(removed)
+            .ok_or(CustomError::MathOverflow)?;
+    }
+    Ok(())
+}
+
+/// Applies a change to a user's shares to `state.lending_staked` if they are in the lending
+/// bucket.
+fn track_risk_bucket(
+    state: &mut State,
+    user_stake: &UserStake,
+    added: u64,
+    removed: u64,
+) -> Result<()> {
+    if user_stake.risk_bucket == RiskBucket::HedgedLending {
+        state.lending_staked = state
+            .lending_staked
+            .checked_add(added)
+            .ok_or(CustomError::MathOverflow)?
+            .checked_sub(removed)
+            .ok_or(CustomError::MathOverflow)?;
+    }
+    Ok(())
+}
+
+/// Converts the lending yield or loss a lending-bucket stake has accrued since its last snapshot
+/// of `state.lending_pnl_index` into shares issued or burned at NAV. NAV moves by exactly the
+/// PnL and share count by its value at NAV, so hedged-only stakers' share value is untouched.
+/// Gains round down and losses round up.
+fn settle_lending_pnl(state: &mut State, user_stake: &mut UserStake) -> Result<()> {
+    let index_delta = state
+        .lending_pnl_index
+        .checked_sub(user_stake.lending_pnl_snapshot)
+        .ok_or(CustomError::MathOverflow)?;
+    user_stake.lending_pnl_snapshot = state.lending_pnl_index;
+    if user_stake.risk_bucket != RiskBucket::HedgedLending || index_delta == 0 {
+        return Ok(());
+    }
+    let gain = index_delta > 0;
+    let rounding = if gain { Rounding::Down } else { Rounding::Up };
+    let value = q64_mul(index_delta.unsigned_abs(), user_stake.amount, rounding)
+        .ok_or(CustomError::MathOverflow)?;
+    let shares = state.value_to_shares(value)?;
+    // The difference between the PnL and its share count keeps NAV per share unchanged.
+    let pnl_adjustment = value as i64 - shares as i64;
+    if gain {
+        state.total_staked = state
+            .total_staked
+            .checked_add(shares)
+            .ok_or(CustomError::MathOverflow)?;
+        state.cumulative_realized_pnl = state
+            .cumulative_realized_pnl
+            .checked_add(pnl_adjustment)
+            .ok_or(CustomError::MathOverflow)?;
+        user_stake.amount = user_stake.amount.checked_add(shares).ok_or(CustomError::MathOverflow)?;
+        track_principal_only(state, user_stake, shares, 0)?;
+        track_risk_bucket(state, user_stake, shares, 0)?;
+    } else {
+        let shares = shares.min(user_stake.amount);
+        state.total_staked = state
+            .total_staked
+            .checked_sub(shares)
+            .ok_or(CustomError::MathOverflow)?;
+        state.cumulative_realized_pnl = state
+            .cumulative_realized_pnl
+            .checked_sub(pnl_adjustment)
+            .ok_or(CustomError::MathOverflow)?;
+        user_stake.amount -= shares;
+        track_principal_only(state, user_stake, 0, shares)?;
+        track_risk_bucket(state, user_stake, 0, shares)?;
+    }
+    Ok(())
+}
+
+/// Credits the queue-jump fees paid since the user's last snapshot of `State::queue_fee_index`
+/// as new shares.
+fn settle_queue_fees(state: &mut State, user_stake: &mut UserStake) -> Result<()> {
+    let index_delta = state
+        .queue_fee_index
+        .checked_sub(user_stake.queue_fee_snapshot)
+        .ok_or(CustomError::MathOverflow)?;
+    user_stake.queue_fee_snapshot = state.queue_fee_index;
+    let value =
+        q64_mul(index_delta, user_stake.amount, Rounding::Down).ok_or(CustomError::MathOverflow)?;
+    if value == 0 {
+        return Ok(());
+    }
+    let shares = state.value_to_shares(value)?;
+    // The difference between the fee and its share count keeps NAV per share unchanged.
+    state.total_staked = state
+        .total_staked
+        .checked_add(shares)
+        .ok_or(CustomError::MathOverflow)?;
+    state.cumulative_realized_pnl = state
+        .cumulative_realized_pnl
+        .checked_add(value as i64 - shares as i64)
+        .ok_or(CustomError::MathOverflow)?;
+    user_stake.amount = user_stake.amount.checked_add(shares).ok_or(CustomError::MathOverflow)?;
+    track_principal_only(state, user_stake, shares, 0)?;
+    track_risk_bucket(state, user_stake, shares, 0)?;
+    Ok(())
+}
+
+/// Time-weighted average of the recorded funding prints. Each print is weighted by how long it
+/// stood before the next one; the newest print is weighted up to `now`.
+fn funding_twap(history: &FundingHistory, now: i64) -> Result<u64> {
+    let count = history.count as usize;
+    require!(count > 0, CustomError::StaleOracle);
+
+    // Oldest observation sits at `head` once the buffer has wrapped, otherwise at index 0.
+    let oldest = if count == FUNDING_HISTORY_LEN { history.head as usize } else { 0 };
+    let mut weighted_sum: u128 = 0;
+    let mut total_time: u128 = 0;
+    for i in 0..count {
+        let obs = history.observations[(oldest + i) % FUNDING_HISTORY_LEN];
+        let end = if i + 1 < count {
+            history.observations[(oldest + i + 1) % FUNDING_HISTORY_LEN].timestamp
+        } else {
+            now
+        };
+        let dt = end.saturating_sub(obs.timestamp).max(0) as u128;
+        weighted_sum = weighted_sum
+            .checked_add((obs.rate as u128).checked_mul(dt).ok_or(CustomError::MathOverflow)?)
+            .ok_or(CustomError::MathOverflow)?;
+        total_time += dt;
+    }
+    if total_time == 0 {
+        // All prints landed in the same second; fall back to the newest one.
+        let newest = (oldest + count - 1) % FUNDING_HISTORY_LEN;
+        return Ok(history.observations[newest].rate);
+    }
+    u64::try_from(weighted_sum / total_time).map_err(|_| error!(CustomError::MathOverflow))
+}
+
+/// Returns the absolute net delta from a keeper report, rejecting reports older than the max age.
+fn read_net_delta(report: &DeltaReport, state: &State) -> Result<u64> {
+    require_fresh_delta_report(report, state)?;
+    Ok(report.net_delta.unsigned_abs())
+}
+
+/// Hedged notional (the perp leg of a fresh keeper report) as a share of total stake, in bps and
+/// capped at 100%.
+fn utilization_bps(report: &DeltaReport, state: &State) -> Result<u64> {
+    require_fresh_delta_report(report, state)?;
+    if state.total_staked == 0 {
+        return Ok(0);
+    }
+    let utilization = (report.perp_exposure.unsigned_abs() as u128)
+        .checked_mul(BPS_DENOMINATOR as u128)
+        .ok_or(CustomError::MathOverflow)?
+        / state.total_staked as u128;
+    Ok(utilization.min(BPS_DENOMINATOR as u128) as u64)
+}
+
+fn require_fresh_delta_report(report: &DeltaReport, state: &State) -> Result<()> {
+    let age = Clock::get()?
+        .unix_timestamp
+        .checked_sub(report.updated_at)
+        .ok_or(CustomError::MathOverflow)?;
+    require!(age <= state.max_delta_report_age, CustomError::StaleDeltaReport);
+    Ok(())
+}
+
+/// Rejects stakes that are not allowed in the current launch phase.
+fn enforce_launch_phase(
+    state: &State,
+    user: &Pubkey,
+    proof: &[[u8; 32]],
+    new_user_amount: u64,
+) -> Result<()> {
+    if state.launch_phase == LaunchPhase::Whitelist {
+        let leaf = keccak::hashv(&[user.as_ref()]).0;
+        require!(
+            verify_merkle_proof(proof, state.whitelist_root, leaf),
+            CustomError::NotWhitelisted
+        );
+    }
+    if state.launch_phase != LaunchPhase::Open {
+        require!(
+            new_user_amount <= state.capped_phase_max_stake,
+            CustomError::ExceedsLaunchCap
+        );
+    }
+    Ok(())
+}
+
+/// Verifies a keccak merkle proof built with sorted-pair hashing.
+fn verify_merkle_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let mut computed = leaf;
+    for node in proof {
+        computed = if computed <= *node {
+            keccak::hashv(&[&computed, node]).0
+        } else {
+            keccak::hashv(&[node, &computed]).0
+        };
+    }
+    computed == root
+}
+
+/// Advances the global funding index by `rate` (bps per FUNDING_INTERVAL) over the time since the
+/// last accrual.
+fn accrue_funding(state: &mut State, rate: u64, now: i64) -> Result<()> {
+    let elapsed = now.saturating_sub(state.last_funding_accrual).max(0) as i128;
+    let delta = (rate as i128)
+        .checked_mul(elapsed)
+        .and_then(|v| v.checked_mul(PRICE_PRECISION as i128))
+        .ok_or(CustomError::MathOverflow)?
+        / FUNDING_INTERVAL as i128;
+    state.cumulative_funding_index = state
+        .cumulative_funding_index
+        .checked_add(delta)
+        .ok_or(CustomError::MathOverflow)?;
+    state.last_funding_accrual = now;
+    Ok(())
+}
+
+/// Applies funding accrued since the position was last touched. Longs pay and shorts receive when
+/// the index rises; the vault is the counterparty, so payments move through realized PnL. A
+/// payment is capped at the position's collateral. Returns the funding the vault received
+/// (negative when it paid).
+fn settle_position_funding(state: &mut State, position: &mut Position) -> Result<i64> {
+    let index_delta = state
+        .cumulative_funding_index
+        .checked_sub(position.last_funding_index)
+        .ok_or(CustomError::MathOverflow)?;
+    position.last_funding_index = state.cumulative_funding_index;
+    let owed = (position.size as i128)
+        .checked_mul(index_delta)
+        .ok_or(CustomError::MathOverflow)?
+        / (BPS_DENOMINATOR as i128 * PRICE_PRECISION as i128);
+    let booked = book_position_payment(state, position, owed)?;
+    position.refresh_liquidation_price(state.max_allowed_loss_bps);
+    if booked != 0 {
+        position.record(PositionActionKind::Funding, booked)?;
+    }
+    Ok(booked)
+}
+
+/// Moves `owed` between a position's collateral and the vault's realized PnL: positive amounts
+/// are paid by the trader to the vault, capped at the position's collateral. Returns the amount
+/// the vault booked.
+fn book_position_payment(state: &mut State, position: &mut Position, owed: i128) -> Result<i64> {
+    let owed = owed.clamp(-(u64::MAX as i128), position.collateral as i128);
+    if owed == 0 {
+        return Ok(0);
+    }
+
+    let payment = owed.unsigned_abs() as u64;
+    if owed > 0 {
+        position.collateral -= payment;
+        state.total_position_collateral = state
+            .total_position_collateral
+            .checked_sub(payment)
+            .ok_or(CustomError::MathOverflow)?;
+    } else {
+        position.collateral = position
+            .collateral
+            .checked_add(payment)
+            .ok_or(CustomError::MathOverflow)?;
+        state.total_position_collateral = state
+            .total_position_collateral
+            .checked_add(payment)
+            .ok_or(CustomError::MathOverflow)?;
+    }
+    let pnl = i64::try_from(owed).map_err(|_| CustomError::MathOverflow)?;
+    state.cumulative_realized_pnl = state
+        .cumulative_realized_pnl
+        .checked_add(pnl)
+        .ok_or(CustomError::MathOverflow)?;
+    state.position_realized_pnl = state
+        .position_realized_pnl
+        .checked_add(pnl)
+        .ok_or(CustomError::MathOverflow)?;
+    Ok(pnl)
+}
+
+/// Unrealized PnL of a position at `mark_price`, in base units.
+fn position_pnl(position: &Position, mark_price: u64) -> Result<i128> {
+    require!(position.entry_price > 0, CustomError::InvalidParameter);
+    Ok(position.size as i128 * (mark_price as i128 - position.entry_price as i128)
+        / position.entry_price as i128)
+}
+
+/// A position is healthy while its unrealized loss stays within `max_loss_bps` of its collateral.
+fn is_position_healthy(position: &Position, mark_price: u64, max_loss_bps: u64) -> Result<bool> {
+    let loss = (-position_pnl(position, mark_price)?).max(0) as u128;
+    Ok(loss * BPS_DENOMINATOR as u128 <= position.collateral as u128 * max_loss_bps as u128)
+}
+
+/// Checks that a keeper-signed instruction carries the vault's current keeper nonce and advances
+/// it, so a captured keeper transaction cannot be replayed later.
+fn consume_keeper_nonce(state: &mut State, nonce: u64) -> Result<()> {
+    require!(nonce == state.keeper_nonce, CustomError::KeeperNonceMismatch);
+    state.keeper_nonce = state.keeper_nonce.checked_add(1).ok_or(CustomError::MathOverflow)?;
+    Ok(())
+}
+
+/// Returns the next event sequence number for this vault.
+fn next_event_seq(state: &mut State) -> Result<u64> {
+    state.event_seq = state.event_seq.checked_add(1).ok_or(CustomError::MathOverflow)?;
+    Ok(state.event_seq)
+}
+
+/// Validates and queues `value` for `key`, returning when it may be activated. The vault's risk
+/// parameters with the change applied must pass `RiskParams::check`.
+fn queue_param_change(
+    state: &State,
+    registry: &mut ParamRegistry,
+    key: ParamKey,
+    value: ParamValue,
+) -> Result<i64> {
+    key.validate(value)?;
+    RiskParams::of(state).with_change(key, value).check()?;
+    let activates_at = Clock::get()?
+        .unix_timestamp
+        .checked_add(PARAM_TIMELOCK_SECS)
+        .ok_or(CustomError::MathOverflow)?;
+    let entry = registry.entry_mut(key)?;
+    entry.pending = Some(value);
+    entry.activates_at = activates_at;
+    Ok(activates_at)
+}
+
+/// Rejects new stake while the keeper is inactive, the vault is paused, or the vault is outside
+/// its delta band.
+fn require_accepting_stakes(state: &State, delta_report: &DeltaReport) -> Result<()> {
+    require!(
+        !keeper_inactive(state, Clock::get()?.unix_timestamp),
+        CustomError::KeeperInactive
+    );
+    require!(!state.is_paused(), CustomError::VaultPaused);
+    let net_delta = read_net_delta(delta_report, state)?;
+    require!(
+        net_delta <= state.allowed_delta_threshold,
+        CustomError::DeltaThresholdExceeded
+    );
+    Ok(())
+}
+
+/// Credits `amount` of new stake to a user after settling their rewards. Fails once the user's
+/// lifetime deposits would exceed their tier's cap.
+fn credit_stake(state: &mut State, user_stake: &mut UserStake, amount: u64, now: i64) -> Result<()> {
+    user_stake.lifetime_deposited = user_stake
+        .lifetime_deposited
+        .checked_add(amount)
+        .ok_or(CustomError::MathOverflow)?;
+    require!(
+        user_stake.lifetime_deposited <= state.tier_caps[user_stake.tier as usize],
+        CustomError::TierCapExceeded
+    );
+    checkpoint_rewards(state, now)?;
+    settle_user_rewards(state, user_stake)?;
+    settle_lending_pnl(state, user_stake)?;
+    settle_queue_fees(state, user_stake)?;
+    state.total_staked = state.total_staked.checked_add(amount).ok_or(CustomError::MathOverflow)?;
+    user_stake.amount = user_stake.amount.checked_add(amount).ok_or(CustomError::MathOverflow)?;
+    track_principal_only(state, user_stake, amount, 0)?;
+    track_risk_bucket(state, user_stake, amount, 0)?;
+    user_stake.last_update = now;
+    Ok(())
+}
+
+/// Caps the part of a base-token stake of `amount` credited now at what
+/// `State::max_single_hedge_notional` allows, so one deposit cannot force a hedge large enough to
+/// move the market against existing stakers. The rest is added to the stake's pending tranche,
+/// credited after the next rebalance. Returns the amount to credit now.
+fn split_large_stake(state: &mut State, user_stake: &mut UserStake, amount: u64) -> Result<u64> {
+    if state.max_single_hedge_notional == 0 {
+        return Ok(amount);
+    }
+    let max_amount = mul_div_u64(
+        state.max_single_hedge_notional,
+        PRICE_PRECISION,
+        get_spot_price()?.max(1),
+        Rounding::Down,
+    )
+    .unwrap_or(u64::MAX);
+    if amount <= max_amount {
+        return Ok(amount);
+    }
+    let pending = amount - max_amount;
+    // The whole deposit must fit the tier cap now, so the tranche can always be credited.
+    let lifetime_deposited = user_stake
+        .lifetime_deposited
+        .checked_add(amount)
+        .ok_or(CustomError::MathOverflow)?;
+    require!(
+        lifetime_deposited <= state.tier_caps[user_stake.tier as usize],
+        CustomError::TierCapExceeded
+    );
+    user_stake.pending_tranche = user_stake
+        .pending_tranche
+        .checked_add(pending)
+        .ok_or(CustomError::MathOverflow)?;
+    user_stake.pending_tranche_after = state.last_hedged_at;
+    state.pending_tranches = state
+        .pending_tranches
+        .checked_add(pending)
+        .ok_or(CustomError::MathOverflow)?;
+    Ok(max_amount)
+}
+
+/// Stake `State::strategy_capacity` still admits; none while deposits are queued, so later
+/// deposits cannot jump the queue, and `u64::MAX` without a capacity.
+fn capacity_headroom(state: &State) -> u64 {
+    if state.strategy_capacity == 0 {
+        return u64::MAX;
+    }
+    if state.pending_deposit_head < state.pending_deposit_tail {
+        return 0;
+    }
+    state
+        .strategy_capacity
+        .saturating_sub(state.total_staked.saturating_add(state.pending_tranches))
+}
+
+/// The optional `PendingDeposit` an instruction was given, which must be present exactly when
+/// `queued` is nonzero, with its bump filled in.
+fn pending_deposit_slot<'a, 'info>(
+    slot: &'a mut Option<Account<'info, PendingDeposit>>,
+    bump: Option<u8>,
+    queued: u64,
+) -> Result<Option<&'a mut Account<'info, PendingDeposit>>> {
+    require!((queued > 0) == slot.is_some(), CustomError::PendingDepositRequired);
+    if let Some(pending_deposit) = slot.as_mut() {
+        pending_deposit.bump = bump.ok_or(CustomError::InvalidParameter)?;
+    }
+    Ok(slot.as_mut())
+}
+
+/// Creates the `PendingDeposit` at the queue tail from an account passed in `remaining_accounts`,
+/// for batch instructions that cannot declare it. The caller fills it in with `queue_deposit`
+/// and serializes it.
+fn create_pending_deposit<'info>(
+    state: &Account<'info, State>,
+    slot: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<PendingDeposit> {
+    let state_key = state.key();
+    let tail = state.pending_deposit_tail.to_le_bytes();
+    let (address, bump) = Pubkey::find_program_address(
+        &[b"pending_deposit", state_key.as_ref(), &tail],
+        &crate::ID,
+    );
+    require_keys_eq!(slot.key(), address, CustomError::InvalidBatchAccounts);
+    let space = 8 + PendingDeposit::INIT_SPACE;
+    anchor_lang::system_program::create_account(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            anchor_lang::system_program::CreateAccount {
+                from: payer.to_account_info(),
+                to: slot.clone(),
+            },
+            &[&[b"pending_deposit", state_key.as_ref(), &tail, &[bump]]],
+        ),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        &crate::ID,
+    )?;
+    Ok(PendingDeposit {
+        bump,
+        state: state_key,
+        id: 0,
+        user: Pubkey::default(),
+        amount: 0,
+        queued_at: 0,
+        mint: Pubkey::default(),
+        deposited: 0,
+        _reserved: [0; RESERVED_SPACE],
+    })
+}
+
+/// Records a deposit as the next `PendingDeposit` in the queue: `amount` of stake to credit,
+/// backed by `deposited` tokens of `mint`. Like a pending tranche, the whole deposit must fit the
+/// tier cap now so it can always be admitted.
+fn queue_deposit(
+    state: &mut Account<State>,
+    user_stake: &mut UserStake,
+    pending_deposit: &mut PendingDeposit,
+    user: Pubkey,
+    mint: Pubkey,
+    deposited: u64,
+    amount: u64,
+) -> Result<()> {
+    let lifetime_deposited = user_stake
+        .lifetime_deposited
+        .checked_add(amount)
+        .ok_or(CustomError::MathOverflow)?;
+    require!(
+        lifetime_deposited <= state.tier_caps[user_stake.tier as usize],
+        CustomError::TierCapExceeded
+    );
+    pending_deposit.state = state.key();
+    pending_deposit.id = state.pending_deposit_tail;
+    pending_deposit.user = user;
+    pending_deposit.amount = amount;
+    pending_deposit.queued_at = Clock::get()?.unix_timestamp;
+    pending_deposit.mint = mint;
+    pending_deposit.deposited = deposited;
+    user_stake.queued_deposits = user_stake
+        .queued_deposits
+        .checked_add(1)
+        .ok_or(CustomError::MathOverflow)?;
+    state.pending_deposit_tail = state
+        .pending_deposit_tail
+        .checked_add(1)
+        .ok_or(CustomError::MathOverflow)?;
+    state.pending_deposits = state
+        .pending_deposits
+        .checked_add(amount)
+        .ok_or(CustomError::MathOverflow)?;
+    Ok(())
+}
+
+/// Posts a base-token deposit to the ledger: the `admitted` part as staker principal and the
+/// `queued` part as a pending deposit.
+fn post_deposit(ledger: &AccountLoader<Ledger>, admitted: u64, queued: u64) -> Result<()> {
+    ledger::post(ledger, LedgerAccount::Vault, LedgerAccount::StakerPrincipal, admitted)?;
+    ledger::post(ledger, LedgerAccount::Vault, LedgerAccount::PendingDeposits, queued)
+}
+
+/// Emits `DepositQueued` when a deposit was queued in `pending_deposit`.
+fn emit_deposit_queued(
+    state: &mut Account<State>,
+    pending_deposit: Option<&PendingDeposit>,
+) -> Result<()> {
+    let Some(pending_deposit) = pending_deposit else {
+        return Ok(());
+    };
+    let seq = next_event_seq(state)?;
+    emit!(DepositQueued {
+        seq,
+        state: state.key(),
+        user: pending_deposit.user,
+        id: pending_deposit.id,
+        amount: pending_deposit.amount,
+        total_queued: state.pending_deposits,
+    });
+    Ok(())
+}
+
+/// Emits `StakeTranchePending` when a stake left `pending` tokens waiting for the next rebalance.
+fn emit_pending_tranche(
+    state: &mut Account<State>,
+    user: Pubkey,
+    user_stake: &UserStake,
+    pending: u64,
+) -> Result<()> {
+    if pending == 0 {
+        return Ok(());
+    }
+    let seq = next_event_seq(state)?;
+    emit!(StakeTranchePending {
+        seq,
+        state: state.key(),
+        user,
+        amount: pending,
+        total_pending: user_stake.pending_tranche,
+    });
+    Ok(())
+}
+
+/// Removes `amount` of stake for withdrawal. Returns true if it may be paid out now, or false if
+/// it exceeded this epoch's outflow budget and was queued instead.
+fn debit_unstake(
+    state: &mut State,
+    user_stake: &mut UserStake,
+    amount: u64,
+    now: i64,
+    epoch: u64,
+) -> Result<bool> {
+    // Enforce a minimum staking duration to help prevent flash loan exploits.
+    // Waived, along with the outflow cap, once the keeper has gone silent, and for balances small
+    // enough that their exit cannot move the hedge book.
+    let emergency = keeper_inactive(state, now);
+    let priority = emergency || user_stake.amount < state.small_balance_threshold;
+    require!(
+        priority
+            || now.checked_sub(user_stake.last_update)
+                .ok_or(CustomError::MathOverflow)?
+                >= state.min_stake_duration,
+        CustomError::EarlyUnstakeNotAllowed
+    );
+
+    checkpoint_rewards(state, now)?;
+    settle_user_rewards(state, user_stake)?;
+    settle_lending_pnl(state, user_stake)?;
+    settle_queue_fees(state, user_stake)?;
+    require!(user_stake.unlocked() >= amount, CustomError::InsufficientStake);
+    user_stake.amount = user_stake.amount.checked_sub(amount).ok_or(CustomError::MathOverflow)?;
+    track_principal_only(state, user_stake, 0, amount)?;
+    track_risk_bucket(state, user_stake, 0, amount)?;
+    state.total_staked = state
+        .total_staked
+        .checked_sub(amount)
+        .ok_or(CustomError::MathOverflow)?;
+
+    // Withdrawals beyond this epoch's outflow budget wait in the queue instead.
+    let budget = remaining_outflow_budget(state, epoch)?;
+    if amount > budget && !priority {
+        state.queued_withdrawals = state
+            .queued_withdrawals
+            .checked_add(amount)
+            .ok_or(CustomError::MathOverflow)?;
+        user_stake.queued_withdrawal = user_stake
+            .queued_withdrawal
+            .checked_add(amount)
+            .ok_or(CustomError::MathOverflow)?;
+        return Ok(false);
+    }
+    state.epoch_outflow = state
+        .epoch_outflow
+        .checked_add(amount)
+        .ok_or(CustomError::MathOverflow)?;
+    Ok(true)
 }
 
-#[derive(Accounts)]
-pub struct RewardMakers<'info> {
-    #[account(mut, seeds = [b"state", state_owner.key().as_ref()], bump = state.bump)]
-    pub state: Account<'info, State>,
-    /// CHECK: Reference to state owner.
-    pub state_owner: AccountInfo<'info>,
-    #[account(mut)]
-    pub token_mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub rewards_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+/// Moves lamports from `from` into a WSOL token account and syncs its token balance.
+fn wrap_lamports<'info>(
+    from: &Signer<'info>,
+    wsol_account: &Account<'info, TokenAccount>,
+    system_program: &Program<'info, System>,
+    token_program: &Program<'info, Token>,
+    lamports: u64,
+) -> Result<()> {
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: from.to_account_info(),
+                to: wsol_account.to_account_info(),
+            },
+        ),
+        lamports,
+    )?;
+    token::sync_native(CpiContext::new(
+        token_program.to_account_info(),
+        SyncNative { account: wsol_account.to_account_info() },
+    ))
 }
 
-#[derive(Accounts)]
-pub struct Liquidate<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
-    // Additional accounts for managing positions could be added here.
+/// Rejects the caller if its blocklist entry exists.
+fn require_not_blocked(blocked: &AccountInfo) -> Result<()> {
+    require!(blocked.data_is_empty(), CustomError::AddressBlocked);
+    Ok(())
 }
 
-#[derive(Accounts)]
-pub struct Vote<'info> {
-    #[account(mut, seeds = [b"state", state_owner.key().as_ref()], bump = state.bump)]
-    pub state: Account<'info, State>,
-    /// CHECK: Reference to state owner.
-    pub state_owner: AccountInfo<'info>,
+/// `bps` basis points of `amount`, rounded down.
+fn bps_of(amount: u64, bps: u64) -> Result<u64> {
+    let value = (amount as u128)
+        .checked_mul(bps as u128)
+        .ok_or(CustomError::MathOverflow)?
+        / BPS_DENOMINATOR as u128;
+    u64::try_from(value).map_err(|_| CustomError::MathOverflow.into())
 }
 
-// -----------------------------------------------------------------------------
-// Helper Functions & Placeholders
-// -----------------------------------------------------------------------------
+/// Withdraw fee on `amount` of the base asset: the base collateral's fee if the base mint is
+/// registered, otherwise none.
+fn base_withdraw_fee(registry: &CollateralRegistry, state: &State, amount: u64) -> Result<u64> {
+    let fee_bps = registry
+        .collaterals
+        .iter()
+        .find(|c| c.mint == state.base_mint)
+        .map_or(0, |c| c.withdraw_fee_bps);
+    bps_of(amount, fee_bps)
+}
 
-fn get_funding_rate_from_oracle() -> Result<u64> {
-    // Placeholder: Return a dummy funding rate (e.g., 5 basis points).
-    Ok(5)
+/// Initial-margin leverage limit for positions, taken from the base mint's collateral config.
+fn base_max_leverage_bps(registry: &CollateralRegistry, state: &State) -> u64 {
+    registry
+        .collaterals
+        .iter()
+        .find(|c| c.mint == state.base_mint)
+        .map_or(DEFAULT_MAX_LEVERAGE_BPS, |c| c.max_leverage_bps)
 }
 
-fn get_arbitrage_profits_from_vault() -> Result<u64> {
-    // Placeholder: Return dummy arbitrage profits.
-    Ok(1_000)
+/// True while `size` of notional on `collateral` stays within `max_leverage_bps`.
+fn within_leverage(collateral: u64, size: i64, max_leverage_bps: u64) -> bool {
+    size.unsigned_abs() as u128 * BPS_DENOMINATOR as u128
+        <= collateral as u128 * max_leverage_bps as u128
 }
 
-fn get_maker_trading_volume() -> Result<u64> {
-    // Placeholder: Return dummy maker trading volume.
-    Ok(5_000)
+/// Deposit fee and risk-weighted stake credited for depositing `amount` of a collateral, priced
+/// from `cache` when one is given.
+fn price_collateral_deposit(
+    config: &CollateralConfig,
+    stake_pool: Option<&AccountInfo>,
+    cache: Option<&PriceCache>,
+    amount: u64,
+) -> Result<(u64, u64)> {
+    let fee = bps_of(amount, config.deposit_fee_bps)?;
+    let net_amount = amount.checked_sub(fee).ok_or(CustomError::MathOverflow)?;
+    let conversion_rate = match cache {
+        Some(cache) => cache.conversion_rate(config.mint)?,
+        None => get_conversion_rate(config, stake_pool)?,
+    };
+    let normalized_amount = (net_amount as u128)
+        .checked_mul(conversion_rate as u128)
+        .ok_or(CustomError::MathOverflow)?
+        .checked_mul(config.weight_bps as u128)
+        .ok_or(CustomError::MathOverflow)?
+        / (PRICE_PRECISION as u128 * BPS_DENOMINATOR as u128);
+    let normalized_amount =
+        u64::try_from(normalized_amount).map_err(|_| CustomError::MathOverflow)?;
+    Ok((fee, normalized_amount))
 }
 
-fn get_conversion_rate(_asset_type: u8) -> Result<u64> {
-    // Placeholder: Assume a 1:1 conversion rate.
-    Ok(1)
+/// Pays `amount` less the base withdraw fee to `destination` (default: the user's token account)
+/// and moves the fee into the fee vault. Large withdrawals may not share a transaction with a swap.
+fn pay_withdrawal<'info>(
+    accounts: &mut Unstake<'info>,
+    destination: Option<&Account<'info, TokenAccount>>,
+    amount: u64,
+) -> Result<()> {
+    if is_large_unstake(&accounts.state, amount) {
+        let instructions_sysvar = accounts
+            .instructions_sysvar
+            .as_ref()
+            .ok_or(CustomError::InstructionsSysvarRequired)?;
+        reject_dex_swaps(instructions_sysvar)?;
+    }
+    let fee = base_withdraw_fee(&accounts.collateral_registry, &accounts.state, amount)?;
+    let net_amount = amount.checked_sub(fee).ok_or(CustomError::MathOverflow)?;
+    transfer_from_vault(
+        &accounts.state,
+        &accounts.state_owner,
+        &accounts.vault_account,
+        destination.unwrap_or(&accounts.user_token_account),
+        &accounts.token_program,
+        net_amount,
+    )?;
+    ledger::post(
+        &accounts.ledger,
+        LedgerAccount::StakerPrincipal,
+        LedgerAccount::Vault,
+        net_amount,
+    )?;
+    // The fee stays in the vault's custody but now belongs to the protocol.
+    ledger::post(&accounts.ledger, LedgerAccount::StakerPrincipal, LedgerAccount::FeeRevenue, fee)?;
+    if fee > 0 {
+        transfer_from_vault(
+            &accounts.state,
+            &accounts.state_owner,
+            &accounts.vault_account,
+            &accounts.fee_vault,
+            &accounts.token_program,
+            fee,
+        )?;
+        book_fee(&mut accounts.state, accounts.vault_account.mint, fee)?;
+        let seq = next_event_seq(&mut accounts.state)?;
+        emit!(FeeCollected {
+            seq,
+            state: accounts.state.key(),
+            user: accounts.user.key(),
+            mint: accounts.state.base_mint,
+            amount: fee,
+        });
+    }
+    Ok(())
 }
 
-struct UserPosition {
-    pub loss_percentage: u64,
+/// Fee for skipping the withdrawal queue, in bps: rises with the share of the vault already
+/// queued and with how much of it is deployed in hedges.
+fn queue_jump_fee_bps(state: &State, utilization_bps: u64) -> Result<u64> {
+    let outstanding = (state.total_staked as u128)
+        .checked_add(state.queued_withdrawals as u128)
+        .ok_or(CustomError::MathOverflow)?;
+    let depth_bps = (state.queued_withdrawals as u128 * BPS_DENOMINATOR as u128)
+        .checked_div(outstanding)
+        .unwrap_or(0) as u64;
+    let fee_bps = QUEUE_JUMP_BASE_FEE_BPS
+        + depth_bps * QUEUE_JUMP_DEPTH_WEIGHT_BPS / BPS_DENOMINATOR
+        + utilization_bps * QUEUE_JUMP_UTILIZATION_WEIGHT_BPS / BPS_DENOMINATOR;
+    Ok(fee_bps.min(QUEUE_JUMP_MAX_FEE_BPS))
 }
 
-fn get_user_position(_user: &Pubkey) -> Result<UserPosition> {
-    // Placeholder: Return a dummy user position.
-    Ok(UserPosition { loss_percentage: 10 })
+/// Books a fee into `base_fees_accrued` when it was paid in the base mint.
+fn book_fee(state: &mut State, mint: Pubkey, fee: u64) -> Result<()> {
+    if mint == state.base_mint {
+        state.base_fees_accrued = state
+            .base_fees_accrued
+            .checked_add(fee)
+            .ok_or(CustomError::MathOverflow)?;
+    }
+    Ok(())
 }
 
-fn force_close_position(_ctx: &Context<Liquidate>) -> Result<()> {
-    // Placeholder for force-closing a user's position.
+/// Raises the NAV-per-share high-water mark, or trips the drawdown breaker once NAV per share
+/// falls more than `max_drawdown_bps` below it.
+fn check_drawdown(state: &mut Account<State>) -> Result<()> {
+    let nav_per_share = state.nav_per_share()?;
+    if nav_per_share >= state.peak_nav_per_share {
+        state.peak_nav_per_share = nav_per_share;
+        return Ok(());
+    }
+    if state.max_drawdown_bps == 0 || state.drawdown_paused {
+        return Ok(());
+    }
+    let drawdown = state.peak_nav_per_share - nav_per_share;
+    if drawdown > bps_of(state.peak_nav_per_share, state.max_drawdown_bps)? {
+        state.drawdown_paused = true;
+        let seq = next_event_seq(state)?;
+        emit!(DrawdownBreakerTripped {
+            seq,
+            state: state.key(),
+            peak_nav_per_share: state.peak_nav_per_share,
+            nav_per_share,
+        });
+    }
     Ok(())
 }
 
-fn update_state_after_liquidation(_ctx: &Context<Liquidate>) -> Result<()> {
-    // Placeholder for updating state after liquidation.
+/// Requires at least 60% of votes in favour.
+fn require_vote_passed(tally: &VoteTally) -> Result<()> {
+    require!(tally.epoch == Clock::get()?.epoch, CustomError::NotEnoughVotes);
+    let total_votes = tally
+        .yes_weight
+        .checked_add(tally.no_weight)
+        .ok_or(CustomError::MathOverflow)?;
+    let yes_pct = tally
+        .yes_weight
+        .checked_mul(100)
+        .ok_or(CustomError::MathOverflow)?
+        .checked_div(total_votes)
+        .ok_or(CustomError::NotEnoughVotes)?;
+    require!(
+        yes_pct >= 60,
+        CustomError::NotEnoughVotes
+    );
     Ok(())
 }
 
-fn get_total_votes() -> Result<u64> {
-    // Placeholder: Return total number of votes.
-    Ok(100)
+/// Voting weight of a staker: one vote per share, plus a vote-escrow bonus of up to 3x the locked
+/// shares that decays linearly to zero at unlock.
+fn voting_weight(user_stake: &UserStake, escrow: Option<&VoteEscrow>, now: i64) -> Result<u128> {
+    let mut weight = user_stake.amount as u128;
+    if let Some(escrow) = escrow {
+        let remaining = escrow.unlock_at.saturating_sub(now).clamp(0, VOTE_ESCROW_MAX_LOCK_SECS);
+        let bonus = (escrow.amount as u128)
+            .checked_mul((VOTE_ESCROW_MAX_MULTIPLIER_BPS - BPS_DENOMINATOR) as u128)
+            .ok_or(CustomError::MathOverflow)?
+            .checked_mul(remaining as u128)
+            .ok_or(CustomError::MathOverflow)?
+            / (BPS_DENOMINATOR as u128 * VOTE_ESCROW_MAX_LOCK_SECS as u128);
+        weight = weight.checked_add(bonus).ok_or(CustomError::MathOverflow)?;
+    }
+    Ok(weight)
+}
+
+/// True once the keeper has not confirmed the hedge within `max_rebalance_staleness`.
+fn keeper_inactive(state: &State, now: i64) -> bool {
+    state.max_rebalance_staleness > 0
+        && now.saturating_sub(state.last_hedged_at) > state.max_rebalance_staleness
+}
+
+/// Rolls the outflow window at epoch boundaries and returns what may still leave this epoch.
+fn remaining_outflow_budget(state: &mut State, epoch: u64) -> Result<u64> {
+    if epoch != state.outflow_epoch {
+        state.outflow_epoch = epoch;
+        state.epoch_start_staked = state
+            .total_staked
+            .checked_add(state.queued_withdrawals)
+            .ok_or(CustomError::MathOverflow)?;
+        state.epoch_outflow = 0;
+    }
+    if state.max_outflow_bps_per_epoch >= BPS_DENOMINATOR {
+        return Ok(u64::MAX);
+    }
+    let cap = (state.epoch_start_staked as u128)
+        .checked_mul(state.max_outflow_bps_per_epoch as u128)
+        .ok_or(CustomError::MathOverflow)?
+        / BPS_DENOMINATOR as u128;
+    Ok((cap as u64).saturating_sub(state.epoch_outflow))
 }
 
-fn get_yes_votes() -> Result<u64> {
-    // Placeholder: Return number of yes votes.
-    Ok(70)
+/// Helper function to transfer tokens out of a state-owned account, signed by the state PDA.
+fn transfer_from_vault<'info>(
+    state: &Account<'info, State>,
+    state_owner: &AccountInfo<'info>,
+    from: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let vault_id = state.vault_id.to_le_bytes();
+    let seeds = &[
+        b"state".as_ref(),
+        state_owner.key.as_ref(),
+        vault_id.as_ref(),
+        &[state.bump],
+    ];
+    let signer = &[&seeds[..]];
+    let cpi_accounts = Transfer {
+        from: from.to_account_info(),
+        to: to.to_account_info(),
+        authority: state.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer),
+        amount,
+    )?;
+    Ok(())
 }
 
 /// Helper function to mint rewards to a rewards account.
 fn mint_rewards<'info>(
-    state: &Account<'info, State>,
+    state: &mut Account<'info, State>,
     state_owner: &AccountInfo<'info>,
     token_mint: &Account<'info, Mint>,
     rewards_account: &Account<'info, TokenAccount>,
     token_program: &Program<'info, Token>,
     amount: u64,
 ) -> Result<()> {
-    let seeds = &[b"state", state_owner.key.as_ref(), &[state.bump]];
+    require!(state.registered, CustomError::VaultNotRegistered);
+    require!(amount <= state.remaining_emissions(), CustomError::RewardMintCapExceeded);
+    state.total_rewards_minted = state
+        .total_rewards_minted
+        .checked_add(amount)
+        .ok_or(CustomError::MathOverflow)?;
+    let vault_id = state.vault_id.to_le_bytes();
+    let seeds = &[
+        b"state".as_ref(),
+        state_owner.key.as_ref(),
+        vault_id.as_ref(),
+        &[state.bump],
+    ];
     let signer = &[&seeds[..]];
     let cpi_accounts = MintTo {
         mint: token_mint.to_account_info(),
@@ -470,6 +9536,734 @@ fn mint_rewards<'info>(
     Ok(())
 }
 
+// -----------------------------------------------------------------------------
+// Events
+// -----------------------------------------------------------------------------
+// Every event carries the vault's `seq` (see `State::event_seq`).
+
+#[event]
+pub struct Staked {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub mint: Pubkey,
+    pub deposited: u64,
+    pub stake_credited: u64,
+    /// Caller-supplied reconciliation ID, if any.
+    pub reference: Option<[u8; 32]>,
+}
+
+#[event]
+pub struct Unstaked {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct WithdrawalQueued {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct QueuedWithdrawalPaid {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct LiquidationJobClaimed {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub position: Pubkey,
+    pub liquidator: Pubkey,
+    pub expires_at_slot: u64,
+}
+
+#[event]
+pub struct LiquidatorSlashed {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub liquidator: Pubkey,
+    pub position: Pubkey,
+    pub reporter: Pubkey,
+    pub slashed: u64,
+}
+
+#[event]
+pub struct VaultRegistered {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub registered_by: Pubkey,
+}
+
+#[event]
+pub struct UnstakedTo {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct RoleGranted {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub role: Role,
+    pub holder: Pubkey,
+    pub granted_by: Pubkey,
+}
+
+#[event]
+pub struct RoleRevoked {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub role: Role,
+    pub holder: Pubkey,
+    pub revoked_by: Pubkey,
+}
+
+#[event]
+pub struct UserTierSet {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub tier: Tier,
+    pub set_by: Pubkey,
+}
+
+#[event]
+pub struct WithdrawalExpedited {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    /// Queued amount released, before the queue-jump fee.
+    pub amount: u64,
+    pub fee: u64,
+}
+
+#[event]
+pub struct StakeTransferred {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct StakeMigrated {
+    pub from_seq: u64,
+    pub to_seq: u64,
+    pub from_state: Pubkey,
+    pub to_state: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    /// Part of `amount` credited in the destination now; the rest is a pending tranche or queued.
+    pub stake_credited: u64,
+}
+
+#[event]
+pub struct Rebalanced {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub net_delta: i64,
+    pub unrealized_pnl: i64,
+}
+
+#[event]
+pub struct HedgeSettled {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub realized_pnl: i64,
+    pub cumulative_realized_pnl: i64,
+}
+
+#[event]
+pub struct RewardsDistributed {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub amount: u64,
+    pub reward_index: u128,
+    /// Rounding dust carried into the next checkpoint.
+    pub dust_carry: u64,
+}
+
+#[event]
+pub struct CollateralMarked {
+    pub seq: u64,
+    pub state: Pubkey,
+    /// Combined base-asset value of all deposited collateral.
+    pub total_value: u64,
+}
+
+#[event]
+pub struct TvlMarked {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub total_usd_value: u64,
+    pub nav: u64,
+}
+
+#[event]
+pub struct PricesRefreshed {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub slot: u64,
+    pub mark_price: u64,
+}
+
+#[event]
+pub struct Exited {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub rewards: u64,
+}
+
+#[event]
+pub struct LpSnapshotUpdated {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub owner: Pubkey,
+    pub balance: u64,
+    pub total_snapshot: u64,
+}
+
+#[event]
+pub struct LpRewardsClaimed {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct RewardsRedirected {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub amount: u64,
+    pub principal_only_staked: u64,
+}
+
+#[event]
+pub struct PrincipalOnlySet {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub enabled: bool,
+    pub principal_only_staked: u64,
+}
+
+#[event]
+pub struct RewardsClaimed {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AutoClaimThresholdSet {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub threshold: u64,
+}
+
+#[event]
+pub struct RewardsAutoClaimed {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub caller: Pubkey,
+    /// Rewards delivered to the user, after the caller's tip.
+    pub amount: u64,
+    pub tip: u64,
+}
+
+#[event]
+pub struct RewardsCompounded {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub stake_credited: u64,
+}
+
+#[event]
+pub struct RewardsSwept {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AirdropClaimed {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub distribution: Pubkey,
+    pub claimant: Pubkey,
+    pub index: u32,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AddressBlockChanged {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub address: Pubkey,
+    pub blocked: bool,
+}
+
+#[event]
+pub struct GuardianPauseChanged {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub paused: bool,
+    pub by: Pubkey,
+}
+
+#[event]
+pub struct GuardianNominated {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub nominee: Pubkey,
+    pub by: Pubkey,
+}
+
+#[event]
+pub struct GuardianRotated {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub previous: Pubkey,
+    pub guardian: Pubkey,
+}
+
+#[event]
+pub struct DrawdownBreakerTripped {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub peak_nav_per_share: u64,
+    pub nav_per_share: u64,
+}
+
+#[event]
+pub struct ParamChangeProposed {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub key: ParamKey,
+    pub value: ParamValue,
+    pub activates_at: i64,
+}
+
+#[event]
+pub struct ParamChangeActivated {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub key: ParamKey,
+    pub value: ParamValue,
+}
+
+#[event]
+pub struct FeeCollected {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct StakeLocked {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub unlock_at: i64,
+}
+
+#[event]
+pub struct LockExtended {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub unlock_at: i64,
+}
+
+#[event]
+pub struct StakeRelocked {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub unlock_at: i64,
+}
+
+#[event]
+pub struct StakeUnlocked {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct GaugeVoted {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub gauge: Pubkey,
+    pub voter: Pubkey,
+    pub weight_bps: u64,
+    /// Votes the allocation now contributes, and the gauge's resulting live weight.
+    pub weight: u64,
+    pub gauge_weight: u64,
+}
+
+#[event]
+pub struct GaugeVoteKicked {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub gauge: Pubkey,
+    pub vote_escrow: Pubkey,
+    /// Votes the allocation contributed before and after, and the gauge's resulting live weight.
+    pub previous_weight: u64,
+    pub weight: u64,
+    pub gauge_weight: u64,
+}
+
+#[event]
+pub struct GaugeRateApplied {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub week: i64,
+    pub gauge_weight: u64,
+    pub total_weight: u64,
+    pub reward_per_second: u64,
+}
+
+#[event]
+pub struct StakeTranchePending {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    /// The stake's whole pending tranche, including earlier deposits.
+    pub total_pending: u64,
+}
+
+#[event]
+pub struct PendingTrancheActivated {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct DepositQueued {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub id: u64,
+    pub amount: u64,
+    /// Base tokens waiting in the whole queue.
+    pub total_queued: u64,
+}
+
+#[event]
+pub struct PendingDepositAdmitted {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub id: u64,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PendingDepositCancelled {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub user: Pubkey,
+    pub id: u64,
+    pub mint: Pubkey,
+    /// Tokens of `mint` returned to the depositor.
+    pub refunded: u64,
+}
+
+#[event]
+pub struct LendingPnlReported {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub pnl: i64,
+    pub lending_staked: u64,
+    pub lending_pnl_index: i128,
+}
+
+#[event]
+pub struct ProtocolStakeChanged {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub delta: i64,
+    pub protocol_stake: u64,
+}
+
+#[event]
+pub struct PositionOpened {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub owner: Pubkey,
+    pub collateral: u64,
+    pub size: i64,
+    pub entry_price: u64,
+    pub liquidation_price: u64,
+}
+
+#[event]
+pub struct TpSlSet {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub owner: Pubkey,
+    pub take_profit_price: u64,
+    pub stop_loss_price: u64,
+}
+
+#[event]
+pub struct TriggerExecuted {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub owner: Pubkey,
+    pub executor: Pubkey,
+    pub mark_price: u64,
+    /// Trader PnL realized on the close.
+    pub realized_pnl: i64,
+    pub collateral_returned: u64,
+    pub tip: u64,
+}
+
+#[event]
+pub struct MarginCalled {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub owner: Pubkey,
+    pub deadline: i64,
+    pub liquidation_price: u64,
+}
+
+#[event]
+pub struct MarginAdjusted {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub owner: Pubkey,
+    /// Positive when margin was added, negative when removed.
+    pub delta: i64,
+    pub collateral: u64,
+    pub liquidation_price: u64,
+}
+
+#[event]
+pub struct FeeSweepProposed {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub fee_vault: Pubkey,
+    pub proposer: Pubkey,
+    pub amount: u64,
+    pub executable_at: i64,
+}
+
+#[event]
+pub struct FeeSweepCancelled {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub fee_vault: Pubkey,
+    pub proposer: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct FeesSwept {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub mint: Pubkey,
+    pub collector: Pubkey,
+    pub amount: u64,
+    /// Whether the state owner co-signed, as opposed to a small or pre-approved sweep.
+    pub co_signed: bool,
+}
+
+#[event]
+pub struct TokensRescued {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub mint: Pubkey,
+    pub source: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct TradeIntentPosted {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub id: u64,
+    pub manager: Pubkey,
+    pub venue: HedgeVenue,
+    pub side: TradeSide,
+    pub size: u64,
+    pub max_slippage_bps: u64,
+    pub executable_at: i64,
+}
+
+#[event]
+pub struct TradeIntentVetoed {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub id: u64,
+    pub guardian: Pubkey,
+}
+
+#[event]
+pub struct TradeIntentExecuted {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub id: u64,
+    pub venue: HedgeVenue,
+    pub side: TradeSide,
+    pub size: u64,
+    pub mark_price: u64,
+}
+
+#[event]
+pub struct BuybackBurned {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub revenue_mint: Pubkey,
+    pub amount_in: u64,
+    pub burned: u64,
+}
+
+#[event]
+pub struct FundingPublisherUpdated {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub publisher: Pubkey,
+    /// True when added to the allowlist, false when removed.
+    pub listed: bool,
+}
+
+#[event]
+pub struct FundingRateSubmitted {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub publisher: Pubkey,
+    pub rate: u64,
+}
+
+#[event]
+pub struct LedgerVerified {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub entry_count: u64,
+    pub total_posted: u64,
+}
+
+#[event]
+pub struct VestingCreated {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub beneficiary: Pubkey,
+    pub total: u64,
+    pub cliff_at: i64,
+    pub end_at: i64,
+}
+
+#[event]
+pub struct VestingReleased {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+}
+
+#[event]
+pub struct LeaderboardClosed {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub epoch: u64,
+    pub makers: u32,
+    pub bonus_paid: u64,
+}
+
+#[event]
+pub struct Discrepancy {
+    pub seq: u64,
+    pub state: Pubkey,
+    /// Book value and what the vault's token accounts actually hold, in base units.
+    pub expected: i64,
+    pub actual: i64,
+    /// Whether the shortfall exceeded the tolerance and paused the vault.
+    pub paused: bool,
+}
+
+#[event]
+pub struct PositionDeleveraged {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub owner: Pubkey,
+    pub size_closed: u64,
+    /// Trader PnL realized on the closed size.
+    pub realized_pnl: i64,
+    pub collateral_returned: u64,
+    /// Signed size left open.
+    pub size: i64,
+    pub liquidation_price: u64,
+}
+
+#[event]
+pub struct PositionAutoDeleveraged {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub owner: Pubkey,
+    pub size_closed: u64,
+    /// Trader PnL realized on the closed size.
+    pub realized_pnl: i64,
+    /// Signed size left open.
+    pub size: i64,
+    pub liquidation_price: u64,
+}
+
+#[event]
+pub struct PositionChunkLiquidated {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub owner: Pubkey,
+    pub liquidator: Pubkey,
+    pub size_closed: u64,
+    pub realized_pnl: i64,
+    pub size_remaining: i64,
+    /// Size closed so far in this liquidation.
+    pub liquidated_size: u64,
+    pub liquidation_price: u64,
+}
+
+#[event]
+pub struct PositionLiquidated {
+    pub seq: u64,
+    pub state: Pubkey,
+    pub owner: Pubkey,
+    pub liquidator: Pubkey,
+    pub collateral_seized: u64,
+}
+
 // -----------------------------------------------------------------------------
 // Error Codes
 // -----------------------------------------------------------------------------
@@ -482,4 +10276,192 @@ pub enum CustomError {
     EarlyUnstakeNotAllowed,
     #[msg("Not enough votes for the proposal.")]
     NotEnoughVotes,
+    #[msg("Address is not on the launch whitelist.")]
+    NotWhitelisted,
+    #[msg("Stake exceeds the per-wallet cap for the current launch phase.")]
+    ExceedsLaunchCap,
+    #[msg("Launch is already in the open phase.")]
+    LaunchAlreadyOpen,
+    #[msg("Merkle proof does not match the distribution root.")]
+    InvalidMerkleProof,
+    #[msg("Airdrop leaf has already been claimed.")]
+    AirdropAlreadyClaimed,
+    #[msg("Claim index is outside the distribution's bitmap.")]
+    ClaimIndexOutOfRange,
+    #[msg("Arithmetic overflow or underflow.")]
+    MathOverflow,
+    #[msg("Oracle data is stale.")]
+    StaleOracle,
+    #[msg("Vault is paused.")]
+    VaultPaused,
+    #[msg("Signer is not authorized for this action.")]
+    Unauthorized,
+    #[msg("Amount exceeds the configured cap.")]
+    ExceedsCap,
+    #[msg("Rewards have not passed the unclaimed expiry window.")]
+    RewardsNotExpired,
+    #[msg("Invalid parameter value.")]
+    InvalidParameter,
+    #[msg("Delta report is older than the allowed maximum age.")]
+    StaleDeltaReport,
+    #[msg("Vault net delta exceeds the allowed threshold.")]
+    DeltaThresholdExceeded,
+    #[msg("Vault net delta is within the allowed threshold; no rebalance needed.")]
+    DeltaWithinThreshold,
+    #[msg("Source and destination vaults must differ.")]
+    SameVault,
+    #[msg("Vaults do not share the same base mint.")]
+    VaultMintMismatch,
+    #[msg("Outflow limit for this epoch has been reached.")]
+    OutflowLimitReached,
+    #[msg("Position is healthy and cannot be liquidated.")]
+    PositionHealthy,
+    #[msg("Price moved beyond the caller's limit.")]
+    SlippageExceeded,
+    #[msg("Collateral type is not registered.")]
+    UnknownCollateral,
+    #[msg("Collateral type no longer accepts deposits.")]
+    CollateralDisabled,
+    #[msg("Token account mint does not match the collateral type.")]
+    CollateralMintMismatch,
+    #[msg("Collateral mint is already registered.")]
+    CollateralAlreadyRegistered,
+    #[msg("Position has already been margin called.")]
+    AlreadyMarginCalled,
+    #[msg("Position has not been margin called.")]
+    NotMarginCalled,
+    #[msg("Margin call grace period has not elapsed.")]
+    GracePeriodActive,
+    #[msg("Position does not hold enough collateral.")]
+    InsufficientCollateral,
+    #[msg("Action would leave the position unhealthy.")]
+    PositionUnhealthy,
+    #[msg("LST stake pool account is missing or invalid.")]
+    InvalidStakePool,
+    #[msg("Keeper is inactive; no rebalance or in-band delta report is recent enough.")]
+    KeeperInactive,
+    #[msg("No parameter change is pending.")]
+    NoPendingParamChange,
+    #[msg("Parameter change is still timelocked.")]
+    ParamTimelockActive,
+    #[msg("No quotes are live.")]
+    NoLiveQuotes,
+    #[msg("Address is blocked.")]
+    AddressBlocked,
+    #[msg("State owner does not match the vault state.")]
+    StateOwnerMismatch,
+    #[msg("Vault token account is not owned by the vault state.")]
+    InvalidVaultOwner,
+    #[msg("Vault token account holds the wrong mint.")]
+    InvalidVaultMint,
+    #[msg("Batch accounts do not match the entries.")]
+    InvalidBatchAccounts,
+    #[msg("Crank called before its minimum interval elapsed.")]
+    CrankTooSoon,
+    #[msg("Protocol first-loss capital is not exhausted.")]
+    InsuranceNotExhausted,
+    #[msg("Position is not the top auto-deleveraging candidate.")]
+    NotAdlCandidate,
+    #[msg("Already voted this epoch.")]
+    AlreadyVoted,
+    #[msg("Vote-escrow lock has not expired.")]
+    StakeStillLocked,
+    #[msg("Vault is not an approved delegate for this amount.")]
+    InsufficientDelegation,
+    #[msg("Leaderboard epoch has ended; close it first.")]
+    LeaderboardEpochOver,
+    #[msg("Leaderboard epoch is still running.")]
+    LeaderboardEpochActive,
+    #[msg("Boost campaign has ended.")]
+    BoostCampaignEnded,
+    #[msg("NFT is not a verified member of the campaign collection.")]
+    NftNotInCollection,
+    #[msg("Buyback-and-burn is disabled.")]
+    BuybackDisabled,
+    #[msg("Position leverage exceeds the initial margin limit.")]
+    LeverageTooHigh,
+    #[msg("Deposit exceeds the lifetime cap for the user's tier.")]
+    TierCapExceeded,
+    #[msg("Hedge program does not match the vault's configured venue.")]
+    InvalidHedgeVenue,
+    #[msg("No withdrawal is queued.")]
+    NoQueuedWithdrawal,
+    #[msg("Stablecoin collateral is outside its depeg band.")]
+    StablecoinDepegged,
+    #[msg("Vault is not registered in the global config.")]
+    VaultNotRegistered,
+    #[msg("Vault is already registered.")]
+    VaultAlreadyRegistered,
+    #[msg("The global vault registry is full.")]
+    VaultRegistryFull,
+    #[msg("Liquidator still has open jobs.")]
+    LiquidatorBusy,
+    #[msg("Liquidator bond is below the required amount.")]
+    InsufficientBond,
+    #[msg("Position is reserved by another liquidator's job.")]
+    LiquidationJobActive,
+    #[msg("Ledger does not balance or does not match the vault's holdings.")]
+    LedgerImbalance,
+    #[msg("Claim the queued withdrawal first.")]
+    QueuedWithdrawalPending,
+    #[msg("Not enough fresh funding submissions for a quorum.")]
+    FundingQuorumNotMet,
+    #[msg("Funding publisher allowlist is full.")]
+    FundingPublishersFull,
+    #[msg("Not enough epoch snapshots in the lookback window.")]
+    InsufficientSnapshots,
+    #[msg("Nothing has vested since the last release.")]
+    NothingVested,
+    #[msg("Stake index page is full.")]
+    StakeIndexPageFull,
+    #[msg("Claim exceeds pending rewards.")]
+    InsufficientRewards,
+    #[msg("Sweep above the threshold needs a co-signer or a matured proposal.")]
+    SweepNeedsCosign,
+    #[msg("Keeper nonce does not match the vault's current nonce.")]
+    KeeperNonceMismatch,
+    #[msg("Mark price has not reached the position's take-profit or stop-loss.")]
+    TriggerNotHit,
+    #[msg("Minting would exceed the vault's reward mint cap.")]
+    RewardMintCapExceeded,
+    #[msg("Stake already tracks the maximum number of deposited assets.")]
+    AssetSlotsFull,
+    #[msg("Trade intent is still inside its veto window.")]
+    TradeIntentPending,
+    #[msg("Stake is already open in a different risk bucket.")]
+    RiskBucketMismatch,
+    #[msg("Risk parameters are inconsistent with each other.")]
+    InconsistentRiskParams,
+    #[msg("Mock oracles are only available in mock-oracles builds.")]
+    MockOraclesDisabled,
+    #[msg("Lock has expired; relock it instead.")]
+    LockExpired,
+    #[msg("Pending tranche activates after the keeper next confirms the hedge.")]
+    TranchePending,
+    #[msg("Vote escrow already splits its power across the maximum number of gauges.")]
+    GaugeSlotsFull,
+    #[msg("Transaction also calls a DEX; submit it on its own.")]
+    DexSwapInTransaction,
+    #[msg("Large withdrawals must pass the instructions sysvar.")]
+    InstructionsSysvarRequired,
+    #[msg("Tokens of this mint back user funds or rewards and cannot be rescued.")]
+    RescueMintProtected,
+    #[msg("Program is not on the config's CPI whitelist.")]
+    ProgramNotWhitelisted,
+    #[msg("The program whitelist is full.")]
+    ProgramWhitelistFull,
+    #[msg("Pending rewards are not above the user's auto-claim threshold.")]
+    AutoClaimBelowThreshold,
+    #[msg("Price cache was not refreshed in this slot.")]
+    StalePriceCache,
+    #[msg("Deposit exceeds the strategy's capacity.")]
+    StrategyAtCapacity,
+    #[msg("A pending deposit account is required exactly when the deposit exceeds capacity.")]
+    PendingDepositRequired,
+    #[msg("This epoch's passed vote was already executed.")]
+    VoteAlreadyExecuted,
+    #[msg("Stake that voted this epoch cannot be transferred until the next one.")]
+    StakeVotedThisEpoch,
+    #[msg("Stake has deposits waiting in the deposit queue.")]
+    QueuedDepositPending,
 }
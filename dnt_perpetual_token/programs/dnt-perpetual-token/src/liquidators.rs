@@ -0,0 +1,300 @@
+// -----------------------------------------------------------------------------
+// Liquidator registry: liquidators post a SOL bond to register, then claim liquidation jobs on
+// first-come terms. A claimed position can only be liquidated by its claimant until the job
+// times out, after which anyone may liquidate it and anyone may slash the claimant's bond.
+// -----------------------------------------------------------------------------
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+
+use crate::{
+    next_event_seq, CustomError, LiquidationJobClaimed, LiquidatorSlashed, Position, State,
+    RESERVED_SPACE,
+};
+
+/// Bond a liquidator posts to register, in lamports.
+pub const LIQUIDATOR_BOND_LAMPORTS: u64 = 100_000_000;
+/// Slots a claimed job stays exclusive to its claimant before it can be slashed.
+pub const LIQUIDATION_JOB_TIMEOUT_SLOTS: u64 = 150;
+
+#[account]
+#[derive(InitSpace)]
+pub struct Liquidator {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub authority: Pubkey,
+    /// Bond held on this account on top of its rent-exempt balance.
+    pub bond: u64,
+    pub active_jobs: u32,
+    pub jobs_completed: u64,
+    pub jobs_failed: u64,
+    pub registered_at: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+/// A claim on liquidating `position`, at `[b"liquidation_job", position]`.
+#[account]
+#[derive(InitSpace)]
+pub struct LiquidationJob {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub position: Pubkey,
+    pub liquidator: Pubkey,
+    pub claimed_slot: u64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+impl LiquidationJob {
+    pub fn expired(&self, slot: u64) -> bool {
+        slot > self.claimed_slot.saturating_add(LIQUIDATION_JOB_TIMEOUT_SLOTS)
+    }
+}
+
+pub fn register(ctx: Context<RegisterLiquidator>) -> Result<()> {
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.authority.to_account_info(),
+                to: ctx.accounts.liquidator.to_account_info(),
+            },
+        ),
+        LIQUIDATOR_BOND_LAMPORTS,
+    )?;
+    let liquidator = &mut ctx.accounts.liquidator;
+    liquidator.bump = ctx.bumps.liquidator;
+    liquidator.state = ctx.accounts.state.key();
+    liquidator.authority = ctx.accounts.authority.key();
+    liquidator.bond = LIQUIDATOR_BOND_LAMPORTS;
+    liquidator.registered_at = Clock::get()?.unix_timestamp;
+    Ok(())
+}
+
+pub fn deregister(ctx: Context<DeregisterLiquidator>) -> Result<()> {
+    require!(ctx.accounts.liquidator.active_jobs == 0, CustomError::LiquidatorBusy);
+    Ok(())
+}
+
+pub fn claim_job(ctx: Context<ClaimLiquidationJob>) -> Result<()> {
+    let position = &ctx.accounts.position;
+    require!(position.margin_call_deadline != 0, CustomError::NotMarginCalled);
+    require!(
+        Clock::get()?.unix_timestamp >= position.margin_call_deadline,
+        CustomError::GracePeriodActive
+    );
+    let liquidator = &mut ctx.accounts.liquidator;
+    require!(liquidator.bond >= LIQUIDATOR_BOND_LAMPORTS, CustomError::InsufficientBond);
+    liquidator.active_jobs = liquidator
+        .active_jobs
+        .checked_add(1)
+        .ok_or(CustomError::MathOverflow)?;
+
+    let slot = Clock::get()?.slot;
+    let job = &mut ctx.accounts.job;
+    job.bump = ctx.bumps.job;
+    job.state = ctx.accounts.state.key();
+    job.position = position.key();
+    job.liquidator = ctx.accounts.authority.key();
+    job.claimed_slot = slot;
+
+    let seq = next_event_seq(&mut ctx.accounts.state)?;
+    emit!(LiquidationJobClaimed {
+        seq,
+        state: ctx.accounts.state.key(),
+        position: ctx.accounts.position.key(),
+        liquidator: ctx.accounts.authority.key(),
+        expires_at_slot: slot + LIQUIDATION_JOB_TIMEOUT_SLOTS,
+    });
+    Ok(())
+}
+
+/// Slashes the bond of a liquidator whose job timed out; the reporter receives it.
+pub fn slash(ctx: Context<SlashLiquidator>) -> Result<()> {
+    require!(ctx.accounts.job.expired(Clock::get()?.slot), CustomError::LiquidationJobActive);
+    let liquidator = &mut ctx.accounts.liquidator;
+    let slashed = liquidator.bond;
+    liquidator.bond = 0;
+    liquidator.active_jobs = liquidator.active_jobs.saturating_sub(1);
+    liquidator.jobs_failed = liquidator
+        .jobs_failed
+        .checked_add(1)
+        .ok_or(CustomError::MathOverflow)?;
+    move_lamports(
+        &liquidator.to_account_info(),
+        &ctx.accounts.reporter.to_account_info(),
+        slashed,
+    )?;
+
+    let seq = next_event_seq(&mut ctx.accounts.state)?;
+    emit!(LiquidatorSlashed {
+        seq,
+        state: ctx.accounts.state.key(),
+        liquidator: ctx.accounts.liquidator.authority,
+        position: ctx.accounts.job.position,
+        reporter: ctx.accounts.reporter.key(),
+        slashed,
+    });
+    Ok(())
+}
+
+/// Loads the job on a position, if one was claimed, and fails while it reserves the position for
+/// someone other than `liquidator`. Returns whether `liquidator` is the claimant.
+pub fn check_job(job_info: &AccountInfo, liquidator: &AccountInfo) -> Result<bool> {
+    if job_info.data_is_empty() {
+        return Ok(false);
+    }
+    require_keys_eq!(*job_info.owner, crate::ID, CustomError::InvalidParameter);
+    let job = LiquidationJob::try_deserialize(&mut &job_info.try_borrow_data()?[..])?;
+    if job.liquidator != liquidator.key() {
+        require!(job.expired(Clock::get()?.slot), CustomError::LiquidationJobActive);
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Enforces and settles the job on a position being liquidated. Without a job, anyone may
+/// liquidate. A live job reserves the position for its claimant, who closes it on success and
+/// gets its rent back; an expired job is left for `slash_liquidator`.
+pub fn settle_job<'info>(
+    job_info: &AccountInfo<'info>,
+    record: Option<&mut Account<'info, Liquidator>>,
+    liquidator: &AccountInfo<'info>,
+) -> Result<()> {
+    if !check_job(job_info, liquidator)? {
+        return Ok(());
+    }
+
+    let record = record.ok_or(CustomError::InvalidParameter)?;
+    require_keys_eq!(record.authority, liquidator.key(), CustomError::InvalidParameter);
+    record.active_jobs = record.active_jobs.saturating_sub(1);
+    record.jobs_completed = record
+        .jobs_completed
+        .checked_add(1)
+        .ok_or(CustomError::MathOverflow)?;
+    move_lamports(job_info, liquidator, job_info.lamports())?;
+    job_info.assign(&system_program::ID);
+    job_info.realloc(0, false)?;
+    Ok(())
+}
+
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    **from.try_borrow_mut_lamports()? = from
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(CustomError::MathOverflow)?;
+    **to.try_borrow_mut_lamports()? = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(CustomError::MathOverflow)?;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RegisterLiquidator<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        init,
+        seeds = [b"liquidator", state.key().as_ref(), authority.key().as_ref()],
+        bump,
+        payer = authority,
+        space = 8 + Liquidator::INIT_SPACE,
+    )]
+    pub liquidator: Account<'info, Liquidator>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DeregisterLiquidator<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"liquidator", state.key().as_ref(), authority.key().as_ref()],
+        bump = liquidator.bump,
+        close = authority,
+    )]
+    pub liquidator: Account<'info, Liquidator>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimLiquidationJob<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        seeds = [b"position", state.key().as_ref(), position.owner.as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        seeds = [b"liquidator", state.key().as_ref(), authority.key().as_ref()],
+        bump = liquidator.bump,
+    )]
+    pub liquidator: Account<'info, Liquidator>,
+    #[account(
+        init,
+        seeds = [b"liquidation_job", position.key().as_ref()],
+        bump,
+        payer = authority,
+        space = 8 + LiquidationJob::INIT_SPACE,
+    )]
+    pub job: Account<'info, LiquidationJob>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SlashLiquidator<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"liquidation_job", job.position.as_ref()],
+        bump = job.bump,
+        constraint = job.state == state.key() @ CustomError::InvalidParameter,
+        close = reporter,
+    )]
+    pub job: Account<'info, LiquidationJob>,
+    #[account(
+        mut,
+        seeds = [b"liquidator", state.key().as_ref(), job.liquidator.as_ref()],
+        bump = liquidator.bump,
+    )]
+    pub liquidator: Account<'info, Liquidator>,
+    #[account(mut)]
+    pub reporter: Signer<'info>,
+}
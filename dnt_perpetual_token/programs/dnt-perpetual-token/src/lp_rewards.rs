@@ -0,0 +1,295 @@
+// -----------------------------------------------------------------------------
+// LP rewards: `State::lp_emission_bps` of each reward emission is set aside for holders of a
+// configured AMM LP token (e.g. a DNT/USDC pool). Holders register a snapshot of their LP
+// balance and earn the set-aside rewards pro rata through a Q64.64 index, paid from the same
+// rewards pool as staker rewards. Anyone may refresh a holder's snapshot, so a holder who
+// withdraws liquidity cannot keep earning on a stale balance.
+// -----------------------------------------------------------------------------
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+
+use crate::fixed_point::{q64_from_ratio, q64_mul, Rounding};
+use crate::ledger::{self, Ledger, LedgerAccount};
+use crate::{
+    next_event_seq, transfer_from_vault, CustomError, LpRewardsClaimed, LpSnapshotUpdated, State,
+    RESERVED_SPACE,
+};
+
+#[account]
+#[derive(InitSpace)]
+pub struct LpIncentive {
+    pub bump: u8,
+    pub state: Pubkey,
+    /// LP token of the incentivized pool.
+    pub lp_mint: Pubkey,
+    /// Rewards per LP token unit over the program's lifetime, Q64.64.
+    pub reward_index: u128,
+    /// Sum of registered holders' balance snapshots.
+    pub total_snapshot: u64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+/// A registered LP holder, at `[b"lp_stake", state, owner]`.
+#[account]
+#[derive(InitSpace)]
+pub struct LpStake {
+    pub bump: u8,
+    pub owner: Pubkey,
+    /// LP balance as of the last snapshot; rewards accrue on this amount.
+    pub snapshot_balance: u64,
+    pub snapshot_at: i64,
+    pub reward_index_snapshot: u128,
+    pub pending_rewards: u64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+impl LpIncentive {
+    /// Folds rewards set aside since the last accrual into the index. With no registered
+    /// balance they stay set aside for later.
+    fn accrue(&mut self, state: &mut State) -> Result<()> {
+        if self.total_snapshot == 0 || state.lp_rewards_pending == 0 {
+            return Ok(());
+        }
+        let pending = state.lp_rewards_pending;
+        let index_delta = q64_from_ratio(pending, self.total_snapshot, Rounding::Down)
+            .ok_or(CustomError::MathOverflow)?;
+        self.reward_index = self
+            .reward_index
+            .checked_add(index_delta)
+            .ok_or(CustomError::MathOverflow)?;
+        state.lp_rewards_pending = 0;
+        Ok(())
+    }
+
+    /// Credits `lp_stake` with rewards earned on its snapshot since it last settled.
+    fn settle(&self, lp_stake: &mut LpStake) -> Result<()> {
+        let index_delta = self
+            .reward_index
+            .checked_sub(lp_stake.reward_index_snapshot)
+            .ok_or(CustomError::MathOverflow)?;
+        let accrued = q64_mul(index_delta, lp_stake.snapshot_balance, Rounding::Down)
+            .ok_or(CustomError::MathOverflow)?;
+        lp_stake.pending_rewards = lp_stake
+            .pending_rewards
+            .checked_add(accrued)
+            .ok_or(CustomError::MathOverflow)?;
+        lp_stake.reward_index_snapshot = self.reward_index;
+        Ok(())
+    }
+
+    /// Replaces `lp_stake`'s snapshot with `balance`, settling what it earned on the old one.
+    fn resnapshot(
+        &mut self,
+        state: &mut State,
+        lp_stake: &mut LpStake,
+        balance: u64,
+    ) -> Result<()> {
+        self.accrue(state)?;
+        self.settle(lp_stake)?;
+        self.total_snapshot = self
+            .total_snapshot
+            .checked_sub(lp_stake.snapshot_balance)
+            .and_then(|v| v.checked_add(balance))
+            .ok_or(CustomError::MathOverflow)?;
+        lp_stake.snapshot_balance = balance;
+        lp_stake.snapshot_at = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+}
+
+pub fn initialize(ctx: Context<InitializeLpIncentive>, lp_mint: Pubkey) -> Result<()> {
+    let incentive = &mut ctx.accounts.lp_incentive;
+    incentive.bump = ctx.bumps.lp_incentive;
+    incentive.state = ctx.accounts.state.key();
+    incentive.lp_mint = lp_mint;
+    Ok(())
+}
+
+pub fn register(ctx: Context<RegisterLp>) -> Result<()> {
+    let lp_stake = &mut ctx.accounts.lp_stake;
+    lp_stake.bump = ctx.bumps.lp_stake;
+    lp_stake.owner = ctx.accounts.owner.key();
+    lp_stake.reward_index_snapshot = ctx.accounts.lp_incentive.reward_index;
+    let balance = ctx.accounts.lp_token_account.amount;
+    snapshot(
+        &mut ctx.accounts.state,
+        &mut ctx.accounts.lp_incentive,
+        &mut ctx.accounts.lp_stake,
+        balance,
+    )
+}
+
+pub fn refresh(ctx: Context<RefreshLpSnapshot>) -> Result<()> {
+    let balance = ctx.accounts.lp_token_account.amount;
+    snapshot(
+        &mut ctx.accounts.state,
+        &mut ctx.accounts.lp_incentive,
+        &mut ctx.accounts.lp_stake,
+        balance,
+    )
+}
+
+fn snapshot(
+    state: &mut Account<State>,
+    incentive: &mut LpIncentive,
+    lp_stake: &mut LpStake,
+    balance: u64,
+) -> Result<()> {
+    incentive.resnapshot(state, lp_stake, balance)?;
+    let seq = next_event_seq(state)?;
+    emit!(LpSnapshotUpdated {
+        seq,
+        state: state.key(),
+        owner: lp_stake.owner,
+        balance,
+        total_snapshot: incentive.total_snapshot,
+    });
+    Ok(())
+}
+
+pub fn claim(ctx: Context<ClaimLpRewards>) -> Result<()> {
+    let incentive = &mut ctx.accounts.lp_incentive;
+    incentive.accrue(&mut ctx.accounts.state)?;
+    incentive.settle(&mut ctx.accounts.lp_stake)?;
+    let amount = ctx.accounts.lp_stake.pending_rewards;
+    require!(amount > 0, CustomError::InsufficientRewards);
+    ctx.accounts.lp_stake.pending_rewards = 0;
+
+    transfer_from_vault(
+        &ctx.accounts.state,
+        &ctx.accounts.state_owner,
+        &ctx.accounts.rewards_account,
+        &ctx.accounts.reward_token_account,
+        &ctx.accounts.token_program,
+        amount,
+    )?;
+    ledger::post(
+        &ctx.accounts.ledger,
+        LedgerAccount::RewardsOwed,
+        LedgerAccount::RewardsPool,
+        amount,
+    )?;
+    let seq = next_event_seq(&mut ctx.accounts.state)?;
+    emit!(LpRewardsClaimed {
+        seq,
+        state: ctx.accounts.state.key(),
+        owner: ctx.accounts.owner.key(),
+        amount,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeLpIncentive<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        seeds = [b"lp_incentive", state.key().as_ref()],
+        bump,
+        payer = state_owner,
+        space = 8 + LpIncentive::INIT_SPACE,
+    )]
+    pub lp_incentive: Account<'info, LpIncentive>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterLp<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(mut, seeds = [b"lp_incentive", state.key().as_ref()], bump = lp_incentive.bump)]
+    pub lp_incentive: Account<'info, LpIncentive>,
+    #[account(
+        init,
+        seeds = [b"lp_stake", state.key().as_ref(), owner.key().as_ref()],
+        bump,
+        payer = owner,
+        space = 8 + LpStake::INIT_SPACE,
+    )]
+    pub lp_stake: Account<'info, LpStake>,
+    #[account(
+        constraint = lp_token_account.owner == owner.key() @ CustomError::Unauthorized,
+        constraint = lp_token_account.mint == lp_incentive.lp_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub lp_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshLpSnapshot<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(mut, seeds = [b"lp_incentive", state.key().as_ref()], bump = lp_incentive.bump)]
+    pub lp_incentive: Account<'info, LpIncentive>,
+    #[account(
+        mut,
+        seeds = [b"lp_stake", state.key().as_ref(), lp_stake.owner.as_ref()],
+        bump = lp_stake.bump,
+    )]
+    pub lp_stake: Account<'info, LpStake>,
+    #[account(
+        constraint = lp_token_account.owner == lp_stake.owner @ CustomError::Unauthorized,
+        constraint = lp_token_account.mint == lp_incentive.lp_mint @ CustomError::InvalidVaultMint,
+    )]
+    pub lp_token_account: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimLpRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(mut, seeds = [b"lp_incentive", state.key().as_ref()], bump = lp_incentive.bump)]
+    pub lp_incentive: Account<'info, LpIncentive>,
+    #[account(
+        mut,
+        seeds = [b"lp_stake", state.key().as_ref(), owner.key().as_ref()],
+        bump = lp_stake.bump,
+    )]
+    pub lp_stake: Account<'info, LpStake>,
+    pub owner: Signer<'info>,
+    #[account(mut, seeds = [b"ledger", state.key().as_ref()], bump = ledger.load()?.bump)]
+    pub ledger: AccountLoader<'info, Ledger>,
+    // The rewards pool claims are paid from.
+    #[account(mut, constraint = rewards_account.owner == state.key())]
+    pub rewards_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = reward_token_account.owner == owner.key(),
+        constraint = reward_token_account.mint == rewards_account.mint,
+    )]
+    pub reward_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
@@ -0,0 +1,226 @@
+// -----------------------------------------------------------------------------
+// Market-making strategy: vault funds quote both sides of an Openbook v2 or Phoenix
+// market. The keeper places and cancels resting quotes; realized spread PnL is
+// settled into the strategy account and routed through `distribute_arbitrage_profits`.
+// -----------------------------------------------------------------------------
+
+use anchor_lang::prelude::*;
+
+use crate::{Config, CustomError, PnlBucket, State, StrategyKind, RESERVED_SPACE};
+
+pub const OPENBOOK_V2_PROGRAM_ID: Pubkey = pubkey!("opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb");
+pub const PHOENIX_PROGRAM_ID: Pubkey = pubkey!("PhoeNiXZ8ByJGLkxNfZRnkUfjvmuYqLR89jjFHGqdXY");
+
+/// Order book venue a strategy quotes on.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum QuoteVenue {
+    OpenbookV2,
+    Phoenix,
+}
+
+impl QuoteVenue {
+    pub fn program_id(self) -> Pubkey {
+        match self {
+            QuoteVenue::OpenbookV2 => OPENBOOK_V2_PROGRAM_ID,
+            QuoteVenue::Phoenix => PHOENIX_PROGRAM_ID,
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MarketMakingStrategy {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub venue: QuoteVenue,
+    pub market: Pubkey,
+    /// Resting quote prices, scaled by PRICE_PRECISION, and size per side in base units.
+    pub bid_price: u64,
+    pub ask_price: u64,
+    pub quote_size: u64,
+    pub quotes_live: bool,
+    /// Spread PnL settled from fills but not yet routed to rewards.
+    pub pending_spread_pnl: i64,
+    pub lifetime_spread_pnl: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+impl MarketMakingStrategy {
+    /// Takes the positive part of the pending spread PnL for distribution; losses stay pending and
+    /// net against future fills.
+    pub fn take_distributable_pnl(&mut self) -> u64 {
+        if self.pending_spread_pnl <= 0 {
+            return 0;
+        }
+        let amount = self.pending_spread_pnl as u64;
+        self.pending_spread_pnl = 0;
+        amount
+    }
+}
+
+pub fn initialize(
+    ctx: Context<InitializeMarketMaking>,
+    venue: QuoteVenue,
+    market: Pubkey,
+) -> Result<()> {
+    let strategy = &mut ctx.accounts.strategy;
+    strategy.bump = ctx.bumps.strategy;
+    strategy.state = ctx.accounts.state.key();
+    strategy.venue = venue;
+    strategy.market = market;
+    strategy.quotes_live = false;
+    strategy.pending_spread_pnl = 0;
+    strategy.lifetime_spread_pnl = 0;
+    Ok(())
+}
+
+pub fn place_quotes(
+    ctx: Context<ManageQuotes>,
+    bid_price: u64,
+    ask_price: u64,
+    quote_size: u64,
+) -> Result<()> {
+    require!(
+        bid_price > 0 && bid_price < ask_price && quote_size > 0,
+        CustomError::InvalidParameter
+    );
+    require!(!ctx.accounts.state.is_paused(), CustomError::VaultPaused);
+    // Replace any resting quotes rather than stacking them.
+    if ctx.accounts.strategy.quotes_live {
+        cancel_venue_quotes(&ctx)?;
+    }
+    submit_venue_quotes(&ctx, bid_price, ask_price, quote_size)?;
+
+    let strategy = &mut ctx.accounts.strategy;
+    strategy.bid_price = bid_price;
+    strategy.ask_price = ask_price;
+    strategy.quote_size = quote_size;
+    strategy.quotes_live = true;
+    Ok(())
+}
+
+pub fn cancel_quotes(ctx: Context<ManageQuotes>) -> Result<()> {
+    require!(ctx.accounts.strategy.quotes_live, CustomError::NoLiveQuotes);
+    cancel_venue_quotes(&ctx)?;
+    ctx.accounts.strategy.quotes_live = false;
+    Ok(())
+}
+
+pub fn settle_fills(ctx: Context<ManageQuotes>) -> Result<()> {
+    let fills = settle_venue_fills(&ctx)?;
+    let spread_pnl = fills.spread_pnl;
+    let bucket = &mut ctx.accounts.pnl_bucket;
+    bucket.spread_pnl = bucket
+        .spread_pnl
+        .checked_add(fills.spread_pnl)
+        .ok_or(CustomError::MathOverflow)?;
+    bucket.maker_rebates = bucket
+        .maker_rebates
+        .checked_add(fills.maker_rebates)
+        .ok_or(CustomError::MathOverflow)?;
+    bucket.fees_paid = bucket
+        .fees_paid
+        .checked_add(fills.fees_paid)
+        .ok_or(CustomError::MathOverflow)?;
+    bucket.last_update = Clock::get()?.unix_timestamp;
+
+    let strategy = &mut ctx.accounts.strategy;
+    strategy.pending_spread_pnl = strategy
+        .pending_spread_pnl
+        .checked_add(spread_pnl)
+        .ok_or(CustomError::MathOverflow)?;
+    strategy.lifetime_spread_pnl = strategy
+        .lifetime_spread_pnl
+        .checked_add(spread_pnl)
+        .ok_or(CustomError::MathOverflow)?;
+    Ok(())
+}
+
+fn submit_venue_quotes(
+    _ctx: &Context<ManageQuotes>,
+    _bid_price: u64,
+    _ask_price: u64,
+    _quote_size: u64,
+) -> Result<()> {
+    // Placeholder: CPI into the venue to post a post-only bid and ask, signed by the state PDA.
+    Ok(())
+}
+
+fn cancel_venue_quotes(_ctx: &Context<ManageQuotes>) -> Result<()> {
+    // Placeholder: CPI into the venue to cancel all of the vault's open orders on the market.
+    Ok(())
+}
+
+/// Realized results of settling filled quotes.
+struct VenueFills {
+    /// Spread captured net of rebates and fees.
+    spread_pnl: i64,
+    maker_rebates: i64,
+    fees_paid: i64,
+}
+
+fn settle_venue_fills(_ctx: &Context<ManageQuotes>) -> Result<VenueFills> {
+    // Placeholder: CPI to settle filled orders back to the vault and report what they realized.
+    Ok(VenueFills { spread_pnl: 0, maker_rebates: 0, fees_paid: 0 })
+}
+
+#[derive(Accounts)]
+pub struct InitializeMarketMaking<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        seeds = [b"market_making", state.key().as_ref()],
+        bump,
+        payer = state_owner,
+        space = 8 + MarketMakingStrategy::INIT_SPACE,
+    )]
+    pub strategy: Account<'info, MarketMakingStrategy>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageQuotes<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"market_making", state.key().as_ref()],
+        bump = strategy.bump,
+    )]
+    pub strategy: Account<'info, MarketMakingStrategy>,
+    #[account(
+        mut,
+        seeds = [b"pnl_bucket", state.key().as_ref(), &[StrategyKind::MarketMaking as u8]],
+        bump = pnl_bucket.bump,
+    )]
+    pub pnl_bucket: Account<'info, PnlBucket>,
+    #[account(constraint = keeper.key() == state.keeper @ CustomError::Unauthorized)]
+    pub keeper: Signer<'info>,
+    /// CHECK: Order book market; must match the strategy's configured market.
+    #[account(mut, address = strategy.market)]
+    pub market: UncheckedAccount<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    /// CHECK: Venue program; must match the strategy's configured venue and be whitelisted.
+    #[account(
+        address = strategy.venue.program_id(),
+        constraint = config.is_whitelisted(venue_program.key())
+            @ CustomError::ProgramNotWhitelisted,
+    )]
+    pub venue_program: UncheckedAccount<'info>,
+}
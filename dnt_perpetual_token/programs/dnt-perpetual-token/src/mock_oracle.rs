@@ -0,0 +1,105 @@
+// -----------------------------------------------------------------------------
+// Mock oracles: devnet and integration-test builds (`--features mock-oracles`) get a per-vault
+// `MockFeed` the state owner sets directly. Margin and liquidation checks read its mark price
+// when the feed is passed as a remaining account, and a mock funding rate accrues straight into
+// the funding index, so those flows run without live Pyth data or funding publishers. Anchor
+// cannot compile instructions out, so other builds keep them in the interface but reject them
+// and never read the feed. Never enable the feature for a mainnet build.
+// -----------------------------------------------------------------------------
+
+use anchor_lang::prelude::*;
+
+use crate::{accrue_funding, CustomError, State, RESERVED_SPACE};
+
+/// Owner-set prices for one vault, at `[b"mock_feed", state]`.
+#[account]
+#[derive(InitSpace)]
+pub struct MockFeed {
+    pub bump: u8,
+    pub state: Pubkey,
+    /// Perp mark price, scaled by PRICE_PRECISION; zero leaves the live price in place.
+    pub mark_price: u64,
+    /// Last funding rate accrued through `set_mock_funding`, in bps per FUNDING_INTERVAL.
+    pub funding_rate: u64,
+    pub updated_at: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+/// Mark price from `state`'s mock feed, if one is among `accounts` and has a price set.
+#[cfg(feature = "mock-oracles")]
+pub fn mark_price(state: &Pubkey, accounts: &[AccountInfo]) -> Result<Option<u64>> {
+    let (feed_key, _) = Pubkey::find_program_address(&[b"mock_feed", state.as_ref()], &crate::ID);
+    let Some(info) = accounts.iter().find(|a| a.key() == feed_key) else {
+        return Ok(None);
+    };
+    require_keys_eq!(*info.owner, crate::ID, CustomError::Unauthorized);
+    let feed = MockFeed::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    Ok((feed.mark_price > 0).then_some(feed.mark_price))
+}
+
+fn require_enabled() -> Result<()> {
+    require!(cfg!(feature = "mock-oracles"), CustomError::MockOraclesDisabled);
+    Ok(())
+}
+
+pub fn initialize(ctx: Context<InitializeMockFeed>) -> Result<()> {
+    require_enabled()?;
+    let feed = &mut ctx.accounts.mock_feed;
+    feed.bump = ctx.bumps.mock_feed;
+    feed.state = ctx.accounts.state.key();
+    Ok(())
+}
+
+pub fn set_price(ctx: Context<SetMockFeed>, mark_price: u64) -> Result<()> {
+    require_enabled()?;
+    let feed = &mut ctx.accounts.mock_feed;
+    feed.mark_price = mark_price;
+    feed.updated_at = Clock::get()?.unix_timestamp;
+    Ok(())
+}
+
+pub fn set_funding(ctx: Context<SetMockFeed>, rate: u64) -> Result<()> {
+    require_enabled()?;
+    let now = Clock::get()?.unix_timestamp;
+    accrue_funding(&mut ctx.accounts.state, rate, now)?;
+    let feed = &mut ctx.accounts.mock_feed;
+    feed.funding_rate = rate;
+    feed.updated_at = now;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeMockFeed<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        seeds = [b"mock_feed", state.key().as_ref()],
+        bump,
+        payer = state_owner,
+        space = 8 + MockFeed::INIT_SPACE,
+    )]
+    pub mock_feed: Account<'info, MockFeed>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetMockFeed<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(mut, seeds = [b"mock_feed", state.key().as_ref()], bump = mock_feed.bump)]
+    pub mock_feed: Account<'info, MockFeed>,
+    pub state_owner: Signer<'info>,
+}
@@ -0,0 +1,151 @@
+// -----------------------------------------------------------------------------
+// Price cache: validating the perp mark and every collateral feed in each instruction is
+// compute-heavy, so the permissionless `refresh_prices` crank reads them once per slot into a
+// per-vault `PriceCache`. Stake and liquidation instructions given the cache as a remaining
+// account price from it instead, and only while it was refreshed in the current slot.
+// -----------------------------------------------------------------------------
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    get_conversion_rate, get_mark_price, next_event_seq, CollateralRegistry, CustomError,
+    PricesRefreshed, State, RESERVED_SPACE,
+};
+
+/// Collateral rates a `PriceCache` can hold.
+pub const MAX_CACHED_RATES: usize = 16;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct CachedRate {
+    pub mint: Pubkey,
+    /// Value of one collateral unit in base units, scaled by PRICE_PRECISION.
+    pub rate: u64,
+}
+
+/// Prices read in `slot`, at `[b"price_cache", state]`.
+#[account]
+#[derive(InitSpace)]
+pub struct PriceCache {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub slot: u64,
+    /// Perp mark price, scaled by PRICE_PRECISION.
+    pub mark_price: u64,
+    /// One entry per registered collateral, in registry order.
+    #[max_len(MAX_CACHED_RATES)]
+    pub rates: Vec<CachedRate>,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+impl PriceCache {
+    /// Cached conversion rate for `mint`.
+    pub fn conversion_rate(&self, mint: Pubkey) -> Result<u64> {
+        self.rates
+            .iter()
+            .find(|r| r.mint == mint)
+            .map(|r| r.rate)
+            .ok_or(CustomError::UnknownCollateral.into())
+    }
+}
+
+/// `state`'s price cache, if it is among `accounts`. Fails unless it was refreshed this slot.
+pub fn load(state: &Pubkey, accounts: &[AccountInfo]) -> Result<Option<PriceCache>> {
+    let (cache_key, _) =
+        Pubkey::find_program_address(&[b"price_cache", state.as_ref()], &crate::ID);
+    let Some(info) = accounts.iter().find(|a| a.key() == cache_key) else {
+        return Ok(None);
+    };
+    require_keys_eq!(*info.owner, crate::ID, CustomError::Unauthorized);
+    let cache = PriceCache::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    require!(cache.slot == Clock::get()?.slot, CustomError::StalePriceCache);
+    Ok(Some(cache))
+}
+
+pub fn initialize(ctx: Context<InitializePriceCache>) -> Result<()> {
+    let cache = &mut ctx.accounts.price_cache;
+    cache.bump = ctx.bumps.price_cache;
+    cache.state = ctx.accounts.state.key();
+    Ok(())
+}
+
+pub fn refresh(ctx: Context<RefreshPrices>) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    if ctx.accounts.price_cache.slot == slot {
+        // Already read this slot.
+        return Ok(());
+    }
+    let collaterals = &ctx.accounts.collateral_registry.collaterals;
+    require!(collaterals.len() <= MAX_CACHED_RATES, CustomError::InvalidParameter);
+    let mut stake_pools = ctx.remaining_accounts.iter();
+    let mut rates = Vec::with_capacity(collaterals.len());
+    for config in collaterals {
+        let stake_pool = if config.lst_stake_pool == Pubkey::default() {
+            None
+        } else {
+            Some(stake_pools.next().ok_or(CustomError::InvalidBatchAccounts)?)
+        };
+        rates.push(CachedRate {
+            mint: config.mint,
+            rate: get_conversion_rate(config, stake_pool)?,
+        });
+    }
+    require!(stake_pools.next().is_none(), CustomError::InvalidBatchAccounts);
+
+    let cache = &mut ctx.accounts.price_cache;
+    cache.slot = slot;
+    cache.mark_price = get_mark_price()?;
+    cache.rates = rates;
+
+    let seq = next_event_seq(&mut ctx.accounts.state)?;
+    emit!(PricesRefreshed {
+        seq,
+        state: ctx.accounts.state.key(),
+        slot,
+        mark_price: ctx.accounts.price_cache.mark_price,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializePriceCache<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        init,
+        seeds = [b"price_cache", state.key().as_ref()],
+        bump,
+        payer = payer,
+        space = 8 + PriceCache::INIT_SPACE,
+    )]
+    pub price_cache: Account<'info, PriceCache>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshPrices<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        seeds = [b"collateral_registry", state.key().as_ref()],
+        bump = collateral_registry.bump,
+    )]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+    #[account(mut, seeds = [b"price_cache", state.key().as_ref()], bump = price_cache.bump)]
+    pub price_cache: Account<'info, PriceCache>,
+}
@@ -0,0 +1,143 @@
+// -----------------------------------------------------------------------------
+// Program whitelist: every hedge venue or order book program the vault CPIs into must be listed
+// in `Config::whitelisted_programs`, so a compromised keeper cannot route vault funds through an
+// arbitrary program. The config admin adds or removes a program by queueing a
+// `ProgramWhitelistChange`, which anyone may apply once PARAM_TIMELOCK_SECS has passed.
+// -----------------------------------------------------------------------------
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    Config, CustomError, DRIFT_PROGRAM_ID, MANGO_V4_PROGRAM_ID, OPENBOOK_V2_PROGRAM_ID,
+    PARAM_TIMELOCK_SECS, PHOENIX_PROGRAM_ID, RESERVED_SPACE, ZETA_PROGRAM_ID,
+};
+
+/// Programs the config whitelist can hold.
+pub const MAX_WHITELISTED_PROGRAMS: usize = 16;
+
+/// Built-in hedge and quoting venues, whitelisted when the config is created.
+pub const DEFAULT_WHITELISTED_PROGRAMS: [Pubkey; 5] = [
+    DRIFT_PROGRAM_ID,
+    MANGO_V4_PROGRAM_ID,
+    ZETA_PROGRAM_ID,
+    OPENBOOK_V2_PROGRAM_ID,
+    PHOENIX_PROGRAM_ID,
+];
+
+/// A queued whitelist addition or removal, at `[b"whitelist_change", program]`.
+#[account]
+#[derive(InitSpace)]
+pub struct ProgramWhitelistChange {
+    pub bump: u8,
+    pub program: Pubkey,
+    /// Adds `program` when set, removes it otherwise.
+    pub add: bool,
+    pub executable_at: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+impl Config {
+    pub fn is_whitelisted(&self, program: Pubkey) -> bool {
+        self.whitelisted_programs.contains(&program)
+    }
+}
+
+pub fn propose(ctx: Context<ProposeWhitelistChange>, program: Pubkey, add: bool) -> Result<()> {
+    let change = &mut ctx.accounts.change;
+    change.bump = ctx.bumps.change;
+    change.program = program;
+    change.add = add;
+    change.executable_at = Clock::get()?
+        .unix_timestamp
+        .checked_add(PARAM_TIMELOCK_SECS)
+        .ok_or(CustomError::MathOverflow)?;
+    Ok(())
+}
+
+pub fn apply(ctx: Context<ApplyWhitelistChange>) -> Result<()> {
+    let change = &ctx.accounts.change;
+    require!(
+        Clock::get()?.unix_timestamp >= change.executable_at,
+        CustomError::ParamTimelockActive
+    );
+    let whitelist = &mut ctx.accounts.config.whitelisted_programs;
+    let listed = whitelist.iter().position(|p| *p == change.program);
+    match (change.add, listed) {
+        (true, None) => {
+            require!(
+                whitelist.len() < MAX_WHITELISTED_PROGRAMS,
+                CustomError::ProgramWhitelistFull
+            );
+            whitelist.push(change.program);
+        }
+        (false, Some(index)) => {
+            whitelist.swap_remove(index);
+        }
+        // Already in the requested state.
+        _ => {}
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(program: Pubkey)]
+pub struct ProposeWhitelistChange<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ CustomError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        seeds = [b"whitelist_change", program.as_ref()],
+        bump,
+        payer = admin,
+        space = 8 + ProgramWhitelistChange::INIT_SPACE,
+    )]
+    pub change: Account<'info, ProgramWhitelistChange>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApplyWhitelistChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ CustomError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+    /// CHECK: Config admin; receives the change account's rent.
+    #[account(mut)]
+    pub admin: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"whitelist_change", change.program.as_ref()],
+        bump = change.bump,
+        close = admin,
+    )]
+    pub change: Account<'info, ProgramWhitelistChange>,
+}
+
+#[derive(Accounts)]
+pub struct CancelWhitelistChange<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ CustomError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"whitelist_change", change.program.as_ref()],
+        bump = change.bump,
+        close = admin,
+    )]
+    pub change: Account<'info, ProgramWhitelistChange>,
+}
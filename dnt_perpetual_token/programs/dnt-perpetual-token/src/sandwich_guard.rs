@@ -0,0 +1,56 @@
+// -----------------------------------------------------------------------------
+// Sandwich guard: rebalances and large withdrawals move the vault's base asset enough to be
+// worth sandwiching within one transaction. These instructions read the Instructions sysvar and
+// refuse to run alongside a top-level instruction to a known DEX. AMM swaps name pool vaults
+// rather than mints, so the traded asset cannot be told reliably and any DEX instruction counts.
+// -----------------------------------------------------------------------------
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions::load_instruction_at_checked;
+
+use crate::{CustomError, State, BPS_DENOMINATOR, OPENBOOK_V2_PROGRAM_ID, PHOENIX_PROGRAM_ID};
+
+pub const JUPITER_V6_PROGRAM_ID: Pubkey = pubkey!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+pub const ORCA_WHIRLPOOL_PROGRAM_ID: Pubkey =
+    pubkey!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+pub const RAYDIUM_AMM_V4_PROGRAM_ID: Pubkey =
+    pubkey!("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+pub const RAYDIUM_CLMM_PROGRAM_ID: Pubkey = pubkey!("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK");
+
+/// Programs whose instructions may not share a transaction with a guarded instruction.
+pub const GUARDED_DEX_PROGRAMS: [Pubkey; 6] = [
+    JUPITER_V6_PROGRAM_ID,
+    ORCA_WHIRLPOOL_PROGRAM_ID,
+    RAYDIUM_AMM_V4_PROGRAM_ID,
+    RAYDIUM_CLMM_PROGRAM_ID,
+    OPENBOOK_V2_PROGRAM_ID,
+    PHOENIX_PROGRAM_ID,
+];
+
+/// Withdrawals above this share of total stake, in bps, must pass the guard.
+pub const LARGE_UNSTAKE_BPS: u64 = 100;
+
+/// Fails if any top-level instruction in the transaction calls a guarded DEX. Swaps made by CPI
+/// from another program are not visible here.
+pub fn reject_dex_swaps(instructions_sysvar: &AccountInfo) -> Result<()> {
+    let count = {
+        let data = instructions_sysvar.try_borrow_data()?;
+        let bytes = data.get(..2).ok_or(CustomError::InvalidParameter)?;
+        u16::from_le_bytes([bytes[0], bytes[1]]) as usize
+    };
+    for index in 0..count {
+        let ix = load_instruction_at_checked(index, instructions_sysvar)?;
+        require!(
+            !GUARDED_DEX_PROGRAMS.contains(&ix.program_id),
+            CustomError::DexSwapInTransaction
+        );
+    }
+    Ok(())
+}
+
+/// True when withdrawing `amount` of the base asset, with `state` already debited, is large
+/// enough to require the guard.
+pub fn is_large_unstake(state: &State, amount: u64) -> bool {
+    let before = state.total_staked as u128 + amount as u128;
+    amount as u128 * BPS_DENOMINATOR as u128 > before * LARGE_UNSTAKE_BPS as u128
+}
@@ -0,0 +1,163 @@
+// -----------------------------------------------------------------------------
+// Stake index: every `UserStake` with a nonzero balance is listed in one of the vault's paged
+// zero-copy `StakeIndexPage` accounts, so distributions, ADL and snapshots can walk all stakers
+// on-chain instead of relying on getProgramAccounts. Every stake, unstake, transfer and
+// migration path takes the page and keeps the index current; `sync_stake_index` lets anyone fix
+// up a stake indexed before then.
+// -----------------------------------------------------------------------------
+
+use anchor_lang::prelude::*;
+
+use crate::{CustomError, State, UserStake, RESERVED_SPACE};
+
+/// `UserStake` addresses each index page holds.
+pub const STAKE_INDEX_PAGE_LEN: usize = 256;
+
+/// One page of the stake index, at `[b"stake_index", state, page]`. Entries `0..count` are live.
+#[account(zero_copy)]
+pub struct StakeIndexPage {
+    pub state: Pubkey,
+    pub page: u32,
+    pub count: u32,
+    pub bump: u8,
+    pub _padding: [u8; 7],
+    pub entries: [Pubkey; STAKE_INDEX_PAGE_LEN],
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+impl StakeIndexPage {
+    pub const SPACE: usize = 8 + std::mem::size_of::<StakeIndexPage>();
+
+    /// Live entries on this page.
+    pub fn stakes(&self) -> &[Pubkey] {
+        &self.entries[..self.count as usize]
+    }
+
+    fn insert(&mut self, user_stake: Pubkey) -> Result<()> {
+        require!(
+            (self.count as usize) < STAKE_INDEX_PAGE_LEN,
+            CustomError::StakeIndexPageFull
+        );
+        self.entries[self.count as usize] = user_stake;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn remove(&mut self, user_stake: Pubkey) -> Result<()> {
+        let index = self
+            .stakes()
+            .iter()
+            .position(|&k| k == user_stake)
+            .ok_or(CustomError::InvalidParameter)?;
+        self.count -= 1;
+        self.entries[index] = self.entries[self.count as usize];
+        self.entries[self.count as usize] = Pubkey::default();
+        Ok(())
+    }
+}
+
+/// Lists `user_stake` on `page` once it holds a balance, and delists it from its page once it
+/// is empty. A no-op when the index already reflects the stake.
+pub fn sync(
+    page: &AccountLoader<StakeIndexPage>,
+    user_stake: &mut UserStake,
+    user_stake_key: Pubkey,
+) -> Result<()> {
+    if user_stake.amount > 0 && !user_stake.indexed {
+        let mut page = page.load_mut()?;
+        page.insert(user_stake_key)?;
+        user_stake.indexed = true;
+        user_stake.index_page = page.page;
+    } else if user_stake.amount == 0 && user_stake.indexed {
+        let mut page = page.load_mut()?;
+        require!(page.page == user_stake.index_page, CustomError::InvalidParameter);
+        page.remove(user_stake_key)?;
+        user_stake.indexed = false;
+    }
+    Ok(())
+}
+
+/// `sync` against an index page passed as a remaining account, as batch instructions do.
+pub fn sync_page_account<'info>(
+    state: Pubkey,
+    page: &'info AccountInfo<'info>,
+    user_stake: &mut UserStake,
+    user_stake_key: Pubkey,
+) -> Result<()> {
+    let page = AccountLoader::<StakeIndexPage>::try_from(page)?;
+    require_keys_eq!(page.load()?.state, state, CustomError::InvalidBatchAccounts);
+    sync(&page, user_stake, user_stake_key)
+}
+
+pub fn add_page(ctx: Context<AddStakeIndexPage>) -> Result<()> {
+    let state = &mut ctx.accounts.state;
+    let mut page = ctx.accounts.stake_index.load_init()?;
+    page.state = state.key();
+    page.page = state.stake_index_pages;
+    page.bump = ctx.bumps.stake_index;
+    state.stake_index_pages = state
+        .stake_index_pages
+        .checked_add(1)
+        .ok_or(CustomError::MathOverflow)?;
+    Ok(())
+}
+
+pub fn sync_stake(ctx: Context<SyncStakeIndex>) -> Result<()> {
+    let key = ctx.accounts.user_stake.key();
+    sync(&ctx.accounts.stake_index, &mut ctx.accounts.user_stake, key)
+}
+
+#[derive(Accounts)]
+pub struct AddStakeIndexPage<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        init,
+        seeds = [
+            b"stake_index",
+            state.key().as_ref(),
+            &state.stake_index_pages.to_le_bytes(),
+        ],
+        bump,
+        payer = payer,
+        space = StakeIndexPage::SPACE,
+    )]
+    pub stake_index: AccountLoader<'info, StakeIndexPage>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SyncStakeIndex<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(mut, seeds = [b"user_stake", state.key().as_ref(), user.key().as_ref()], bump)]
+    pub user_stake: Account<'info, UserStake>,
+    /// CHECK: Staker whose position is indexed; only used to derive `user_stake`.
+    pub user: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"stake_index",
+            state.key().as_ref(),
+            &stake_index.load()?.page.to_le_bytes(),
+        ],
+        bump = stake_index.load()?.bump,
+    )]
+    pub stake_index: AccountLoader<'info, StakeIndexPage>,
+}
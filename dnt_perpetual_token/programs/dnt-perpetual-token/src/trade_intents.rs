@@ -0,0 +1,254 @@
+// -----------------------------------------------------------------------------
+// Trade intents: discretionary hedge trades by a strategy manager are posted on-chain first as
+// `TradeIntent` accounts (venue, side, size, max slippage). An intent becomes executable only
+// after TRADE_INTENT_DELAY_SECS, and the guardian can veto it until then, so every manual trade
+// is visible to stakers before it touches the book.
+// -----------------------------------------------------------------------------
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    get_mark_price, next_event_seq, Config, CustomError, HedgeVenue, Role, RoleAssignment, State,
+    TradeIntentExecuted, TradeIntentPosted, TradeIntentVetoed, BPS_DENOMINATOR, RESERVED_SPACE,
+};
+
+/// Delay between posting a trade intent and when it may be executed.
+pub const TRADE_INTENT_DELAY_SECS: i64 = 15 * 60;
+
+/// Direction of the perp trade.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+/// A pending discretionary hedge trade, at `[b"trade_intent", state, id]`.
+#[account]
+#[derive(InitSpace)]
+pub struct TradeIntent {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub id: u64,
+    pub manager: Pubkey,
+    pub venue: HedgeVenue,
+    pub side: TradeSide,
+    /// Perp size in base units.
+    pub size: u64,
+    /// Largest move of the mark away from `reference_price`, in bps, the trade may execute at.
+    pub max_slippage_bps: u64,
+    /// Mark price when the intent was posted, scaled by PRICE_PRECISION.
+    pub reference_price: u64,
+    pub executable_at: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+impl TradeIntent {
+    /// Signed size delta for the venue; negative sells.
+    fn size_delta(&self) -> Result<i64> {
+        let size = i64::try_from(self.size).map_err(|_| CustomError::MathOverflow)?;
+        Ok(match self.side {
+            TradeSide::Buy => size,
+            TradeSide::Sell => -size,
+        })
+    }
+}
+
+pub fn post(
+    ctx: Context<PostTradeIntent>,
+    venue: HedgeVenue,
+    side: TradeSide,
+    size: u64,
+    max_slippage_bps: u64,
+) -> Result<()> {
+    require!(
+        size > 0 && size <= i64::MAX as u64 && max_slippage_bps <= BPS_DENOMINATOR,
+        CustomError::InvalidParameter
+    );
+    let executable_at = Clock::get()?
+        .unix_timestamp
+        .checked_add(TRADE_INTENT_DELAY_SECS)
+        .ok_or(CustomError::MathOverflow)?;
+    let state = &mut ctx.accounts.state;
+    let id = state.trade_intent_count;
+    state.trade_intent_count = id.checked_add(1).ok_or(CustomError::MathOverflow)?;
+
+    let intent = &mut ctx.accounts.trade_intent;
+    intent.bump = ctx.bumps.trade_intent;
+    intent.state = state.key();
+    intent.id = id;
+    intent.manager = ctx.accounts.manager.key();
+    intent.venue = venue;
+    intent.side = side;
+    intent.size = size;
+    intent.max_slippage_bps = max_slippage_bps;
+    intent.reference_price = get_mark_price()?;
+    intent.executable_at = executable_at;
+
+    let seq = next_event_seq(state)?;
+    emit!(TradeIntentPosted {
+        seq,
+        state: ctx.accounts.state.key(),
+        id,
+        manager: ctx.accounts.manager.key(),
+        venue,
+        side,
+        size,
+        max_slippage_bps,
+        executable_at,
+    });
+    Ok(())
+}
+
+pub fn veto(ctx: Context<VetoTradeIntent>) -> Result<()> {
+    let seq = next_event_seq(&mut ctx.accounts.state)?;
+    emit!(TradeIntentVetoed {
+        seq,
+        state: ctx.accounts.state.key(),
+        id: ctx.accounts.trade_intent.id,
+        guardian: ctx.accounts.guardian.key(),
+    });
+    Ok(())
+}
+
+pub fn execute(ctx: Context<ExecuteTradeIntent>) -> Result<()> {
+    require!(!ctx.accounts.state.is_paused(), CustomError::VaultPaused);
+    let intent = &ctx.accounts.trade_intent;
+    require!(
+        Clock::get()?.unix_timestamp >= intent.executable_at,
+        CustomError::TradeIntentPending
+    );
+    let mark_price = get_mark_price()?;
+    let drift_bps = (mark_price.abs_diff(intent.reference_price) as u128)
+        .checked_mul(BPS_DENOMINATOR as u128)
+        .ok_or(CustomError::MathOverflow)?
+        / intent.reference_price.max(1) as u128;
+    require!(
+        drift_bps <= intent.max_slippage_bps as u128,
+        CustomError::SlippageExceeded
+    );
+
+    let hedge_program = ctx.accounts.hedge_program.to_account_info();
+    intent.venue.adjust_hedge(&hedge_program, intent.size_delta()?)?;
+
+    let (id, venue, side, size) = (intent.id, intent.venue, intent.side, intent.size);
+    let seq = next_event_seq(&mut ctx.accounts.state)?;
+    emit!(TradeIntentExecuted {
+        seq,
+        state: ctx.accounts.state.key(),
+        id,
+        venue,
+        side,
+        size,
+        mark_price,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct PostTradeIntent<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        seeds = [
+            b"role",
+            state.key().as_ref(),
+            &[Role::StrategyManager as u8],
+            manager.key().as_ref(),
+        ],
+        bump = role_assignment.bump,
+    )]
+    pub role_assignment: Account<'info, RoleAssignment>,
+    #[account(
+        init,
+        seeds = [
+            b"trade_intent",
+            state.key().as_ref(),
+            &state.trade_intent_count.to_le_bytes(),
+        ],
+        bump,
+        payer = manager,
+        space = 8 + TradeIntent::INIT_SPACE,
+    )]
+    pub trade_intent: Account<'info, TradeIntent>,
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VetoTradeIntent<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"trade_intent", state.key().as_ref(), &trade_intent.id.to_le_bytes()],
+        bump = trade_intent.bump,
+        has_one = manager @ CustomError::Unauthorized,
+        close = manager,
+    )]
+    pub trade_intent: Account<'info, TradeIntent>,
+    /// CHECK: Manager who posted the intent; receives its rent.
+    #[account(mut)]
+    pub manager: UncheckedAccount<'info>,
+    #[account(constraint = guardian.key() == state.guardian @ CustomError::Unauthorized)]
+    pub guardian: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteTradeIntent<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        seeds = [
+            b"role",
+            state.key().as_ref(),
+            &[Role::StrategyManager as u8],
+            manager.key().as_ref(),
+        ],
+        bump = role_assignment.bump,
+    )]
+    pub role_assignment: Account<'info, RoleAssignment>,
+    #[account(
+        mut,
+        seeds = [b"trade_intent", state.key().as_ref(), &trade_intent.id.to_le_bytes()],
+        bump = trade_intent.bump,
+        has_one = manager @ CustomError::Unauthorized,
+        close = manager,
+    )]
+    pub trade_intent: Account<'info, TradeIntent>,
+    #[account(mut)]
+    pub manager: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    /// CHECK: Program of the intent's venue; checked against `trade_intent.venue` and the config
+    /// whitelist.
+    #[account(
+        constraint = hedge_program.key() == trade_intent.venue.program_id()
+            @ CustomError::InvalidHedgeVenue,
+        constraint = config.is_whitelisted(hedge_program.key())
+            @ CustomError::ProgramNotWhitelisted,
+    )]
+    pub hedge_program: UncheckedAccount<'info>,
+}
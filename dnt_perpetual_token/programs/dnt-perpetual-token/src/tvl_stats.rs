@@ -0,0 +1,131 @@
+// -----------------------------------------------------------------------------
+// TVL stats: a single `TvlStats` account per vault holding each collateral's deposited total
+// and value, refreshed by the permissionless `mark_tvl` crank. Dashboards and TVL adapters read
+// this one account instead of walking every `UserStake` or the collateral registry.
+// -----------------------------------------------------------------------------
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    collateral_value, next_event_seq, CollateralRegistry, CustomError, State, TvlMarked,
+    RESERVED_SPACE,
+};
+
+/// Collateral types `TvlStats` can report.
+pub const MAX_TVL_ASSETS: usize = 16;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct AssetTvl {
+    pub mint: Pubkey,
+    /// Tokens deposited on behalf of stakers.
+    pub deposited_amount: u64,
+    /// Their base-asset value at the last mark.
+    pub usd_value: u64,
+}
+
+/// Per-asset TVL of a vault, at `[b"tvl_stats", state]`.
+#[account]
+#[derive(InitSpace)]
+pub struct TvlStats {
+    pub bump: u8,
+    pub state: Pubkey,
+    /// One entry per registered collateral, in registry order.
+    #[max_len(MAX_TVL_ASSETS)]
+    pub assets: Vec<AssetTvl>,
+    pub total_usd_value: u64,
+    /// `State::nav` at the last mark.
+    pub nav: u64,
+    pub marked_at: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+pub fn initialize(ctx: Context<InitializeTvlStats>) -> Result<()> {
+    let stats = &mut ctx.accounts.tvl_stats;
+    stats.bump = ctx.bumps.tvl_stats;
+    stats.state = ctx.accounts.state.key();
+    Ok(())
+}
+
+pub fn mark(ctx: Context<MarkTvl>) -> Result<()> {
+    let collaterals = &ctx.accounts.collateral_registry.collaterals;
+    require!(collaterals.len() <= MAX_TVL_ASSETS, CustomError::InvalidParameter);
+    let mut stake_pools = ctx.remaining_accounts.iter();
+    let mut assets = Vec::with_capacity(collaterals.len());
+    let mut total_usd_value: u64 = 0;
+    for config in collaterals {
+        let stake_pool = if config.lst_stake_pool == Pubkey::default() {
+            None
+        } else {
+            Some(stake_pools.next().ok_or(CustomError::InvalidBatchAccounts)?)
+        };
+        let usd_value = collateral_value(config, stake_pool)?;
+        total_usd_value = total_usd_value
+            .checked_add(usd_value)
+            .ok_or(CustomError::MathOverflow)?;
+        assets.push(AssetTvl {
+            mint: config.mint,
+            deposited_amount: config.deposited_amount,
+            usd_value,
+        });
+    }
+    require!(stake_pools.next().is_none(), CustomError::InvalidBatchAccounts);
+
+    let stats = &mut ctx.accounts.tvl_stats;
+    stats.assets = assets;
+    stats.total_usd_value = total_usd_value;
+    stats.nav = ctx.accounts.state.nav();
+    stats.marked_at = Clock::get()?.unix_timestamp;
+
+    let seq = next_event_seq(&mut ctx.accounts.state)?;
+    emit!(TvlMarked {
+        seq,
+        state: ctx.accounts.state.key(),
+        total_usd_value,
+        nav: ctx.accounts.tvl_stats.nav,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeTvlStats<'info> {
+    #[account(
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        init,
+        seeds = [b"tvl_stats", state.key().as_ref()],
+        bump,
+        payer = payer,
+        space = 8 + TvlStats::INIT_SPACE,
+    )]
+    pub tvl_stats: Account<'info, TvlStats>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MarkTvl<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        seeds = [b"collateral_registry", state.key().as_ref()],
+        bump = collateral_registry.bump,
+    )]
+    pub collateral_registry: Account<'info, CollateralRegistry>,
+    #[account(mut, seeds = [b"tvl_stats", state.key().as_ref()], bump = tvl_stats.bump)]
+    pub tvl_stats: Account<'info, TvlStats>,
+}
@@ -0,0 +1,185 @@
+// -----------------------------------------------------------------------------
+// Vesting: governance mints a team or treasury allocation of $DNT into an escrow held by the
+// state PDA. Nothing vests before the cliff; after it the allocation vests linearly from the
+// start over `duration` seconds, and the beneficiary releases whatever has vested so far.
+// -----------------------------------------------------------------------------
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::{
+    mint_rewards, next_event_seq, transfer_from_vault, CustomError, State, VestingCreated,
+    VestingReleased, RESERVED_SPACE,
+};
+
+/// A $DNT allocation vesting to `beneficiary`, at `[b"vesting", state, beneficiary]`.
+#[account]
+#[derive(InitSpace)]
+pub struct Vesting {
+    pub bump: u8,
+    pub state: Pubkey,
+    pub beneficiary: Pubkey,
+    pub total: u64,
+    pub released: u64,
+    pub start: i64,
+    /// Seconds after `start` before anything vests.
+    pub cliff: i64,
+    /// Seconds after `start` at which the full allocation has vested.
+    pub duration: i64,
+    /// Reserved for future fields.
+    pub _reserved: [u8; RESERVED_SPACE],
+}
+
+impl Vesting {
+    /// Amount vested at `now`, released or not.
+    pub fn vested(&self, now: i64) -> Result<u64> {
+        let elapsed = now.saturating_sub(self.start);
+        if elapsed < self.cliff {
+            return Ok(0);
+        }
+        if elapsed >= self.duration {
+            return Ok(self.total);
+        }
+        let vested = self.total as u128 * elapsed as u128 / self.duration as u128;
+        u64::try_from(vested).map_err(|_| error!(CustomError::MathOverflow))
+    }
+}
+
+pub fn create(
+    ctx: Context<CreateVesting>,
+    beneficiary: Pubkey,
+    total: u64,
+    cliff: i64,
+    duration: i64,
+) -> Result<()> {
+    require!(
+        total > 0 && duration > 0 && (0..=duration).contains(&cliff),
+        CustomError::InvalidParameter
+    );
+    let start = Clock::get()?.unix_timestamp;
+    let vesting = &mut ctx.accounts.vesting;
+    vesting.bump = ctx.bumps.vesting;
+    vesting.state = ctx.accounts.state.key();
+    vesting.beneficiary = beneficiary;
+    vesting.total = total;
+    vesting.released = 0;
+    vesting.start = start;
+    vesting.cliff = cliff;
+    vesting.duration = duration;
+
+    mint_rewards(
+        &mut ctx.accounts.state,
+        &ctx.accounts.state_owner.to_account_info(),
+        &ctx.accounts.token_mint,
+        &ctx.accounts.escrow,
+        &ctx.accounts.token_program,
+        total,
+    )?;
+
+    let seq = next_event_seq(&mut ctx.accounts.state)?;
+    emit!(VestingCreated {
+        seq,
+        state: ctx.accounts.state.key(),
+        beneficiary,
+        total,
+        cliff_at: start.saturating_add(cliff),
+        end_at: start.saturating_add(duration),
+    });
+    Ok(())
+}
+
+pub fn release(ctx: Context<ReleaseVested>) -> Result<()> {
+    let vesting = &mut ctx.accounts.vesting;
+    let amount = vesting
+        .vested(Clock::get()?.unix_timestamp)?
+        .checked_sub(vesting.released)
+        .ok_or(CustomError::MathOverflow)?;
+    require!(amount > 0, CustomError::NothingVested);
+    vesting.released = vesting.released.checked_add(amount).ok_or(CustomError::MathOverflow)?;
+
+    transfer_from_vault(
+        &ctx.accounts.state,
+        &ctx.accounts.state_owner,
+        &ctx.accounts.escrow,
+        &ctx.accounts.beneficiary_token_account,
+        &ctx.accounts.token_program,
+        amount,
+    )?;
+
+    let seq = next_event_seq(&mut ctx.accounts.state)?;
+    emit!(VestingReleased {
+        seq,
+        state: ctx.accounts.state.key(),
+        beneficiary: ctx.accounts.beneficiary.key(),
+        amount,
+        remaining: ctx.accounts.vesting.total - ctx.accounts.vesting.released,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(beneficiary: Pubkey)]
+pub struct CreateVesting<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        seeds = [b"vesting", state.key().as_ref(), beneficiary.as_ref()],
+        bump,
+        payer = state_owner,
+        space = 8 + Vesting::INIT_SPACE,
+    )]
+    pub vesting: Account<'info, Vesting>,
+    // $DNT, minted by the state PDA.
+    #[account(
+        mut,
+        constraint = token_mint.mint_authority == Some(state.key()).into() @ CustomError::Unauthorized,
+    )]
+    pub token_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        seeds = [b"vesting_escrow", vesting.key().as_ref()],
+        bump,
+        payer = state_owner,
+        token::mint = token_mint,
+        token::authority = state,
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub state_owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseVested<'info> {
+    #[account(
+        mut,
+        seeds = [b"state", state_owner.key().as_ref(), &state.vault_id.to_le_bytes()],
+        bump = state.bump,
+        has_one = state_owner @ CustomError::StateOwnerMismatch,
+    )]
+    pub state: Account<'info, State>,
+    /// CHECK: State owner; checked against `state.state_owner` via `has_one`.
+    pub state_owner: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"vesting", state.key().as_ref(), beneficiary.key().as_ref()],
+        bump = vesting.bump,
+    )]
+    pub vesting: Account<'info, Vesting>,
+    #[account(mut, seeds = [b"vesting_escrow", vesting.key().as_ref()], bump)]
+    pub escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = beneficiary_token_account.mint == escrow.mint @ CustomError::InvalidVaultMint,
+    )]
+    pub beneficiary_token_account: Account<'info, TokenAccount>,
+    pub beneficiary: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
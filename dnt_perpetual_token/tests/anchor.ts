@@ -9,26 +9,38 @@ describe("dnt_perpetual_token", () => {
   const provider = anchor.getProvider();
   const program = anchor.workspace.DntPerpetualToken as anchor.Program<DntPerpetualToken>;
 
-  it("Initializes the state", async () => {
-    // Derive the state PDA using seeds: "state" and payer's public key.
-    const [statePDA, bump] = await PublicKey.findProgramAddress(
-      [Buffer.from("state"), provider.publicKey.toBuffer()],
+  it("Creates a vault", async () => {
+    const vaultId = new BN(0);
+    // Wrapped SOL is always present on localnet, so it serves as the base mint here.
+    const baseMint = new PublicKey("So11111111111111111111111111111111111111112");
+
+    // Derive the state PDA using seeds: "state", payer's public key and the vault id.
+    const [statePDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("state"), provider.publicKey.toBuffer(), vaultId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [shareMintPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("share_mint"), statePDA.toBuffer()],
       program.programId
     );
 
-    // Call initialize with the proper accounts.
-    const tx = await program.methods.initialize()
+    const tx = await program.methods.createVault(vaultId, new BN(100))
       .accounts({
         state: statePDA,
+        baseMint,
+        shareMint: shareMintPDA,
         payer: provider.publicKey,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
       .rpc();
-    console.log("Initialized state with tx:", tx);
+    console.log("Created vault with tx:", tx);
 
     // Fetch the state account and assert that initialization is correct.
     const stateAccount = await program.account.state.fetch(statePDA);
     assert.ok(new BN(stateAccount.totalStaked).eq(new BN(0)));
     assert.strictEqual(stateAccount.allowedDeltaThreshold.toNumber(), 100);
+    assert.ok(stateAccount.baseMint.equals(baseMint));
+    assert.ok(stateAccount.shareMint.equals(shareMintPDA));
   });
 });